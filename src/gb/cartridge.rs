@@ -2,48 +2,129 @@ use std::path::Path;
 use std::io::Read;
 
 use crate::gb::error::RomParseError;
-use crate::gb::header::GbHeader;
+use crate::gb::header::{Controller, GbHeader, LogoCheck};
+use crate::gb::mbc::{RAM_BANK_SIZE, ROM_BANK_SIZE};
+use crate::gb::mbc::camera::CAMERA_RAM_SIZE;
+use crate::gb::mbc::huc3::HUC3_FOOTER_SIZE;
+use crate::gb::mbc::mbc6::{FLASH_SIZE, MBC6_RAM_SIZE};
+use crate::gb::mbc::rtc::{RTC_FOOTER_SIZE, RTC_FOOTER_SIZE_32};
+use crate::gb::mbc::tama5::TAMA5_FOOTER_SIZE;
+use crate::save::SaveRam;
+use crate::validation::{LoadPolicy, Problem, ValidationReport};
 
 
 pub struct Cartridge {
     pub gb_header: GbHeader,
+    /// The whole ROM image, header included, as mapped from $0000 upward. Up to 0.1 this
+    /// held only the bytes after the header, starting at offset 0x150.
     pub rom_data: Vec<u8>,
+    /// Problems found while loading
+    pub report: ValidationReport,
 }
 
 impl Cartridge {
     pub fn load_rom_file(path: impl AsRef<Path>) -> Result<Self, RomParseError> {
-        let bytes = std::fs::read(path)?;
-        let header = GbHeader::from_bytes(&bytes)?;
+        Self::load_rom_file_with_policy(path, LoadPolicy::Strict)
+    }
 
-        let rom_data = bytes[0x150..].to_vec();
-        // most of the information in the header does not matter on real hardware
-        // (the ROM’s size is determined only by the capacity of the ROM chip in the cartridge, not the header byte)
-        let bank_size = 16 * 1024; // 16KB banks
-        if bytes.len() % bank_size != 0 {
-            return Err(RomParseError::InvalidRomSize);
-        }
+    pub fn load_rom_file_with_policy(path: impl AsRef<Path>, policy: LoadPolicy) -> Result<Self, RomParseError> {
+        Self::load_rom_file_with_logo_check(path, policy, LogoCheck::Dmg)
+    }
 
-        Ok(Cartridge {
-            gb_header: header,
-            rom_data,
-        })
+    /// Load a cartridge whose logo only has to pass `check`, such as an unlicensed game
+    pub fn load_rom_file_with_logo_check(
+        path: impl AsRef<Path>,
+        policy: LoadPolicy,
+        check: LogoCheck,
+    ) -> Result<Self, RomParseError> {
+        let bytes = std::fs::read(path)?;
+        Self::from_bytes(bytes, policy, check)
     }
 
     pub fn load_rom_data<R: Read>(data: &mut R) -> Result<Self, RomParseError> {
+        Self::load_rom_data_with_policy(data, LoadPolicy::Strict)
+    }
+
+    pub fn load_rom_data_with_policy<R: Read>(data: &mut R, policy: LoadPolicy) -> Result<Self, RomParseError> {
+        Self::load_rom_data_with_logo_check(data, policy, LogoCheck::Dmg)
+    }
+
+    /// Load a cartridge whose logo only has to pass `check`, such as an unlicensed game
+    pub fn load_rom_data_with_logo_check<R: Read>(
+        data: &mut R,
+        policy: LoadPolicy,
+        check: LogoCheck,
+    ) -> Result<Self, RomParseError> {
         let mut bytes = Vec::new();
         data.read_to_end(&mut bytes)?;
+        Self::from_bytes(bytes, policy, check)
+    }
+
+    fn from_bytes(mut bytes: Vec<u8>, policy: LoadPolicy, check: LogoCheck) -> Result<Self, RomParseError> {
+        let (header, mut report) = GbHeader::from_bytes_with_logo_check(&bytes, policy, check)?;
 
-        let header = GbHeader::from_bytes(&bytes)?;
+        // the MMM01 menu's header covers the menu only
+        if header.capabilities().controller != Controller::Mmm01 {
+            let computed = bytes
+                .iter()
+                .enumerate()
+                .filter(|&(offset, _)| offset != 0x14E && offset != 0x14F)
+                .fold(0u16, |sum, (_, &byte)| sum.wrapping_add(byte as u16));
+            if computed != header.global_checksum {
+                report.warn(0x14E, Problem::ChecksumMismatch { stored: header.global_checksum, computed });
+            }
+        }
 
-        let rom_data = bytes[0x150..].to_vec();
-        let bank_size = 16 * 1024; // 16KB banks
-        if rom_data.len() % bank_size != 0 {
-            return Err(RomParseError::InvalidRomSize);
+        // most of the information in the header does not matter on real hardware
+        // (the ROM’s size is determined only by the capacity of the ROM chip in the cartridge, not the header byte)
+        let size = bytes.len();
+        if !size.is_multiple_of(ROM_BANK_SIZE) {
+            let problem = Problem::PartialBank { size, bank_size: ROM_BANK_SIZE };
+            report.error(policy, size, problem, RomParseError::InvalidRomSize)?;
+            // unprogrammed ROM reads as 0xFF
+            bytes.resize(size.next_multiple_of(ROM_BANK_SIZE), 0xFF);
+        }
+        let expected = header.rom_size as usize;
+        if size < expected {
+            report.warn(size, Problem::Underdump { expected, found: size });
+        } else if size > expected {
+            report.warn(expected, Problem::Overdump { expected, found: size });
         }
 
         Ok(Cartridge {
             gb_header: header,
-            rom_data,
+            rom_data: bytes,
+            report,
         })
     }
-}
\ No newline at end of file
+
+    /// Battery-backed memory to keep in a `.sav` file, laid out like the controller's
+    /// `save_data`, or `None` without a battery.
+    ///
+    /// MBC2 and MBC7 memory is built in, so its size comes from the controller rather than
+    /// the header; MBC6 saves its flash after the RAM. MBC3 timer cartridges accept the
+    /// BGB/VBA-M RTC footer after the RAM, HuC3 and TAMA5 cartridges their own RTC footers.
+    pub fn save_ram(&self) -> Option<SaveRam> {
+        let header = &self.gb_header;
+        let capabilities = header.capabilities();
+        if !capabilities.battery {
+            return None;
+        }
+        let ram = match capabilities.controller {
+            // without banking only the first 8 KiB can be reached
+            Controller::None => (header.ram_size as usize).min(RAM_BANK_SIZE),
+            _ if capabilities.internal_ram > 0 => capabilities.internal_ram,
+            _ => header.ram_size as usize,
+        };
+        match capabilities.controller {
+            Controller::Mbc3 if capabilities.rtc => {
+                Some(SaveRam::with_footer(ram, &[RTC_FOOTER_SIZE, RTC_FOOTER_SIZE_32]))
+            }
+            Controller::Mbc6 => Some(SaveRam::new(MBC6_RAM_SIZE + FLASH_SIZE)),
+            Controller::PocketCamera => Some(SaveRam::new(CAMERA_RAM_SIZE)),
+            Controller::HuC3 => Some(SaveRam::with_footer(ram, &[HUC3_FOOTER_SIZE])),
+            Controller::Tama5 => Some(SaveRam::with_footer(ram, &[TAMA5_FOOTER_SIZE])),
+            _ => (ram > 0).then(|| SaveRam::new(ram)),
+        }
+    }
+}
@@ -11,8 +11,24 @@ pub enum RomParseError {
     InvalidCartridgeType,
     #[error("invalid header checksum")]
     InvalidHeaderChecksum,
+    #[error("unknown ROM size code {0:#04X}")]
+    UnknownRomSizeCode(u8),
+    #[error("unknown RAM size code {0:#04X}")]
+    UnknownRamSizeCode(u8),
+    #[error("ROM size {size} is more than the controller can address ({limit})")]
+    RomSizeExceedsController { size: u32, limit: u32 },
+    #[error("RAM size {size} is more than the controller can address ({limit})")]
+    RamSizeExceedsController { size: u32, limit: u32 },
     #[error("invalid ROM size")]
     InvalidRomSize,
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
 }
+
+
+/// Errors reading photos out of a Pocket Camera save
+#[derive(Error, Debug)]
+pub enum PhotoError {
+    #[error("camera save is {found} bytes, expected at least {expected}")]
+    SaveTooSmall { found: usize, expected: usize },
+}
@@ -1,19 +1,135 @@
 use bitfield_struct::bitfield;
 
 use crate::gb::error::RomParseError;
+use crate::gb::licensee::{new_licensee_name, old_licensee_name, USE_NEW_LICENSEE};
+use crate::validation::{LoadPolicy, Problem, ValidationReport};
+use crate::gb::mbc::eeprom::EEPROM_93LC56_SIZE;
+use crate::gb::mbc::mbc2::MBC2_RAM_SIZE;
+use crate::gb::mbc::tama5::TAMA5_RAM_SIZE;
 
 
 /// Entry point and Nintendo logo from 0x104-0x133
-const GB_LOGO: &[u8; 48] = &[
+pub(crate) const GB_LOGO: &[u8; 48] = &[
     0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
     0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
     0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
 ];
 
+/// Bytes of the logo the CGB boot ROM compares
+const CGB_LOGO_CHECK_LEN: usize = 0x18;
+
+/// Size of the MMM01 menu at the end of the ROM, which holds the cartridge's real header
+const MMM01_MENU_SIZE: usize = 0x8000;
+
+/// Layout of the title area at 0x134-0x143, which changed as the header gained fields
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HeaderFormat {
-    GB,   // Original Game Boy
-    GBC,  // Game Boy Color
+    /// 16-character title up to 0x143 (original Game Boy games)
+    Dmg,
+    /// 15-character title, CGB flag at 0x143
+    Cgb,
+    /// 11-character title, manufacturer code at 0x13F-0x142, CGB flag at 0x143
+    CgbManufacturer,
+}
+
+/// How much of the Nintendo logo at 0x104-0x133 a load requires
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogoCheck {
+    /// The whole logo, as the DMG boot ROM compares it
+    #[default]
+    Dmg,
+    /// Only the top half, as the CGB boot ROM compares it
+    Cgb,
+    /// Any logo the boot ROM can be made to accept through a known `LogoScheme`
+    Unlicensed,
+}
+
+/// How a cartridge gets past the boot ROM's logo check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogoScheme {
+    /// The Nintendo logo, as in licensed games
+    Nintendo,
+    /// Only the top half matches, which passes on a CGB but locks up a DMG
+    TopHalf,
+    /// Sachen's mapper starts locked, forcing address line A7 high and swapping A0/A6 and
+    /// A1/A4, so the boot ROM checks a scrambled Nintendo logo at 0x184-0x1B3 while the
+    /// game's own logo at 0x104 is what the dump shows
+    Sachen,
+    /// The Nintendo logo is elsewhere in the first bank, for cartridges that show their own
+    /// logo while the boot ROM draws it and switch in Nintendo's for the comparison read
+    /// that follows (used by several unlicensed publishers)
+    SwitchedLogo { offset: usize },
+    /// No known scheme; the cartridge does not boot on real hardware
+    Unknown,
+}
+
+impl LogoScheme {
+    /// Identify the scheme from the first bank of the ROM
+    pub fn identify(rom: &[u8]) -> Self {
+        let logo = &rom[0x104..0x134];
+        if logo == GB_LOGO {
+            return LogoScheme::Nintendo;
+        }
+        if logo[..CGB_LOGO_CHECK_LEN] == GB_LOGO[..CGB_LOGO_CHECK_LEN] {
+            return LogoScheme::TopHalf;
+        }
+        if rom.len() >= 0x200 {
+            let scrambled = (0x104..0x134).map(|addr| rom[sachen_locked_address(addr)]);
+            if scrambled.eq(GB_LOGO.iter().copied()) {
+                return LogoScheme::Sachen;
+            }
+        }
+        let bank = &rom[..rom.len().min(0x4000)];
+        match bank.windows(GB_LOGO.len()).position(|window| window == GB_LOGO) {
+            Some(offset) => LogoScheme::SwitchedLogo { offset },
+            None => LogoScheme::Unknown,
+        }
+    }
+
+    /// True if the boot ROM behind `check` accepts the cartridge
+    pub fn passes(self, check: LogoCheck) -> bool {
+        matches!(
+            (self, check),
+            (LogoScheme::Nintendo, _)
+                | (LogoScheme::TopHalf, LogoCheck::Cgb | LogoCheck::Unlicensed)
+                | (LogoScheme::Sachen | LogoScheme::SwitchedLogo { .. }, LogoCheck::Unlicensed)
+        )
+    }
+}
+
+/// Address a Sachen mapper reads while locked: A7 forced high, A0/A6 and A1/A4 swapped
+fn sachen_locked_address(addr: usize) -> usize {
+    let addr = addr | 0x80;
+    let bit = |n: usize| (addr >> n) & 1;
+    (addr & !0x53) | bit(0) << 6 | bit(6) | bit(1) << 4 | bit(4) << 1
+}
+
+/// How a game runs on a Game Boy Color, from the CGB flag at 0x143
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CgbMode {
+    /// No CGB flag: monochrome game, colorized by the CGB boot ROM
+    Dmg,
+    /// 0x80: runs on both, with color on the CGB
+    Compatible,
+    /// 0xC0: runs only on the CGB
+    Only,
+    /// Bit 7 with bit 2 or 3 set: the CGB boot ROM enters the undocumented PGB mode, which
+    /// drives an external LCD
+    Pgb,
+}
+
+impl CgbMode {
+    pub fn from_bits(value: u8) -> Self {
+        if value & 0x80 == 0 {
+            CgbMode::Dmg
+        } else if value & 0x0C != 0 {
+            CgbMode::Pgb
+        } else if value & 0x40 != 0 {
+            CgbMode::Only
+        } else {
+            CgbMode::Compatible
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -90,6 +206,310 @@ impl CartridgeType {
     }
 }
 
+/// Memory bank controller family of a cartridge type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Controller {
+    /// No controller: 32 KiB of ROM, optionally 8 KiB of RAM
+    None,
+    Mbc1,
+    Mbc2,
+    Mmm01,
+    Mbc3,
+    Mbc5,
+    Mbc6,
+    Mbc7,
+    PocketCamera,
+    Tama5,
+    HuC1,
+    HuC3,
+}
+
+impl Controller {
+    /// Largest ROM the controller can bank in
+    pub fn max_rom_size(self) -> u32 {
+        const MIB: u32 = 1024 * 1024;
+        match self {
+            Controller::None => 32 * 1024,
+            Controller::Mbc2 => 256 * 1024,
+            Controller::Tama5 => 512 * 1024,
+            Controller::Mbc6 | Controller::PocketCamera | Controller::HuC1 => MIB,
+            Controller::Mbc1 | Controller::Mbc7 | Controller::HuC3 => 2 * MIB,
+            // MBC30, the MBC3 of Pocket Monsters Crystal (Japan), has an eighth bank bit
+            Controller::Mbc3 => 4 * MIB,
+            Controller::Mbc5 | Controller::Mmm01 => 8 * MIB,
+        }
+    }
+
+    /// Largest external RAM the controller can bank in; RAM inside the controller is not
+    /// external, so the header should state none
+    pub fn max_ram_size(self) -> u32 {
+        match self {
+            Controller::Mbc2 | Controller::Mbc7 | Controller::Tama5 => 0,
+            Controller::None => 8 * 1024,
+            Controller::Mbc1 | Controller::Mbc6 | Controller::HuC1 | Controller::HuC3 => 32 * 1024,
+            // MBC30 again, with a third RAM bank bit
+            Controller::Mbc3 => 64 * 1024,
+            Controller::Mbc5 | Controller::Mmm01 | Controller::PocketCamera => 128 * 1024,
+        }
+    }
+}
+
+/// Hardware a cartridge type carries, for deciding what to emulate and save
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CartridgeCapabilities {
+    pub controller: Controller,
+    /// RAM (or EEPROM) the game can write, external or built into the controller
+    pub ram: bool,
+    /// Bytes of memory inside the controller, which the header's RAM size does not count:
+    /// the MBC2's 512 half-bytes, the TAMA5's 32 bytes and the MBC7's 256-byte EEPROM
+    pub internal_ram: usize,
+    pub battery: bool,
+    pub rtc: bool,
+    pub rumble: bool,
+    pub accelerometer: bool,
+    pub camera: bool,
+    pub infrared: bool,
+}
+
+impl CartridgeCapabilities {
+    const fn new(controller: Controller) -> Self {
+        Self {
+            controller,
+            ram: false,
+            internal_ram: 0,
+            battery: false,
+            rtc: false,
+            rumble: false,
+            accelerometer: false,
+            camera: false,
+            infrared: false,
+        }
+    }
+}
+
+impl CartridgeType {
+    /// What the cartridge type implies about the board.
+    ///
+    /// HuC3, TAMA5, MBC6 and Pocket Camera boards all carry a battery although their type
+    /// names do not mention one.
+    pub fn capabilities(self) -> CartridgeCapabilities {
+        use CartridgeType::*;
+        let base = CartridgeCapabilities::new;
+        match self {
+            RomOnly => base(Controller::None),
+            RomRam => CartridgeCapabilities { ram: true, ..base(Controller::None) },
+            RomRamBattery => CartridgeCapabilities { ram: true, battery: true, ..base(Controller::None) },
+            MBC1 => base(Controller::Mbc1),
+            MBC1Ram => CartridgeCapabilities { ram: true, ..base(Controller::Mbc1) },
+            MBC1RamBattery => CartridgeCapabilities { ram: true, battery: true, ..base(Controller::Mbc1) },
+            MBC2 => CartridgeCapabilities {
+                ram: true,
+                internal_ram: MBC2_RAM_SIZE,
+                ..base(Controller::Mbc2)
+            },
+            MBC2Battery => CartridgeCapabilities {
+                ram: true,
+                internal_ram: MBC2_RAM_SIZE,
+                battery: true,
+                ..base(Controller::Mbc2)
+            },
+            MMM01 => base(Controller::Mmm01),
+            MMM01Ram => CartridgeCapabilities { ram: true, ..base(Controller::Mmm01) },
+            MMM01RamBattery => CartridgeCapabilities { ram: true, battery: true, ..base(Controller::Mmm01) },
+            MBC3TimerBattery => CartridgeCapabilities { battery: true, rtc: true, ..base(Controller::Mbc3) },
+            MBC3TimerRamBattery => CartridgeCapabilities {
+                ram: true,
+                battery: true,
+                rtc: true,
+                ..base(Controller::Mbc3)
+            },
+            MBC3 => base(Controller::Mbc3),
+            MBC3Ram => CartridgeCapabilities { ram: true, ..base(Controller::Mbc3) },
+            MBC3RamBattery => CartridgeCapabilities { ram: true, battery: true, ..base(Controller::Mbc3) },
+            MBC5 => base(Controller::Mbc5),
+            MBC5Ram => CartridgeCapabilities { ram: true, ..base(Controller::Mbc5) },
+            MBC5RamBattery => CartridgeCapabilities { ram: true, battery: true, ..base(Controller::Mbc5) },
+            MBC5Rumble => CartridgeCapabilities { rumble: true, ..base(Controller::Mbc5) },
+            MBC5RumbleRam => CartridgeCapabilities { ram: true, rumble: true, ..base(Controller::Mbc5) },
+            MBC5RumbleRamBattery => CartridgeCapabilities {
+                ram: true,
+                battery: true,
+                rumble: true,
+                ..base(Controller::Mbc5)
+            },
+            MBC6 => CartridgeCapabilities { ram: true, battery: true, ..base(Controller::Mbc6) },
+            MBC7SensorRumbleRamBattery => CartridgeCapabilities {
+                ram: true,
+                internal_ram: EEPROM_93LC56_SIZE,
+                battery: true,
+                rumble: true,
+                accelerometer: true,
+                ..base(Controller::Mbc7)
+            },
+            PocketCamera => CartridgeCapabilities {
+                ram: true,
+                battery: true,
+                camera: true,
+                ..base(Controller::PocketCamera)
+            },
+            BandaiTAMA5 => CartridgeCapabilities {
+                ram: true,
+                internal_ram: TAMA5_RAM_SIZE,
+                battery: true,
+                rtc: true,
+                ..base(Controller::Tama5)
+            },
+            HuC3 => CartridgeCapabilities {
+                ram: true,
+                battery: true,
+                rtc: true,
+                infrared: true,
+                ..base(Controller::HuC3)
+            },
+            HuC1RamBattery => CartridgeCapabilities {
+                ram: true,
+                battery: true,
+                infrared: true,
+                ..base(Controller::HuC1)
+            },
+        }
+    }
+}
+
+/// Extra hardware announced by the first letter of a product code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProductHardware {
+    /// A or B: no extra hardware
+    Standard,
+    /// H: infrared port use
+    Infrared,
+    /// K: tilt sensor
+    Tilt,
+    /// V: rumble motor
+    Rumble,
+}
+
+impl ProductHardware {
+    pub fn from_letter(letter: u8) -> Option<Self> {
+        match letter {
+            b'A' | b'B' => Some(ProductHardware::Standard),
+            b'H' => Some(ProductHardware::Infrared),
+            b'K' => Some(ProductHardware::Tilt),
+            b'V' => Some(ProductHardware::Rumble),
+            _ => None,
+        }
+    }
+}
+
+/// Market a game was released for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Region {
+    Japan,
+    Usa,
+    /// English or multi-language European release (P, X, Y, Z)
+    Europe,
+    Germany,
+    France,
+    Italy,
+    Spain,
+    Netherlands,
+    Australia,
+    Korea,
+    China,
+    /// Destination code 0x01 without a product code to narrow it down
+    Overseas,
+}
+
+impl Region {
+    /// Region for the last letter of a product code
+    pub fn from_letter(letter: u8) -> Option<Self> {
+        match letter {
+            b'J' => Some(Region::Japan),
+            b'E' => Some(Region::Usa),
+            b'P' | b'X' | b'Y' | b'Z' => Some(Region::Europe),
+            b'D' => Some(Region::Germany),
+            b'F' => Some(Region::France),
+            b'I' => Some(Region::Italy),
+            b'S' => Some(Region::Spain),
+            b'H' => Some(Region::Netherlands),
+            b'U' => Some(Region::Australia),
+            b'K' => Some(Region::Korea),
+            b'C' => Some(Region::China),
+            _ => None,
+        }
+    }
+}
+
+/// 4-character product code from the manufacturer code field, e.g. `AAUE`: hardware letter,
+/// two-character game ID, region letter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProductCode {
+    pub hardware: ProductHardware,
+    pub game: String,
+    pub region: Region,
+}
+
+impl ProductCode {
+    /// Parse four upper-case letters or digits, `None` if they do not form a product code
+    pub fn from_bytes(code: &[u8]) -> Option<Self> {
+        let [hardware, game @ .., region] = code else {
+            return None;
+        };
+        if game.len() != 2 || !code.iter().all(|byte| byte.is_ascii_uppercase() || byte.is_ascii_digit()) {
+            return None;
+        }
+        Some(Self {
+            hardware: ProductHardware::from_letter(*hardware)?,
+            game: String::from_utf8_lossy(game).into_owned(),
+            region: Region::from_letter(*region)?,
+        })
+    }
+}
+
+/// Guess the title area layout. A set bit 7 at 0x143 can only be the CGB flag, since titles
+/// are ASCII. The manufacturer code is only told apart from the end of a title by its shape:
+/// four upper-case letters or digits starting with a hardware letter and ending with a region
+/// letter, in a cartridge using the new licensee code as all games with one do.
+fn header_format(bytes: &[u8]) -> HeaderFormat {
+    if bytes[0x143] & 0x80 == 0 {
+        return HeaderFormat::Dmg;
+    }
+    if ProductCode::from_bytes(&bytes[0x13F..0x143]).is_some() && bytes[0x14B] == USE_NEW_LICENSEE {
+        HeaderFormat::CgbManufacturer
+    } else {
+        HeaderFormat::Cgb
+    }
+}
+
+/// ROM size in bytes for the header byte at 0x148, `None` for codes no cartridge uses
+pub fn rom_size_from_code(code: u8) -> Option<u32> {
+    const BANK: u32 = 16 * 1024;
+    match code {
+        // 32 KiB << code, up to 8 MiB
+        0x00..=0x08 => Some((32 * 1024) << code),
+        // codes from early documentation for boards with ROM chips of two sizes, which no
+        // known cartridge uses but some tools still write
+        0x52 => Some(72 * BANK),
+        0x53 => Some(80 * BANK),
+        0x54 => Some(96 * BANK),
+        _ => None,
+    }
+}
+
+/// RAM size in bytes for the header byte at 0x149, `None` for unknown codes
+pub fn ram_size_from_code(code: u8) -> Option<u32> {
+    match code {
+        0x00 => Some(0),
+        // listed in some documentation but never used by a released cartridge
+        0x01 => Some(2 * 1024),
+        0x02 => Some(8 * 1024),
+        0x03 => Some(32 * 1024),
+        0x04 => Some(128 * 1024),
+        0x05 => Some(64 * 1024),
+        _ => None,
+    }
+}
+
 #[bitfield(u8)]
 pub struct GbcFlags {
     #[bits(7)]
@@ -109,13 +529,22 @@ pub struct GbHeader {
     /// Entry point (usually 0x00 0xC3 0x50 0x01)
     pub entry_point: [u8; 4],
     
-    /// Nintendo logo bitmap (must match known pattern)
+    /// Logo bitmap at 0x104, as dumped. `LogoCheck::Dmg` requires all 48 bytes to be
+    /// Nintendo's, `LogoCheck::Cgb` only the first 24, and `LogoCheck::Unlicensed` accepts any
+    /// bitmap whose `logo_scheme` is not `Unknown`; a lenient load reports a failed check
+    /// instead of stopping.
     pub nintendo_logo: [u8; 48],
     
+    /// How the cartridge passes the boot ROM's logo check
+    pub logo_scheme: LogoScheme,
+    
+    /// Layout of the title area
+    pub format: HeaderFormat,
+    
     /// Game title (upper-case ASCII)
     pub title: String,
     
-    /// Manufacturer code (if newer game) or title continuation (if older game)
+    /// 4-character manufacturer code, only in the `CgbManufacturer` layout
     pub manufacturer_code: Option<String>,
     
     /// GBC support flags
@@ -136,6 +565,12 @@ pub struct GbHeader {
     /// RAM size in bytes
     pub ram_size: u32,
     
+    /// ROM size code at 0x148
+    pub rom_size_code: u8,
+    
+    /// RAM size code at 0x149
+    pub ram_size_code: u8,
+    
     /// Destination code (0x00 = Japan, 0x01 = Non-Japan)
     pub destination: u8,
     
@@ -155,41 +590,73 @@ pub struct GbHeader {
 impl GbHeader {
     /// Parse a Game Boy ROM header from bytes
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, RomParseError> {
+        Self::from_bytes_with_policy(bytes, LoadPolicy::Strict).map(|(header, _)| header)
+    }
+
+    /// Parse a header and report what is wrong with it. A lenient parse accepts a bad header
+    /// checksum and sizes the controller cannot address, takes an unknown ROM size code to
+    /// mean the size of `bytes` and an unknown RAM size code to mean no RAM.
+    pub fn from_bytes_with_policy(
+        bytes: &[u8],
+        policy: LoadPolicy,
+    ) -> Result<(Self, ValidationReport), RomParseError> {
+        Self::from_bytes_with_logo_check(bytes, policy, LogoCheck::Dmg)
+    }
+
+    /// Parse a header, accepting the logos `check` allows. A logo that gets past the check
+    /// any other way than Nintendo's own is reported as a warning.
+    pub fn from_bytes_with_logo_check(
+        bytes: &[u8],
+        policy: LoadPolicy,
+        check: LogoCheck,
+    ) -> Result<(Self, ValidationReport), RomParseError> {
+        let mut report = ValidationReport::new();
+        // MMM01 multicarts boot into a menu in the last 32 KiB, whose header describes the
+        // cartridge; the header at $100 belongs to the first game
+        let mut base = 0;
+        if bytes.len() > MMM01_MENU_SIZE {
+            let menu = &bytes[bytes.len() - MMM01_MENU_SIZE..];
+            if &menu[0x104..0x134] == GB_LOGO && (0x0B..=0x0D).contains(&menu[0x147]) {
+                base = bytes.len() - MMM01_MENU_SIZE;
+            }
+        }
+        let header = Self::parse(&bytes[base..], base, policy, check, &mut report)?;
+        Ok((header, report))
+    }
+
+    /// Parse the header at the start of `bytes`, which is at `base` in the file
+    fn parse(
+        bytes: &[u8],
+        base: usize,
+        policy: LoadPolicy,
+        check: LogoCheck,
+        report: &mut ValidationReport,
+    ) -> Result<Self, RomParseError> {
         if bytes.len() < 0x150 {
             return Err(RomParseError::HeaderTooShort);
         }
 
-        // Verify Nintendo logo
-        if &bytes[0x104..0x134] != GB_LOGO {
-            return Err(RomParseError::InvalidLogo);
+        let logo_scheme = LogoScheme::identify(bytes);
+        if !logo_scheme.passes(check) {
+            report.error(policy, base + 0x104, Problem::LogoMismatch, RomParseError::InvalidLogo)?;
+        } else if logo_scheme != LogoScheme::Nintendo {
+            report.warn(base + 0x104, Problem::LogoMismatch);
         }
 
-        // Parse title and manufacturer code
-        let mut title = String::new();
-        let mut manufacturer_code = None;
-        let title_end = if bytes[0x143] == 0x80 || bytes[0x143] == 0xC0 {
-            // If GBC flag is present, title ends at 0x13F
-            0x13F
-        } else {
-            // Otherwise title can extend to 0x143
-            0x143
+        let format = header_format(bytes);
+        let title_end = match format {
+            HeaderFormat::Dmg => 0x144,
+            HeaderFormat::Cgb => 0x143,
+            HeaderFormat::CgbManufacturer => 0x13F,
         };
-
-        // Convert title bytes to string, stopping at first 0x00 or end
-        for &byte in &bytes[0x134..=title_end] {
-            if byte == 0 {
-                break;
-            }
-            title.push(byte as char);
-        }
-
-        // If title is short enough, remaining bytes are manufacturer code
-        if title_end == 0x13F {
-            let mfg = String::from_utf8_lossy(&bytes[0x13F..0x143]).to_string();
-            if !mfg.trim().is_empty() {
-                manufacturer_code = Some(mfg);
-            }
-        }
+        // the title is padded with zeros
+        let title = bytes[0x134..title_end]
+            .iter()
+            .take_while(|&&byte| byte != 0)
+            .map(|&byte| byte as char)
+            .collect();
+        let manufacturer_code = (format == HeaderFormat::CgbManufacturer)
+            .then(|| String::from_utf8_lossy(&bytes[0x13F..0x143]).into_owned());
 
         // Parse GBC and SGB flags
         let gbc_flags = GbcFlags::from_bits(bytes[0x143]);
@@ -199,36 +666,59 @@ impl GbHeader {
         let cart_type = CartridgeType::from_bits(bytes[0x147])
             .ok_or(RomParseError::InvalidCartridgeType)?;
 
-        // Calculate ROM size (32KB << shift)
-        let rom_shift = bytes[0x148];
-        let rom_size = 32 * 1024 * (1 << rom_shift);
-
-        // Calculate RAM size
-        let ram_size = match bytes[0x149] {
-            0x00 => 0,          // No RAM
-            0x01 => 2 * 1024,   // 2 KB
-            0x02 => 8 * 1024,   // 8 KB
-            0x03 => 32 * 1024,  // 32 KB
-            0x04 => 128 * 1024, // 128 KB
-            0x05 => 64 * 1024,  // 64 KB
-            _ => 0,             // Unknown value, assume no RAM
+        let rom_size_code = bytes[0x148];
+        let rom_size = match rom_size_from_code(rom_size_code) {
+            Some(size) => size,
+            None => {
+                let error = RomParseError::UnknownRomSizeCode(rom_size_code);
+                report.error(policy, base + 0x148, Problem::UnknownCode(rom_size_code), error)?;
+                // the hardware only knows the size of the ROM chip
+                (base + bytes.len()) as u32
+            }
+        };
+        let ram_size_code = bytes[0x149];
+        let ram_size = match ram_size_from_code(ram_size_code) {
+            Some(size) => size,
+            None => {
+                let error = RomParseError::UnknownRamSizeCode(ram_size_code);
+                report.error(policy, base + 0x149, Problem::UnknownCode(ram_size_code), error)?;
+                0
+            }
         };
 
+        // sizes the controller cannot address mean a wrong cartridge type or size code
+        let capabilities = cart_type.capabilities();
+        let limit = capabilities.controller.max_rom_size();
+        if rom_size > limit {
+            let problem = Problem::SizeExceedsHardware { size: rom_size, limit };
+            let error = RomParseError::RomSizeExceedsController { size: rom_size, limit };
+            report.error(policy, base + 0x148, problem, error)?;
+        }
+        let limit = if capabilities.ram { capabilities.controller.max_ram_size() } else { 0 };
+        if ram_size > limit {
+            let problem = Problem::SizeExceedsHardware { size: ram_size, limit };
+            let error = RomParseError::RamSizeExceedsController { size: ram_size, limit };
+            report.error(policy, base + 0x149, problem, error)?;
+        }
+
         // Verify header checksum
         let mut checksum: u8 = 0;
         for &byte in &bytes[0x134..0x14D] {
             checksum = checksum.wrapping_sub(byte).wrapping_sub(1);
         }
         if checksum != bytes[0x14D] {
-            return Err(RomParseError::InvalidHeaderChecksum);
+            let problem = Problem::ChecksumMismatch { stored: bytes[0x14D] as u16, computed: checksum as u16 };
+            report.error(policy, base + 0x14D, problem, RomParseError::InvalidHeaderChecksum)?;
         }
 
-        // Parse global checksum (verify only if requested)
+        // the boot ROM ignores the global checksum; `Cartridge` checks it against the whole ROM
         let global_checksum = u16::from_be_bytes([bytes[0x14E], bytes[0x14F]]);
 
         Ok(Self {
             entry_point: bytes[0x100..0x104].try_into().unwrap(),
             nintendo_logo: bytes[0x104..0x134].try_into().unwrap(),
+            logo_scheme,
+            format,
             title,
             manufacturer_code,
             gbc_flags,
@@ -237,6 +727,8 @@ impl GbHeader {
             cartridge_type: cart_type,
             rom_size,
             ram_size,
+            rom_size_code,
+            ram_size_code,
             destination: bytes[0x14A],
             old_licensee_code: bytes[0x14B],
             version: bytes[0x14C],
@@ -250,26 +742,59 @@ impl GbHeader {
         self.gbc_flags.gbc_support()
     }
 
+    /// How the game runs on a Game Boy Color
+    pub fn cgb_mode(&self) -> CgbMode {
+        match self.format {
+            HeaderFormat::Dmg => CgbMode::Dmg,
+            _ => CgbMode::from_bits(self.gbc_flags.into_bits()),
+        }
+    }
+
     /// Returns true if this game supports Super Game Boy features
     pub fn is_sgb(&self) -> bool {
-        self.sgb_flags.sgb_support()
+        // the SGB BIOS only enables its features for exactly 0x03, and ignores the flag
+        // unless the old licensee code is 0x33
+        self.sgb_flags.into_bits() == 0x03 && self.old_licensee_code == USE_NEW_LICENSEE
+    }
+
+    /// Publisher named by the licensee code, following the old code 0x33 to the new one
+    pub fn publisher(&self) -> Option<&'static str> {
+        if self.old_licensee_code == USE_NEW_LICENSEE {
+            new_licensee_name(self.new_licensee_code)
+        } else {
+            old_licensee_name(self.old_licensee_code)
+        }
+    }
+
+    /// Hardware implied by the cartridge type
+    pub fn capabilities(&self) -> CartridgeCapabilities {
+        self.cartridge_type.capabilities()
     }
 
     /// Returns true if this cartridge has battery-backed RAM
     pub fn has_battery(&self) -> bool {
-        use CartridgeType::*;
-        matches!(
-            self.cartridge_type,
-            MBC1RamBattery | MBC2Battery | RomRamBattery | MMM01RamBattery |
-            MBC3TimerBattery | MBC3TimerRamBattery | MBC3RamBattery |
-            MBC5RamBattery | MBC5RumbleRamBattery | MBC7SensorRumbleRamBattery |
-            HuC1RamBattery
-        )
+        self.capabilities().battery
     }
 
     /// Returns true if this cartridge has RAM (battery-backed or not)
     pub fn has_ram(&self) -> bool {
-        self.ram_size > 0
+        // RAM inside the controller is not counted in the header's RAM size
+        self.ram_size > 0 || self.capabilities().internal_ram > 0
+    }
+
+    /// Product code from the manufacturer code field, for cartridges that have one
+    pub fn product_code(&self) -> Option<ProductCode> {
+        ProductCode::from_bytes(self.manufacturer_code.as_ref()?.as_bytes())
+    }
+
+    /// Release region: the product code's region letter when there is one, otherwise just
+    /// Japan or overseas from the destination code
+    pub fn region(&self) -> Region {
+        match self.product_code() {
+            Some(code) => code.region,
+            None if self.is_japanese() => Region::Japan,
+            None => Region::Overseas,
+        }
     }
 
     /// Returns true if this is a Japanese game
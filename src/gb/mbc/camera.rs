@@ -0,0 +1,147 @@
+use crate::gb::cartridge::Cartridge;
+use crate::gb::mbc::{load_ram, ram_offset, rom_offset, Mbc};
+
+
+/// Width of the picture the sensor delivers, in pixels
+pub const SENSOR_WIDTH: usize = 128;
+/// Height of the picture the sensor delivers, in pixels (the M64282FP's visible rows)
+pub const SENSOR_HEIGHT: usize = 112;
+
+/// Battery-backed RAM the photo album is kept in
+pub const CAMERA_RAM_SIZE: usize = 128 * 1024;
+const REGISTER_COUNT: usize = 0x36;
+/// Captured pictures land in RAM bank 0 from $A100, as 16x14 2bpp tiles
+const CAPTURE_OFFSET: usize = 0x100;
+/// Exposure register value the sensor input is taken to be calibrated for
+const NOMINAL_EXPOSURE: u32 = 0x300;
+
+/// Host input for the Pocket Camera's image sensor
+pub trait ImageSensor {
+    /// Grayscale picture of `SENSOR_WIDTH` x `SENSOR_HEIGHT` pixels, row-major, 0 = black.
+    /// Missing pixels read as white.
+    fn frame(&mut self) -> Vec<u8>;
+}
+
+/// Nintendo MAC-GBD (`PocketCamera`): 128 KiB of RAM, and the M64282FP sensor's registers
+/// mapped at $A000-$A035 when RAM bank bit 4 is set.
+///
+/// Captures complete immediately. The exposure time scales the input and the 4x4 dither
+/// matrix quantises it to 2 bits; gain and edge enhancement are not modelled.
+pub struct PocketCamera {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    sensor: Option<Box<dyn ImageSensor>>,
+
+    ram_write_enabled: bool,
+    rom_bank: u8,
+    /// $4000-$5FFF: bits 0-3 select the RAM bank, bit 4 the camera registers
+    ram_select: u8,
+    registers: [u8; REGISTER_COUNT],
+}
+
+impl PocketCamera {
+    pub fn new(cartridge: &Cartridge) -> Self {
+        Self {
+            rom: cartridge.rom_data.clone(),
+            ram: vec![0; CAMERA_RAM_SIZE],
+            sensor: None,
+            ram_write_enabled: false,
+            rom_bank: 1,
+            ram_select: 0,
+            registers: [0; REGISTER_COUNT],
+        }
+    }
+
+    fn registers_selected(&self) -> bool {
+        self.ram_select & 0x10 != 0
+    }
+
+    fn capture(&mut self) {
+        let frame = match &mut self.sensor {
+            Some(sensor) => sensor.frame(),
+            None => Vec::new(),
+        };
+        let exposure = u16::from_be_bytes([self.registers[2], self.registers[3]]) as u32;
+
+        for y in 0..SENSOR_HEIGHT {
+            for x in 0..SENSOR_WIDTH {
+                let pixel = frame.get(y * SENSOR_WIDTH + x).copied().unwrap_or(0xFF) as u32;
+                let value = (pixel * exposure / NOMINAL_EXPOSURE).min(0xFF) as u8;
+
+                let thresholds = 6 + ((y % 4) * 4 + x % 4) * 3;
+                let thresholds = &self.registers[thresholds..thresholds + 3];
+                let color = match value {
+                    value if value < thresholds[0] => 3,
+                    value if value < thresholds[1] => 2,
+                    value if value < thresholds[2] => 1,
+                    _ => 0,
+                };
+
+                let tile = (y / 8) * (SENSOR_WIDTH / 8) + x / 8;
+                let offset = CAPTURE_OFFSET + tile * 16 + (y % 8) * 2;
+                let bit = 0x80 >> (x % 8);
+                for (plane, byte) in self.ram[offset..offset + 2].iter_mut().enumerate() {
+                    if color >> plane & 1 != 0 {
+                        *byte |= bit;
+                    } else {
+                        *byte &= !bit;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Mbc for PocketCamera {
+    fn read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x3FFF => self.rom.get(addr as usize).copied().unwrap_or(0xFF),
+            // bank 0 can be mapped at $4000
+            0x4000..=0x7FFF if !self.rom.is_empty() => {
+                self.rom[rom_offset(&self.rom, self.rom_bank as usize, addr)]
+            }
+            // only the control register reads back, with the busy flag clear
+            0xA000..=0xBFFF if self.registers_selected() => match addr & 0x7F {
+                0x00 => self.registers[0] & 0x06,
+                _ => 0x00,
+            },
+            // RAM is readable even while writes are disabled
+            0xA000..=0xBFFF => self.ram[ram_offset(&self.ram, self.ram_select as usize & 0x0F, addr)],
+            _ => 0xFF,
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x1FFF => self.ram_write_enabled = value & 0x0F == 0x0A,
+            0x2000..=0x3FFF => self.rom_bank = value & 0x3F,
+            0x4000..=0x5FFF => self.ram_select = value & 0x1F,
+            0xA000..=0xBFFF if self.registers_selected() => {
+                let register = (addr & 0x7F) as usize;
+                if register < REGISTER_COUNT {
+                    self.registers[register] = value;
+                }
+                if register == 0 && value & 1 != 0 {
+                    self.capture();
+                }
+            }
+            0xA000..=0xBFFF if self.ram_write_enabled => {
+                let offset = ram_offset(&self.ram, self.ram_select as usize & 0x0F, addr);
+                self.ram[offset] = value;
+            }
+            _ => {}
+        }
+    }
+
+    fn set_image_sensor(&mut self, sensor: Box<dyn ImageSensor>) {
+        self.sensor = Some(sensor);
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        Some(self.ram.clone())
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        load_ram(&mut self.ram, data);
+    }
+}
@@ -0,0 +1,166 @@
+/// 93LC56 size in bytes, organised as 128 16-bit words
+pub const EEPROM_93LC56_SIZE: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EepromState {
+    /// Waiting for a start bit
+    Idle,
+    /// Shifting in the 2-bit opcode and 8-bit address
+    Command { bits: u16, count: u8 },
+    /// Shifting out a word, sequential reads continue with the next address
+    Read { address: u8, bits: u16, count: u8 },
+    /// Shifting in the data word of WRITE or WRAL
+    Write { address: u8, all: bool, bits: u16, count: u8 },
+    /// Command finished, ignoring clocks until CS drops
+    Done,
+}
+
+/// Microchip 93LC56 serial EEPROM in 16-bit mode, driven through its CS, CLK and DI pins
+#[derive(Debug, Clone)]
+pub struct Eeprom93Lc56 {
+    data: Vec<u8>,
+    state: EepromState,
+    write_enabled: bool,
+    cs: bool,
+    clk: bool,
+    di: bool,
+    /// Level on DO (true = ready / 1 bit)
+    output: bool,
+}
+
+impl Default for Eeprom93Lc56 {
+    fn default() -> Self {
+        Self {
+            data: vec![0xFF; EEPROM_93LC56_SIZE],
+            state: EepromState::Idle,
+            write_enabled: false,
+            cs: false,
+            clk: false,
+            di: false,
+            output: true,
+        }
+    }
+}
+
+impl Eeprom93Lc56 {
+    /// Contents, each word stored little-endian
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Replace the contents, truncating or padding with $FF to the chip size
+    pub fn load(&mut self, data: &[u8]) {
+        let len = data.len().min(self.data.len());
+        self.data[..len].copy_from_slice(&data[..len]);
+        self.data[len..].fill(0xFF);
+    }
+
+    /// The pins as last driven, in the MBC7 register layout: CS (bit 7), CLK (6), DI (1), DO (0)
+    pub fn pins(&self) -> u8 {
+        (self.cs as u8) << 7 | (self.clk as u8) << 6 | (self.di as u8) << 1 | self.output as u8
+    }
+
+    /// Update CS, CLK and DI as driven by the host
+    pub fn write_pins(&mut self, cs: bool, clk: bool, di: bool) {
+        if !cs {
+            self.state = EepromState::Idle;
+            self.output = true;
+        } else if !self.clk && clk {
+            self.rising_edge(di);
+        }
+        self.cs = cs;
+        self.clk = clk;
+        self.di = di;
+    }
+
+    fn word(&self, address: u8) -> u16 {
+        let index = (address as usize & 0x7F) * 2;
+        u16::from_le_bytes([self.data[index], self.data[index + 1]])
+    }
+
+    fn set_word(&mut self, address: u8, value: u16) {
+        let index = (address as usize & 0x7F) * 2;
+        self.data[index..index + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn rising_edge(&mut self, di: bool) {
+        let bit = di as u16;
+        self.state = match self.state {
+            EepromState::Idle if di => EepromState::Command { bits: 0, count: 0 },
+            EepromState::Idle => EepromState::Idle,
+            EepromState::Command { bits, count } => {
+                let bits = (bits << 1) | bit;
+                if count + 1 < 10 {
+                    EepromState::Command { bits, count: count + 1 }
+                } else {
+                    self.command(bits)
+                }
+            }
+            EepromState::Read { address, bits, count } => {
+                self.output = bits & 0x8000 != 0;
+                if count + 1 < 16 {
+                    EepromState::Read { address, bits: bits << 1, count: count + 1 }
+                } else {
+                    let address = address.wrapping_add(1) & 0x7F;
+                    EepromState::Read { address, bits: self.word(address), count: 0 }
+                }
+            }
+            EepromState::Write { address, all, bits, count } => {
+                let bits = (bits << 1) | bit;
+                if count + 1 < 16 {
+                    EepromState::Write { address, all, bits, count: count + 1 }
+                } else {
+                    if self.write_enabled {
+                        if all {
+                            for address in 0..0x80 {
+                                self.set_word(address, bits);
+                            }
+                        } else {
+                            self.set_word(address, bits);
+                        }
+                    }
+                    self.output = true;
+                    EepromState::Done
+                }
+            }
+            EepromState::Done => EepromState::Done,
+        };
+    }
+
+    fn command(&mut self, bits: u16) -> EepromState {
+        let address = bits as u8;
+        match bits >> 8 {
+            0b10 => {
+                // a dummy 0 precedes the data
+                self.output = false;
+                EepromState::Read { address, bits: self.word(address), count: 0 }
+            }
+            0b01 => EepromState::Write { address, all: false, bits: 0, count: 0 },
+            0b11 => {
+                if self.write_enabled {
+                    self.set_word(address, 0xFFFF);
+                }
+                self.output = true;
+                EepromState::Done
+            }
+            _ => match address >> 6 {
+                0b11 => {
+                    self.write_enabled = true;
+                    EepromState::Done
+                }
+                0b00 => {
+                    self.write_enabled = false;
+                    EepromState::Done
+                }
+                0b10 => {
+                    if self.write_enabled {
+                        self.data.fill(0xFF);
+                    }
+                    self.output = true;
+                    EepromState::Done
+                }
+                _ => EepromState::Write { address: 0, all: true, bits: 0, count: 0 },
+            },
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use crate::gb::cartridge::Cartridge;
+use crate::gb::mbc::{external_ram, load_ram, ram_offset, rom_offset, save_ram, Mbc};
+
+
+/// Hudson HuC1 (`HuC1RamBattery`), with an infrared LED and receiver in place of the RAM
+/// when selected
+pub struct HuC1 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    battery: bool,
+
+    /// $0000-$1FFF = $0E maps the IR port at $A000-$BFFF, anything else maps RAM
+    ir_mode: bool,
+    rom_bank: u8,
+    ram_bank: u8,
+    ir_led: bool,
+    ir_input: bool,
+}
+
+impl HuC1 {
+    pub fn new(cartridge: &Cartridge) -> Self {
+        Self {
+            rom: cartridge.rom_data.clone(),
+            ram: external_ram(&cartridge.gb_header),
+            battery: cartridge.gb_header.has_battery(),
+            ir_mode: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            ir_led: false,
+            ir_input: false,
+        }
+    }
+}
+
+impl Mbc for HuC1 {
+    fn read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x3FFF => self.rom.get(addr as usize).copied().unwrap_or(0xFF),
+            // bank 0 is not remapped to 1 as on the MBC1
+            0x4000..=0x7FFF if !self.rom.is_empty() => {
+                self.rom[rom_offset(&self.rom, self.rom_bank as usize, addr)]
+            }
+            0xA000..=0xBFFF if self.ir_mode => 0xC0 | self.ir_input as u8,
+            0xA000..=0xBFFF if !self.ram.is_empty() => {
+                self.ram[ram_offset(&self.ram, self.ram_bank as usize, addr)]
+            }
+            _ => 0xFF,
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x1FFF => self.ir_mode = value & 0x0F == 0x0E,
+            0x2000..=0x3FFF => self.rom_bank = value & 0x3F,
+            0x4000..=0x5FFF => self.ram_bank = value & 0x03,
+            0xA000..=0xBFFF if self.ir_mode => self.ir_led = value & 1 != 0,
+            0xA000..=0xBFFF if !self.ram.is_empty() => {
+                let offset = ram_offset(&self.ram, self.ram_bank as usize, addr);
+                self.ram[offset] = value;
+            }
+            _ => {}
+        }
+    }
+
+    fn ir_led(&self) -> bool {
+        self.ir_led
+    }
+
+    fn set_ir_input(&mut self, light: bool) {
+        self.ir_input = light;
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        save_ram(self.battery, &self.ram)
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        load_ram(&mut self.ram, data);
+    }
+}
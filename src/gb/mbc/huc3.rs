@@ -0,0 +1,227 @@
+use crate::gb::cartridge::Cartridge;
+use crate::gb::mbc::rtc::system_time;
+use crate::gb::mbc::{external_ram, load_ram, ram_offset, rom_offset, Mbc};
+
+
+/// Size of the RTC footer appended to HuC3 saves
+pub const HUC3_FOOTER_SIZE: usize = 16 + 128;
+
+const MINUTES_PER_DAY: u64 = 24 * 60;
+/// The day counter is 12 bits wide
+const DAYS_WRAP: u64 = 0x1000;
+
+/// What $A000-$BFFF is connected to, selected through $0000-$1FFF
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HuC3Mode {
+    /// $00: RAM, read-only
+    RamReadOnly,
+    /// $0A: RAM
+    Ram,
+    /// $0B: RTC command input
+    RtcCommand,
+    /// $0C: RTC response output
+    RtcResponse,
+    /// $0D: RTC semaphore
+    RtcSemaphore,
+    /// $0E: infrared port
+    Infrared,
+    Disabled,
+}
+
+/// Hudson HuC3 (`HuC3`): MBC with an RTC chip driven through a nibble command protocol,
+/// and an infrared port.
+///
+/// The RTC counts minutes within the day (12 bits) and days (12 bits). Command `$60` copies
+/// the time into the first six nibbles of its scratch memory, `$61` sets it from there.
+pub struct HuC3 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    battery: bool,
+    clock: Box<dyn Fn() -> u64>,
+
+    mode: HuC3Mode,
+    rom_bank: u8,
+    ram_bank: u8,
+
+    /// 256 nibbles of RTC scratch memory
+    rtc_memory: [u8; 256],
+    rtc_address: u8,
+    /// Last command and its 4-bit result
+    rtc_command: u8,
+    rtc_response: u8,
+    /// Seconds since midnight of day 0 at `rtc_base`
+    rtc_seconds: u64,
+    /// Host time `rtc_seconds` was last valid at
+    rtc_base: u64,
+
+    ir_led: bool,
+    ir_input: bool,
+}
+
+impl HuC3 {
+    pub fn new(cartridge: &Cartridge) -> Self {
+        Self::with_clock(cartridge, system_time)
+    }
+
+    /// Create the controller with a custom time source for the RTC
+    pub fn with_clock(cartridge: &Cartridge, clock: impl Fn() -> u64 + 'static) -> Self {
+        Self {
+            rom: cartridge.rom_data.clone(),
+            ram: external_ram(&cartridge.gb_header),
+            battery: cartridge.gb_header.has_battery(),
+            rtc_base: clock(),
+            clock: Box::new(clock),
+            mode: HuC3Mode::Disabled,
+            rom_bank: 1,
+            ram_bank: 0,
+            rtc_memory: [0; 256],
+            rtc_address: 0,
+            rtc_command: 0,
+            rtc_response: 0,
+            rtc_seconds: 0,
+            ir_led: false,
+            ir_input: false,
+        }
+    }
+
+    /// Minutes within the day and days counted by the RTC
+    pub fn rtc_time(&self) -> (u16, u16) {
+        let seconds = self.rtc_seconds + (self.clock)().saturating_sub(self.rtc_base);
+        let minutes = seconds / 60;
+        ((minutes % MINUTES_PER_DAY) as u16, (minutes / MINUTES_PER_DAY % DAYS_WRAP) as u16)
+    }
+
+    fn set_rtc_time(&mut self, minutes: u16, days: u16) {
+        self.rtc_seconds = (days as u64 * MINUTES_PER_DAY + minutes as u64 % MINUTES_PER_DAY) * 60;
+        self.rtc_base = (self.clock)();
+    }
+
+    fn rtc_write(&mut self, value: u8) {
+        let argument = value & 0x0F;
+        self.rtc_command = (value >> 4) & 0x07;
+        match self.rtc_command {
+            0x1 => {
+                self.rtc_response = self.rtc_memory[self.rtc_address as usize];
+                self.rtc_address = self.rtc_address.wrapping_add(1);
+            }
+            0x3 => {
+                self.rtc_memory[self.rtc_address as usize] = argument;
+                self.rtc_address = self.rtc_address.wrapping_add(1);
+            }
+            0x4 => self.rtc_address = (self.rtc_address & 0xF0) | argument,
+            0x5 => self.rtc_address = (self.rtc_address & 0x0F) | (argument << 4),
+            0x6 => match argument {
+                0x0 => {
+                    let (minutes, days) = self.rtc_time();
+                    for nibble in 0..3 {
+                        self.rtc_memory[nibble] = (minutes >> (nibble * 4)) as u8 & 0x0F;
+                        self.rtc_memory[3 + nibble] = (days >> (nibble * 4)) as u8 & 0x0F;
+                    }
+                }
+                0x1 => {
+                    let value = |start: usize| {
+                        (0..3).fold(0u16, |acc, nibble| {
+                            acc | (self.rtc_memory[start + nibble] as u16) << (nibble * 4)
+                        })
+                    };
+                    let (minutes, days) = (value(0), value(3));
+                    self.set_rtc_time(minutes, days);
+                }
+                // status query: the clock is running
+                0x2 => self.rtc_response = 0x1,
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+}
+
+impl Mbc for HuC3 {
+    fn read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x3FFF => self.rom.get(addr as usize).copied().unwrap_or(0xFF),
+            0x4000..=0x7FFF if !self.rom.is_empty() => {
+                self.rom[rom_offset(&self.rom, self.rom_bank as usize, addr)]
+            }
+            0xA000..=0xBFFF => match self.mode {
+                HuC3Mode::Ram | HuC3Mode::RamReadOnly if !self.ram.is_empty() => {
+                    self.ram[ram_offset(&self.ram, self.ram_bank as usize, addr)]
+                }
+                HuC3Mode::RtcResponse => 0x80 | (self.rtc_command << 4) | self.rtc_response,
+                // commands complete immediately, so the chip is always ready
+                HuC3Mode::RtcSemaphore => 0x01,
+                HuC3Mode::Infrared => 0xC0 | self.ir_input as u8,
+                _ => 0xFF,
+            },
+            _ => 0xFF,
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x1FFF => {
+                self.mode = match value & 0x0F {
+                    0x00 => HuC3Mode::RamReadOnly,
+                    0x0A => HuC3Mode::Ram,
+                    0x0B => HuC3Mode::RtcCommand,
+                    0x0C => HuC3Mode::RtcResponse,
+                    0x0D => HuC3Mode::RtcSemaphore,
+                    0x0E => HuC3Mode::Infrared,
+                    _ => HuC3Mode::Disabled,
+                }
+            }
+            0x2000..=0x3FFF => self.rom_bank = value & 0x7F,
+            0x4000..=0x5FFF => self.ram_bank = value & 0x03,
+            0xA000..=0xBFFF => match self.mode {
+                HuC3Mode::Ram if !self.ram.is_empty() => {
+                    let offset = ram_offset(&self.ram, self.ram_bank as usize, addr);
+                    self.ram[offset] = value;
+                }
+                HuC3Mode::RtcCommand => self.rtc_write(value),
+                HuC3Mode::Infrared => self.ir_led = value & 1 != 0,
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    fn ir_led(&self) -> bool {
+        self.ir_led
+    }
+
+    fn set_ir_input(&mut self, light: bool) {
+        self.ir_input = light;
+    }
+
+    /// External RAM followed by the RTC footer: the clock in seconds and the host time it was
+    /// valid at (64-bit little-endian), then the scratch memory two nibbles per byte, low first
+    fn save_data(&self) -> Option<Vec<u8>> {
+        if !self.battery {
+            return None;
+        }
+        let mut data = self.ram.clone();
+        data.extend_from_slice(&self.rtc_seconds.to_le_bytes());
+        data.extend_from_slice(&self.rtc_base.to_le_bytes());
+        data.extend(self.rtc_memory.chunks(2).map(|pair| pair[0] | pair[1] << 4));
+        Some(data)
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        load_ram(&mut self.ram, data);
+        // a save without a footer keeps the fresh clock
+        let footer = match data.len().checked_sub(self.ram.len()) {
+            Some(HUC3_FOOTER_SIZE) => &data[self.ram.len()..],
+            _ => return,
+        };
+        let seconds = u64::from_le_bytes(footer[0..8].try_into().unwrap());
+        let saved_at = u64::from_le_bytes(footer[8..16].try_into().unwrap());
+        // catch up with the time spent powered off
+        let now = (self.clock)();
+        self.rtc_seconds = seconds + now.saturating_sub(saved_at);
+        self.rtc_base = now;
+        for (index, byte) in footer[16..].iter().enumerate() {
+            self.rtc_memory[index * 2] = byte & 0x0F;
+            self.rtc_memory[index * 2 + 1] = byte >> 4;
+        }
+    }
+}
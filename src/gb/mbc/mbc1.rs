@@ -0,0 +1,112 @@
+use crate::gb::cartridge::Cartridge;
+use crate::gb::header::GB_LOGO;
+use crate::gb::mbc::{external_ram, load_ram, ram_offset, rom_offset, save_ram, Mbc};
+
+
+/// Size of the MBC1M multicarts, four 256 KiB games
+const MULTICART_ROM_SIZE: usize = 1024 * 1024;
+
+/// True for MBC1M multicart ROMs (Bomberman Collection, Mortal Kombat I & II, ...).
+///
+/// These wire the upper bank bits one line lower, so every game occupies 16 banks and has
+/// its own header; the copy of the Nintendo logo at the start of bank $10 gives them away.
+pub fn is_mbc1m(rom: &[u8]) -> bool {
+    let logo = 0x10 * 0x4000 + 0x104;
+    rom.len() == MULTICART_ROM_SIZE && &rom[logo..logo + GB_LOGO.len()] == GB_LOGO
+}
+
+/// Nintendo MBC1 (`MBC1`, `MBC1Ram`, `MBC1RamBattery`), including the MBC1M multicart wiring
+pub struct Mbc1 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    battery: bool,
+    /// MBC1M: BANK2 drives ROM A18-A19 and BANK1 bit 4 is not connected
+    multicart: bool,
+
+    /// $0000-$1FFF
+    ram_enabled: bool,
+    /// $2000-$3FFF, 5 bits
+    bank1: u8,
+    /// $4000-$5FFF, 2 bits
+    bank2: u8,
+    /// $6000-$7FFF: BANK2 also applies to $0000-$3FFF and to RAM
+    advanced_mode: bool,
+}
+
+impl Mbc1 {
+    pub fn new(cartridge: &Cartridge) -> Self {
+        Self {
+            rom: cartridge.rom_data.clone(),
+            ram: external_ram(&cartridge.gb_header),
+            battery: cartridge.gb_header.has_battery(),
+            multicart: is_mbc1m(&cartridge.rom_data),
+            ram_enabled: false,
+            bank1: 1,
+            bank2: 0,
+            advanced_mode: false,
+        }
+    }
+
+    /// True when the ROM was detected as an MBC1M multicart
+    pub fn is_multicart(&self) -> bool {
+        self.multicart
+    }
+
+    fn bank2_shift(&self) -> u8 {
+        if self.multicart { 4 } else { 5 }
+    }
+
+    fn rom_bank(&self, addr: u16) -> usize {
+        let upper = (self.bank2 as usize) << self.bank2_shift();
+        match addr {
+            0x0000..=0x3FFF if self.advanced_mode => upper,
+            0x0000..=0x3FFF => 0,
+            _ => {
+                // the zero check looks at all five bits, even where bit 4 is not wired
+                let bank1 = self.bank1.max(1) as usize;
+                let mask = (1 << self.bank2_shift()) - 1;
+                upper | (bank1 & mask)
+            }
+        }
+    }
+
+    fn ram_bank(&self) -> usize {
+        if self.advanced_mode { self.bank2 as usize } else { 0 }
+    }
+}
+
+impl Mbc for Mbc1 {
+    fn read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x7FFF if !self.rom.is_empty() => {
+                self.rom[rom_offset(&self.rom, self.rom_bank(addr), addr)]
+            }
+            0xA000..=0xBFFF if self.ram_enabled && !self.ram.is_empty() => {
+                self.ram[ram_offset(&self.ram, self.ram_bank(), addr)]
+            }
+            _ => 0xFF,
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x1FFF => self.ram_enabled = value & 0x0F == 0x0A,
+            0x2000..=0x3FFF => self.bank1 = value & 0x1F,
+            0x4000..=0x5FFF => self.bank2 = value & 0x03,
+            0x6000..=0x7FFF => self.advanced_mode = value & 0x01 != 0,
+            0xA000..=0xBFFF if self.ram_enabled && !self.ram.is_empty() => {
+                let offset = ram_offset(&self.ram, self.ram_bank(), addr);
+                self.ram[offset] = value;
+            }
+            _ => {}
+        }
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        save_ram(self.battery, &self.ram)
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        load_ram(&mut self.ram, data);
+    }
+}
@@ -0,0 +1,67 @@
+use crate::gb::cartridge::Cartridge;
+use crate::gb::mbc::{load_ram, rom_offset, save_ram, Mbc};
+
+
+/// The MBC2's built-in RAM: 512 4-bit cells
+pub const MBC2_RAM_SIZE: usize = 512;
+
+/// Nintendo MBC2 (`MBC2`, `MBC2Battery`), with its RAM inside the controller
+pub struct Mbc2 {
+    rom: Vec<u8>,
+    /// One byte per cell, only the low nibble is stored
+    ram: Vec<u8>,
+    battery: bool,
+
+    ram_enabled: bool,
+    /// 4-bit ROM bank
+    rom_bank: u8,
+}
+
+impl Mbc2 {
+    pub fn new(cartridge: &Cartridge) -> Self {
+        Self {
+            rom: cartridge.rom_data.clone(),
+            ram: vec![0; MBC2_RAM_SIZE],
+            battery: cartridge.gb_header.has_battery(),
+            ram_enabled: false,
+            rom_bank: 1,
+        }
+    }
+}
+
+impl Mbc for Mbc2 {
+    fn read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x3FFF => self.rom.get(addr as usize).copied().unwrap_or(0xFF),
+            0x4000..=0x7FFF if !self.rom.is_empty() => {
+                self.rom[rom_offset(&self.rom, self.rom_bank.max(1) as usize, addr)]
+            }
+            // only A0-A8 are decoded, and the upper nibble is open bus
+            0xA000..=0xBFFF if self.ram_enabled => self.ram[addr as usize % MBC2_RAM_SIZE] | 0xF0,
+            _ => 0xFF,
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            // A8 selects between the RAM enable and ROM bank registers
+            0x0000..=0x3FFF if addr & 0x0100 == 0 => self.ram_enabled = value & 0x0F == 0x0A,
+            0x0000..=0x3FFF => self.rom_bank = value & 0x0F,
+            0xA000..=0xBFFF if self.ram_enabled => {
+                self.ram[addr as usize % MBC2_RAM_SIZE] = value & 0x0F;
+            }
+            _ => {}
+        }
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        save_ram(self.battery, &self.ram)
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        load_ram(&mut self.ram, data);
+        for cell in &mut self.ram {
+            *cell &= 0x0F;
+        }
+    }
+}
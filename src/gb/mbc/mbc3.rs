@@ -0,0 +1,131 @@
+use crate::gb::cartridge::Cartridge;
+use crate::gb::header::CartridgeType;
+use crate::gb::mbc::rtc::{system_time, Rtc, RTC_FOOTER_SIZE, RTC_FOOTER_SIZE_32};
+use crate::gb::mbc::{external_ram, load_ram, ram_offset, rom_offset, save_ram, Mbc};
+
+
+/// Nintendo MBC3, with the real-time clock on the `MBC3Timer*` types.
+///
+/// The ROM bank register is kept at 8 bits so MBC30 boards (Pocket Monsters Crystal,
+/// 4 MiB ROM, 8 RAM banks) work too; on a 2 MiB MBC3 bit 7 simply wraps.
+pub struct Mbc3 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    battery: bool,
+    rtc: Option<Rtc>,
+    /// Host time source for the RTC, in Unix seconds
+    clock: Box<dyn Fn() -> u64>,
+
+    /// $0000-$1FFF: RAM and RTC access
+    ram_enabled: bool,
+    /// $2000-$3FFF
+    rom_bank: u8,
+    /// $4000-$5FFF: RAM bank ($00-$07) or RTC register ($08-$0C)
+    ram_select: u8,
+}
+
+impl Mbc3 {
+    pub fn new(cartridge: &Cartridge) -> Self {
+        Self::with_clock(cartridge, system_time)
+    }
+
+    /// Create the controller with a custom time source for the RTC
+    pub fn with_clock(cartridge: &Cartridge, clock: impl Fn() -> u64 + 'static) -> Self {
+        let header = &cartridge.gb_header;
+        let has_rtc = matches!(
+            header.cartridge_type,
+            CartridgeType::MBC3TimerBattery | CartridgeType::MBC3TimerRamBattery
+        );
+
+        Self {
+            rom: cartridge.rom_data.clone(),
+            ram: external_ram(header),
+            battery: header.has_battery(),
+            rtc: has_rtc.then(|| Rtc::new(clock())),
+            clock: Box::new(clock),
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_select: 0,
+        }
+    }
+
+    /// The real-time clock, on cartridges that have one
+    pub fn rtc(&self) -> Option<&Rtc> {
+        self.rtc.as_ref()
+    }
+
+    fn rtc_register(&self) -> Option<usize> {
+        match self.ram_select {
+            0x08..=0x0C => Some(self.ram_select as usize - 0x08),
+            _ => None,
+        }
+    }
+}
+
+impl Mbc for Mbc3 {
+    fn read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x3FFF => self.rom.get(addr as usize).copied().unwrap_or(0xFF),
+            0x4000..=0x7FFF if !self.rom.is_empty() => {
+                self.rom[rom_offset(&self.rom, self.rom_bank.max(1) as usize, addr)]
+            }
+            0xA000..=0xBFFF if self.ram_enabled => match (self.rtc_register(), &self.rtc) {
+                (Some(register), Some(rtc)) => rtc.read(register),
+                (None, _) if !self.ram.is_empty() => {
+                    self.ram[ram_offset(&self.ram, self.ram_select as usize & 7, addr)]
+                }
+                _ => 0xFF,
+            },
+            _ => 0xFF,
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x1FFF => self.ram_enabled = value & 0x0F == 0x0A,
+            0x2000..=0x3FFF => self.rom_bank = value,
+            0x4000..=0x5FFF => self.ram_select = value & 0x0F,
+            0x6000..=0x7FFF => {
+                if let Some(rtc) = &mut self.rtc {
+                    rtc.write_latch(value, (self.clock)());
+                }
+            }
+            0xA000..=0xBFFF if self.ram_enabled => match (self.rtc_register(), &mut self.rtc) {
+                (Some(register), Some(rtc)) => rtc.write(register, value, (self.clock)()),
+                (None, _) if !self.ram.is_empty() => {
+                    let offset = ram_offset(&self.ram, self.ram_select as usize & 7, addr);
+                    self.ram[offset] = value;
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    /// External RAM followed by the 48-byte RTC footer on cartridges with a clock
+    fn save_data(&self) -> Option<Vec<u8>> {
+        let mut data = save_ram(self.battery, &self.ram).unwrap_or_default();
+        if let Some(rtc) = &self.rtc {
+            let mut rtc = rtc.clone();
+            rtc.sync((self.clock)());
+            data.extend_from_slice(&rtc.to_footer());
+        }
+        (self.battery && !data.is_empty()).then_some(data)
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        load_ram(&mut self.ram, data);
+        let Some(rtc) = &mut self.rtc else {
+            return;
+        };
+        // a save without a footer (or from an emulator without RTC support) keeps the fresh clock
+        let footer = match data.len().checked_sub(self.ram.len()) {
+            Some(len @ (RTC_FOOTER_SIZE | RTC_FOOTER_SIZE_32)) => &data[data.len() - len..],
+            _ => return,
+        };
+        if let Some(mut saved) = Rtc::from_footer(footer) {
+            saved.sync((self.clock)());
+            *rtc = saved;
+        }
+    }
+}
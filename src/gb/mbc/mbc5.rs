@@ -0,0 +1,107 @@
+use crate::gb::cartridge::Cartridge;
+use crate::gb::header::CartridgeType;
+use crate::gb::mbc::{external_ram, load_ram, ram_offset, rom_offset, save_ram, Mbc, RumbleCallback};
+
+
+/// Nintendo MBC5, including the `MBC5Rumble*` types where RAM bank bit 3 drives the motor
+pub struct Mbc5 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    battery: bool,
+    has_rumble: bool,
+    rumble_callback: Option<RumbleCallback>,
+
+    /// $0000-$1FFF
+    ram_enabled: bool,
+    /// $2000-$2FFF low 8 bits, $3000-$3FFF bit 8
+    rom_bank: u16,
+    /// $4000-$5FFF
+    ram_bank: u8,
+    rumble: bool,
+}
+
+impl Mbc5 {
+    pub fn new(cartridge: &Cartridge) -> Self {
+        let header = &cartridge.gb_header;
+        let has_rumble = matches!(
+            header.cartridge_type,
+            CartridgeType::MBC5Rumble
+                | CartridgeType::MBC5RumbleRam
+                | CartridgeType::MBC5RumbleRamBattery
+        );
+
+        Self {
+            rom: cartridge.rom_data.clone(),
+            ram: external_ram(header),
+            battery: header.has_battery(),
+            has_rumble,
+            rumble_callback: None,
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            rumble: false,
+        }
+    }
+
+    fn write_ram_bank(&mut self, value: u8) {
+        if !self.has_rumble {
+            self.ram_bank = value & 0x0F;
+            return;
+        }
+        // the motor takes the place of RAM A16, so only 8 banks remain addressable
+        self.ram_bank = value & 0x07;
+        let rumble = value & 0x08 != 0;
+        if rumble != self.rumble {
+            self.rumble = rumble;
+            if let Some(callback) = &mut self.rumble_callback {
+                callback(rumble);
+            }
+        }
+    }
+}
+
+impl Mbc for Mbc5 {
+    fn read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x3FFF => self.rom.get(addr as usize).copied().unwrap_or(0xFF),
+            0x4000..=0x7FFF if !self.rom.is_empty() => {
+                self.rom[rom_offset(&self.rom, self.rom_bank as usize, addr)]
+            }
+            0xA000..=0xBFFF if self.ram_enabled && !self.ram.is_empty() => {
+                self.ram[ram_offset(&self.ram, self.ram_bank as usize, addr)]
+            }
+            _ => 0xFF,
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            // unlike the MBC1, all eight bits are compared
+            0x0000..=0x1FFF => self.ram_enabled = value == 0x0A,
+            0x2000..=0x2FFF => self.rom_bank = (self.rom_bank & 0x100) | value as u16,
+            0x3000..=0x3FFF => self.rom_bank = (self.rom_bank & 0xFF) | ((value as u16 & 1) << 8),
+            0x4000..=0x5FFF => self.write_ram_bank(value),
+            0xA000..=0xBFFF if self.ram_enabled && !self.ram.is_empty() => {
+                let offset = ram_offset(&self.ram, self.ram_bank as usize, addr);
+                self.ram[offset] = value;
+            }
+            _ => {}
+        }
+    }
+
+    fn rumble(&self) -> bool {
+        self.rumble
+    }
+
+    fn set_rumble_callback(&mut self, callback: RumbleCallback) {
+        self.rumble_callback = Some(callback);
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        save_ram(self.battery, &self.ram)
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        load_ram(&mut self.ram, data);
+    }
+}
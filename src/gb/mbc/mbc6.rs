@@ -0,0 +1,183 @@
+use crate::gb::cartridge::Cartridge;
+use crate::gb::mbc::{load_ram, Mbc};
+
+
+const BANK_SIZE: usize = 0x2000;
+const RAM_BANK_SIZE: usize = 0x1000;
+/// Battery-backed RAM on the board
+pub const MBC6_RAM_SIZE: usize = 32 * 1024;
+/// The MX29F008 flash chip on the Net de Get cartridge
+pub const FLASH_SIZE: usize = 1024 * 1024;
+const FLASH_SECTOR_SIZE: usize = 128 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FlashState {
+    Ready,
+    /// Received $AA at $5555
+    Unlock1,
+    /// Received $55 at $2AAA, waiting for a command byte
+    Unlock2,
+    /// Erase command ($80) received, waiting for its own unlock sequence
+    EraseSetup,
+    EraseUnlock1,
+    EraseUnlock2,
+    /// The next write programs a byte
+    Program,
+    /// Reads return the manufacturer and device IDs
+    Identify,
+}
+
+/// Nintendo MBC6 (`MBC6`): two independently switched 8 KiB ROM/flash windows at
+/// $4000-$5FFF and $6000-$7FFF, and two 4 KiB RAM windows at $A000-$AFFF and $B000-$BFFF
+pub struct Mbc6 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    flash: Vec<u8>,
+
+    ram_enabled: bool,
+    ram_banks: [u8; 2],
+    /// $0C00-$0FFF bit 0
+    flash_enabled: bool,
+    /// $1000 bit 0
+    flash_write_enabled: bool,
+    rom_banks: [u8; 2],
+    /// $2800/$3800: the window shows flash instead of ROM
+    flash_selected: [bool; 2],
+    flash_state: FlashState,
+}
+
+impl Mbc6 {
+    pub fn new(cartridge: &Cartridge) -> Self {
+        Self {
+            rom: cartridge.rom_data.clone(),
+            ram: vec![0; MBC6_RAM_SIZE],
+            flash: vec![0xFF; FLASH_SIZE],
+            ram_enabled: false,
+            ram_banks: [0; 2],
+            flash_enabled: false,
+            flash_write_enabled: false,
+            rom_banks: [0; 2],
+            flash_selected: [false; 2],
+            flash_state: FlashState::Ready,
+        }
+    }
+
+    /// Contents of the flash chip
+    pub fn flash(&self) -> &[u8] {
+        &self.flash
+    }
+
+    fn window(addr: u16) -> usize {
+        (addr as usize - 0x4000) / BANK_SIZE
+    }
+
+    fn window_offset(&self, window: usize, addr: u16, size: usize) -> usize {
+        (self.rom_banks[window] as usize * BANK_SIZE + (addr as usize % BANK_SIZE)) % size
+    }
+
+    fn ram_offset(&self, addr: u16) -> usize {
+        let window = (addr as usize - 0xA000) / RAM_BANK_SIZE;
+        (self.ram_banks[window] as usize * RAM_BANK_SIZE + (addr as usize % RAM_BANK_SIZE)) % MBC6_RAM_SIZE
+    }
+
+    fn read_flash(&self, offset: usize) -> u8 {
+        match self.flash_state {
+            // Macronix manufacturer ID, then the MX29F008 device ID
+            FlashState::Identify if offset & 1 == 0 => 0xC2,
+            FlashState::Identify => 0x81,
+            _ => self.flash[offset],
+        }
+    }
+
+    fn write_flash(&mut self, offset: usize, value: u8) {
+        // commands are recognised on the low 15 address bits of the chip
+        let command_addr = offset & 0x7FFF;
+        self.flash_state = match (self.flash_state, command_addr, value) {
+            (_, _, 0xF0) => FlashState::Ready,
+            (FlashState::Ready | FlashState::Identify, 0x5555, 0xAA) => FlashState::Unlock1,
+            (FlashState::Unlock1, 0x2AAA, 0x55) => FlashState::Unlock2,
+            (FlashState::Unlock2, 0x5555, 0x80) => FlashState::EraseSetup,
+            (FlashState::Unlock2, 0x5555, 0xA0) => FlashState::Program,
+            (FlashState::Unlock2, 0x5555, 0x90) => FlashState::Identify,
+            (FlashState::EraseSetup, 0x5555, 0xAA) => FlashState::EraseUnlock1,
+            (FlashState::EraseUnlock1, 0x2AAA, 0x55) => FlashState::EraseUnlock2,
+            (FlashState::EraseUnlock2, 0x5555, 0x10) => {
+                self.flash.fill(0xFF);
+                FlashState::Ready
+            }
+            (FlashState::EraseUnlock2, _, 0x30) => {
+                let sector = offset / FLASH_SECTOR_SIZE * FLASH_SECTOR_SIZE;
+                self.flash[sector..sector + FLASH_SECTOR_SIZE].fill(0xFF);
+                FlashState::Ready
+            }
+            (FlashState::Program, _, value) => {
+                // programming can only clear bits
+                self.flash[offset] &= value;
+                FlashState::Ready
+            }
+            (FlashState::Identify, _, _) => FlashState::Identify,
+            _ => FlashState::Ready,
+        };
+    }
+}
+
+impl Mbc for Mbc6 {
+    fn read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x3FFF => self.rom.get(addr as usize).copied().unwrap_or(0xFF),
+            0x4000..=0x7FFF => {
+                let window = Self::window(addr);
+                if self.flash_selected[window] {
+                    if !self.flash_enabled {
+                        return 0xFF;
+                    }
+                    self.read_flash(self.window_offset(window, addr, FLASH_SIZE))
+                } else if self.rom.is_empty() {
+                    0xFF
+                } else {
+                    self.rom[self.window_offset(window, addr, self.rom.len())]
+                }
+            }
+            0xA000..=0xBFFF if self.ram_enabled => self.ram[self.ram_offset(addr)],
+            _ => 0xFF,
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x03FF => self.ram_enabled = value & 0x0F == 0x0A,
+            0x0400..=0x07FF => self.ram_banks[0] = value & 0x07,
+            0x0800..=0x0BFF => self.ram_banks[1] = value & 0x07,
+            0x0C00..=0x0FFF => self.flash_enabled = value & 1 != 0,
+            0x1000 => self.flash_write_enabled = value & 1 != 0,
+            0x2000..=0x27FF => self.rom_banks[0] = value & 0x7F,
+            0x2800..=0x2FFF => self.flash_selected[0] = value == 0x08,
+            0x3000..=0x37FF => self.rom_banks[1] = value & 0x7F,
+            0x3800..=0x3FFF => self.flash_selected[1] = value == 0x08,
+            0x4000..=0x7FFF => {
+                let window = Self::window(addr);
+                if self.flash_selected[window] && self.flash_enabled && self.flash_write_enabled {
+                    let offset = self.window_offset(window, addr, FLASH_SIZE);
+                    self.write_flash(offset, value);
+                }
+            }
+            0xA000..=0xBFFF if self.ram_enabled => {
+                let offset = self.ram_offset(addr);
+                self.ram[offset] = value;
+            }
+            _ => {}
+        }
+    }
+
+    /// RAM followed by the flash contents
+    fn save_data(&self) -> Option<Vec<u8>> {
+        Some([self.ram.as_slice(), self.flash.as_slice()].concat())
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        load_ram(&mut self.ram, data);
+        if let Some(flash) = data.get(MBC6_RAM_SIZE..) {
+            load_ram(&mut self.flash, flash);
+        }
+    }
+}
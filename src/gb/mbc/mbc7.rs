@@ -0,0 +1,112 @@
+use crate::gb::cartridge::Cartridge;
+use crate::gb::mbc::eeprom::Eeprom93Lc56;
+use crate::gb::mbc::{rom_offset, Mbc};
+
+
+/// Accelerometer reading for a level cartridge
+const ACCELEROMETER_CENTER: u16 = 0x81D0;
+/// Change in the reading per g of tilt
+const ACCELEROMETER_SCALE: f32 = 0x70 as f32;
+/// Value the axes hold between an erase and the next latch
+const ACCELEROMETER_RESET: u16 = 0x8000;
+
+/// Host input for the MBC7's two-axis accelerometer
+pub trait Accelerometer {
+    /// Current tilt along X and Y in g; (0, 0) is a level cartridge
+    fn acceleration(&mut self) -> (f32, f32);
+}
+
+/// Nintendo MBC7 (`MBC7SensorRumbleRamBattery`): a 93LC56 EEPROM and an ADXL202E
+/// accelerometer mapped into $A000-$AFFF
+pub struct Mbc7 {
+    rom: Vec<u8>,
+    eeprom: Eeprom93Lc56,
+    accelerometer: Option<Box<dyn Accelerometer>>,
+
+    /// $0000-$1FFF = $0A and $4000-$5FFF = $40 are both needed to reach $A000-$AFFF
+    ram_enabled: [bool; 2],
+    rom_bank: u8,
+    /// Latched X and Y readings
+    axes: [u16; 2],
+}
+
+impl Mbc7 {
+    pub fn new(cartridge: &Cartridge) -> Self {
+        Self {
+            rom: cartridge.rom_data.clone(),
+            eeprom: Eeprom93Lc56::default(),
+            accelerometer: None,
+            ram_enabled: [false; 2],
+            rom_bank: 1,
+            axes: [ACCELEROMETER_RESET; 2],
+        }
+    }
+
+    fn latch_accelerometer(&mut self) {
+        // only latches after an erase, like the real sensor interface
+        if self.axes != [ACCELEROMETER_RESET; 2] {
+            return;
+        }
+        let (x, y) = match &mut self.accelerometer {
+            Some(accelerometer) => accelerometer.acceleration(),
+            None => (0.0, 0.0),
+        };
+        let axis = |g: f32| (ACCELEROMETER_CENTER as f32 + g * ACCELEROMETER_SCALE) as u16;
+        // tilting right decreases X on the real hardware
+        self.axes = [axis(-x), axis(y)];
+    }
+
+    fn read_register(&self, addr: u16) -> u8 {
+        match (addr >> 4) & 0x0F {
+            0x2 => self.axes[0] as u8,
+            0x3 => (self.axes[0] >> 8) as u8,
+            0x4 => self.axes[1] as u8,
+            0x5 => (self.axes[1] >> 8) as u8,
+            0x6 => 0x00,
+            0x8 => self.eeprom.pins(),
+            _ => 0xFF,
+        }
+    }
+}
+
+impl Mbc for Mbc7 {
+    fn read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x3FFF => self.rom.get(addr as usize).copied().unwrap_or(0xFF),
+            0x4000..=0x7FFF if !self.rom.is_empty() => {
+                self.rom[rom_offset(&self.rom, self.rom_bank as usize, addr)]
+            }
+            0xA000..=0xAFFF if self.ram_enabled == [true; 2] => self.read_register(addr),
+            _ => 0xFF,
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x1FFF => self.ram_enabled[0] = value == 0x0A,
+            0x2000..=0x3FFF => self.rom_bank = value,
+            0x4000..=0x5FFF => self.ram_enabled[1] = value == 0x40,
+            0xA000..=0xAFFF if self.ram_enabled == [true; 2] => match (addr >> 4) & 0x0F {
+                0x0 if value == 0x55 => self.axes = [ACCELEROMETER_RESET; 2],
+                0x1 if value == 0xAA => self.latch_accelerometer(),
+                0x8 => {
+                    self.eeprom.write_pins(value & 0x80 != 0, value & 0x40 != 0, value & 0x02 != 0);
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    fn set_accelerometer(&mut self, accelerometer: Box<dyn Accelerometer>) {
+        self.accelerometer = Some(accelerometer);
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        Some(self.eeprom.data().to_vec())
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        self.eeprom.load(data);
+    }
+}
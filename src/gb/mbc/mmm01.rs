@@ -0,0 +1,158 @@
+use crate::gb::cartridge::Cartridge;
+use crate::gb::mbc::{external_ram, load_ram, ram_offset, rom_offset, save_ram, Mbc};
+
+
+/// Banks shown before the game is locked in: the menu in the last 32 KiB of the ROM
+const MENU_BANKS: [usize; 2] = [0x1FE, 0x1FF];
+
+/// MMM01 multicart controller (`MMM01`, `MMM01Ram`, `MMM01RamBattery`).
+///
+/// At power-on the menu in the last 32 KiB runs and configures the outer ROM/RAM banks and
+/// which bank bits the game may still change. Setting bit 6 of $0000-$1FFF maps the game in
+/// and locks that configuration; from then on the chip behaves like an MBC1 inside the
+/// game's slice of the ROM.
+pub struct Mmm01 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    battery: bool,
+
+    /// The menu has mapped the game and the outer bank configuration is frozen
+    locked: bool,
+    ram_enabled: bool,
+    /// ROM bank bits 0-4
+    rom_low: u8,
+    /// ROM bank bits 5-6, set by the menu
+    rom_mid: u8,
+    /// ROM bank bits 7-8, set by the menu
+    rom_high: u8,
+    /// Bits 1-4 of the ROM bank that the game can no longer change once locked
+    rom_mask: u8,
+    /// RAM bank bits 0-1
+    ram_low: u8,
+    /// RAM bank bits 2-3, set by the menu
+    ram_high: u8,
+    /// RAM bank bits 0-1 that the game can no longer change once locked
+    ram_mask: u8,
+    mbc1_mode: bool,
+    /// Set by the menu to stop the game changing the banking mode
+    mode_locked: bool,
+}
+
+impl Mmm01 {
+    pub fn new(cartridge: &Cartridge) -> Self {
+        Self {
+            rom: cartridge.rom_data.clone(),
+            ram: external_ram(&cartridge.gb_header),
+            battery: cartridge.gb_header.has_battery(),
+            locked: false,
+            ram_enabled: false,
+            rom_low: 0,
+            rom_mid: 0,
+            rom_high: 0,
+            rom_mask: 0,
+            ram_low: 0,
+            ram_high: 0,
+            ram_mask: 0,
+            mbc1_mode: false,
+            mode_locked: false,
+        }
+    }
+
+    /// True once the menu has started a game
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    /// ROM bank bits 0-4 frozen by the mask
+    fn frozen_rom_bits(&self) -> u8 {
+        (self.rom_mask << 1) & 0x1E
+    }
+
+    fn rom_bank(&self, addr: u16) -> usize {
+        if !self.locked {
+            return MENU_BANKS[addr as usize / 0x4000];
+        }
+        let outer = (self.rom_high as usize) << 7 | (self.rom_mid as usize) << 5;
+        let frozen = self.frozen_rom_bits();
+        let low = match addr {
+            // the fixed window shows the game's first bank
+            0x0000..=0x3FFF => self.rom_low & frozen,
+            // bank 0 of the game is remapped to 1 as on the MBC1
+            _ if self.rom_low & !frozen == 0 => self.rom_low | 1,
+            _ => self.rom_low,
+        };
+        outer | low as usize
+    }
+
+    fn ram_bank(&self) -> usize {
+        let low = if self.mbc1_mode || !self.locked { self.ram_low } else { self.ram_low & self.ram_mask };
+        (self.ram_high as usize) << 2 | low as usize
+    }
+
+    fn write_masked(current: u8, value: u8, frozen: u8) -> u8 {
+        (current & frozen) | (value & !frozen)
+    }
+}
+
+impl Mbc for Mmm01 {
+    fn read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x7FFF if !self.rom.is_empty() => {
+                self.rom[rom_offset(&self.rom, self.rom_bank(addr), addr)]
+            }
+            0xA000..=0xBFFF if self.ram_enabled && !self.ram.is_empty() => {
+                self.ram[ram_offset(&self.ram, self.ram_bank(), addr)]
+            }
+            _ => 0xFF,
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x1FFF => {
+                self.ram_enabled = value & 0x0F == 0x0A;
+                if !self.locked {
+                    self.ram_mask = (value >> 4) & 0x03;
+                    self.locked = value & 0x40 != 0;
+                }
+            }
+            0x2000..=0x3FFF if self.locked => {
+                self.rom_low = Self::write_masked(self.rom_low, value & 0x1F, self.frozen_rom_bits());
+            }
+            0x2000..=0x3FFF => {
+                self.rom_low = value & 0x1F;
+                self.rom_mid = (value >> 5) & 0x03;
+            }
+            0x4000..=0x5FFF if self.locked => {
+                self.ram_low = Self::write_masked(self.ram_low, value & 0x03, self.ram_mask);
+            }
+            0x4000..=0x5FFF => {
+                self.ram_low = value & 0x03;
+                self.ram_high = (value >> 2) & 0x03;
+                self.rom_high = (value >> 4) & 0x03;
+                self.mode_locked = value & 0x40 != 0;
+            }
+            0x6000..=0x7FFF => {
+                if !(self.locked && self.mode_locked) {
+                    self.mbc1_mode = value & 0x01 != 0;
+                }
+                if !self.locked {
+                    self.rom_mask = (value >> 2) & 0x0F;
+                }
+            }
+            0xA000..=0xBFFF if self.ram_enabled && !self.ram.is_empty() => {
+                let offset = ram_offset(&self.ram, self.ram_bank(), addr);
+                self.ram[offset] = value;
+            }
+            _ => {}
+        }
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        save_ram(self.battery, &self.ram)
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        load_ram(&mut self.ram, data);
+    }
+}
@@ -0,0 +1,119 @@
+pub mod camera;
+pub mod eeprom;
+pub mod huc1;
+pub mod huc3;
+pub mod mbc1;
+pub mod mbc2;
+pub mod mbc3;
+pub mod mbc5;
+pub mod mbc6;
+pub mod mbc7;
+pub mod mmm01;
+pub mod rom_only;
+pub mod rtc;
+pub mod tama5;
+
+use crate::gb::cartridge::Cartridge;
+use crate::gb::header::{Controller, GbHeader};
+use crate::gb::mbc::camera::ImageSensor;
+use crate::gb::mbc::mbc7::Accelerometer;
+
+
+/// Size of the switchable and fixed ROM windows at $0000-$3FFF and $4000-$7FFF
+pub const ROM_BANK_SIZE: usize = 0x4000;
+/// Size of the external RAM window at $A000-$BFFF
+pub const RAM_BANK_SIZE: usize = 0x2000;
+
+/// Called with the new motor state whenever a rumble cartridge switches its motor on or off
+pub type RumbleCallback = Box<dyn FnMut(bool)>;
+
+/// Cartridge hardware as seen from the CPU bus.
+///
+/// The host forwards accesses to $0000-$7FFF (ROM and MBC registers) and $A000-$BFFF
+/// (external RAM); the controller owns ROM and RAM storage and its bank registers.
+pub trait Mbc {
+    /// Read from $0000-$7FFF or $A000-$BFFF. Disabled or missing RAM reads as $FF.
+    fn read(&mut self, addr: u16) -> u8;
+
+    /// Write to $0000-$7FFF (controller registers) or $A000-$BFFF.
+    fn write(&mut self, addr: u16, value: u8);
+
+    /// Level of the rumble motor output (true = running)
+    fn rumble(&self) -> bool {
+        false
+    }
+
+    /// Register a callback for rumble motor changes. Cartridges without a motor never call it.
+    fn set_rumble_callback(&mut self, _callback: RumbleCallback) {}
+
+    /// Connect the host's tilt input, for cartridges with an accelerometer
+    fn set_accelerometer(&mut self, _accelerometer: Box<dyn Accelerometer>) {}
+
+    /// Connect the host's picture source, for the Pocket Camera
+    fn set_image_sensor(&mut self, _sensor: Box<dyn ImageSensor>) {}
+
+    /// Level of the cartridge's infrared LED (true = lit)
+    fn ir_led(&self) -> bool {
+        false
+    }
+
+    /// Whether the cartridge's infrared receiver currently sees light
+    fn set_ir_input(&mut self, _light: bool) {}
+
+    /// Battery-backed contents to write to a save file, or `None` when the cartridge has no battery.
+    fn save_data(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Restore contents previously returned by `save_data`.
+    fn load_save_data(&mut self, _data: &[u8]) {}
+}
+
+/// Create the memory bank controller for a loaded cartridge.
+pub fn new_mbc(cartridge: &Cartridge) -> Box<dyn Mbc> {
+    match cartridge.gb_header.capabilities().controller {
+        Controller::None => Box::new(rom_only::RomOnly::new(cartridge)),
+        Controller::Mbc1 => Box::new(mbc1::Mbc1::new(cartridge)),
+        Controller::Mbc2 => Box::new(mbc2::Mbc2::new(cartridge)),
+        Controller::Mmm01 => Box::new(mmm01::Mmm01::new(cartridge)),
+        Controller::Mbc3 => Box::new(mbc3::Mbc3::new(cartridge)),
+        Controller::Mbc5 => Box::new(mbc5::Mbc5::new(cartridge)),
+        Controller::Mbc6 => Box::new(mbc6::Mbc6::new(cartridge)),
+        Controller::Mbc7 => Box::new(mbc7::Mbc7::new(cartridge)),
+        Controller::PocketCamera => Box::new(camera::PocketCamera::new(cartridge)),
+        Controller::Tama5 => Box::new(tama5::Tama5::new(cartridge)),
+        Controller::HuC1 => Box::new(huc1::HuC1::new(cartridge)),
+        Controller::HuC3 => Box::new(huc3::HuC3::new(cartridge)),
+    }
+}
+
+/// Offset into `rom` for `bank` of 16 KiB, wrapping banks past the end of the chip.
+pub(crate) fn rom_offset(rom: &[u8], bank: usize, addr: u16) -> usize {
+    let banks = (rom.len() / ROM_BANK_SIZE).max(1);
+    (bank % banks) * ROM_BANK_SIZE + (addr as usize % ROM_BANK_SIZE)
+}
+
+/// Offset into `ram` for `bank` of 8 KiB; RAM smaller than a bank (2 KiB chips) repeats.
+pub(crate) fn ram_offset(ram: &[u8], bank: usize, addr: u16) -> usize {
+    (bank * RAM_BANK_SIZE + (addr as usize % RAM_BANK_SIZE)) % ram.len()
+}
+
+/// Zeroed external RAM as sized by the header
+pub(crate) fn external_ram(header: &GbHeader) -> Vec<u8> {
+    if header.has_ram() {
+        vec![0; header.ram_size as usize]
+    } else {
+        Vec::new()
+    }
+}
+
+/// `save_data` for cartridges whose whole external RAM is battery-backed
+pub(crate) fn save_ram(battery: bool, ram: &[u8]) -> Option<Vec<u8>> {
+    (battery && !ram.is_empty()).then(|| ram.to_vec())
+}
+
+/// `load_save_data` counterpart of `save_ram`
+pub(crate) fn load_ram(ram: &mut [u8], data: &[u8]) {
+    let len = ram.len().min(data.len());
+    ram[..len].copy_from_slice(&data[..len]);
+}
@@ -0,0 +1,55 @@
+use crate::gb::cartridge::Cartridge;
+use crate::gb::mbc::{external_ram, load_ram, save_ram, Mbc, RAM_BANK_SIZE};
+
+
+/// Cartridges without a bank controller: 32 KiB of ROM, optionally with up to 8 KiB of RAM
+/// (`RomOnly`, `RomRam` and `RomRamBattery`)
+pub struct RomOnly {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    battery: bool,
+}
+
+impl RomOnly {
+    pub fn new(cartridge: &Cartridge) -> Self {
+        let mut ram = external_ram(&cartridge.gb_header);
+        // the RAM is wired straight to the bus, so there is at most one bank of it
+        ram.truncate(RAM_BANK_SIZE);
+
+        Self {
+            rom: cartridge.rom_data.clone(),
+            ram,
+            battery: cartridge.gb_header.has_battery(),
+        }
+    }
+}
+
+impl Mbc for RomOnly {
+    fn read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x7FFF => self.rom.get(addr as usize).copied().unwrap_or(0xFF),
+            0xA000..=0xBFFF if !self.ram.is_empty() => {
+                self.ram[(addr as usize - 0xA000) % self.ram.len()]
+            }
+            _ => 0xFF,
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0xA000..=0xBFFF if !self.ram.is_empty() => {
+                let len = self.ram.len();
+                self.ram[(addr as usize - 0xA000) % len] = value;
+            }
+            _ => {}
+        }
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        save_ram(self.battery, &self.ram)
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        load_ram(&mut self.ram, data);
+    }
+}
@@ -0,0 +1,188 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+
+/// Size of the RTC footer BGB and VBA-M append to `.sav` files
+pub const RTC_FOOTER_SIZE: usize = 48;
+/// Older variant of the footer with a 32-bit timestamp
+pub const RTC_FOOTER_SIZE_32: usize = 44;
+
+const SECONDS: usize = 0;
+const MINUTES: usize = 1;
+const HOURS: usize = 2;
+const DAY_LOW: usize = 3;
+const DAY_HIGH: usize = 4;
+
+/// Writable bits of S, M, H, DL and DH
+const REGISTER_MASKS: [u8; 5] = [0x3F, 0x3F, 0x1F, 0xFF, 0xC1];
+
+/// DH bit 6: clock stopped
+const HALT: u8 = 0x40;
+/// DH bit 7: the 9-bit day counter overflowed
+const DAY_CARRY: u8 = 0x80;
+
+/// Host wall-clock time in seconds since the Unix epoch
+pub fn system_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0)
+}
+
+/// The MBC3 real-time clock: seconds, minutes, hours and a 9-bit day counter.
+///
+/// The clock follows host time: `sync` advances it by the seconds elapsed since the previous
+/// sync, which also covers the time between sessions when restored from a save footer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rtc {
+    /// Live S, M, H, DL, DH
+    registers: [u8; 5],
+    /// Copy taken by the last latch, which is what the CPU reads
+    latched: [u8; 5],
+    /// A 0 was written to the latch register, a following 1 latches
+    latch_armed: bool,
+    /// Host time the registers were last brought up to date
+    last_sync: u64,
+}
+
+impl Rtc {
+    pub fn new(now: u64) -> Self {
+        Self { registers: [0; 5], latched: [0; 5], latch_armed: false, last_sync: now }
+    }
+
+    /// Live register values (S, M, H, DL, DH)
+    pub fn registers(&self) -> [u8; 5] {
+        self.registers
+    }
+
+    /// Latched register values (S, M, H, DL, DH)
+    pub fn latched(&self) -> [u8; 5] {
+        self.latched
+    }
+
+    /// Day counter, 0-511
+    pub fn days(&self) -> u16 {
+        self.registers[DAY_LOW] as u16 | ((self.registers[DAY_HIGH] as u16 & 1) << 8)
+    }
+
+    pub fn halted(&self) -> bool {
+        self.registers[DAY_HIGH] & HALT != 0
+    }
+
+    pub fn day_carry(&self) -> bool {
+        self.registers[DAY_HIGH] & DAY_CARRY != 0
+    }
+
+    /// Bring the clock up to host time `now`
+    pub fn sync(&mut self, now: u64) {
+        if !self.halted() {
+            self.advance(now.saturating_sub(self.last_sync));
+        }
+        self.last_sync = now;
+    }
+
+    /// Run the clock forward by `seconds`
+    pub fn advance(&mut self, mut seconds: u64) {
+        // out-of-range values written by the game count up to their bit width and wrap
+        // without carrying, so step through those one second at a time
+        while seconds > 0 && !self.in_range() {
+            self.tick();
+            seconds -= 1;
+        }
+        if seconds == 0 {
+            return;
+        }
+
+        let [s, m, h, ..] = self.registers.map(u64::from);
+        let total = seconds + s + 60 * (m + 60 * (h + 24 * self.days() as u64));
+        let mut days = total / 86400;
+        if days >= 512 {
+            days %= 512;
+            self.registers[DAY_HIGH] |= DAY_CARRY;
+        }
+        self.registers[SECONDS] = (total % 60) as u8;
+        self.registers[MINUTES] = (total / 60 % 60) as u8;
+        self.registers[HOURS] = (total / 3600 % 24) as u8;
+        self.set_days(days as u16);
+    }
+
+    /// Write to the $6000-$7FFF latch register: $00 then $01 copies the live registers
+    pub fn write_latch(&mut self, value: u8, now: u64) {
+        if self.latch_armed && value == 1 {
+            self.sync(now);
+            self.latched = self.registers;
+        }
+        self.latch_armed = value == 0;
+    }
+
+    /// Read register `index` (0-4 for S, M, H, DL, DH) as latched
+    pub fn read(&self, index: usize) -> u8 {
+        self.latched[index]
+    }
+
+    /// Write register `index` (0-4 for S, M, H, DL, DH)
+    pub fn write(&mut self, index: usize, value: u8, now: u64) {
+        self.sync(now);
+        self.registers[index] = value & REGISTER_MASKS[index];
+    }
+
+    /// Encode the BGB/VBA-M footer: live then latched registers as 32-bit words, then the
+    /// 64-bit Unix timestamp they were valid at, all little-endian
+    pub fn to_footer(&self) -> [u8; RTC_FOOTER_SIZE] {
+        let mut footer = [0; RTC_FOOTER_SIZE];
+        for (index, value) in self.registers.iter().chain(&self.latched).enumerate() {
+            footer[index * 4..index * 4 + 4].copy_from_slice(&(*value as u32).to_le_bytes());
+        }
+        footer[40..48].copy_from_slice(&self.last_sync.to_le_bytes());
+        footer
+    }
+
+    /// Decode a 48- or 44-byte footer. The clock is left at the saved time; `sync` catches
+    /// it up with the time spent powered off.
+    pub fn from_footer(footer: &[u8]) -> Option<Self> {
+        let last_sync = match footer.len() {
+            RTC_FOOTER_SIZE => u64::from_le_bytes(footer[40..48].try_into().unwrap()),
+            RTC_FOOTER_SIZE_32 => u32::from_le_bytes(footer[40..44].try_into().unwrap()) as u64,
+            _ => return None,
+        };
+        let word = |index: usize| {
+            let value = u32::from_le_bytes(footer[index * 4..index * 4 + 4].try_into().unwrap());
+            value as u8 & REGISTER_MASKS[index % 5]
+        };
+        Some(Self {
+            registers: std::array::from_fn(word),
+            latched: std::array::from_fn(|index| word(index + 5)),
+            latch_armed: false,
+            last_sync,
+        })
+    }
+
+    fn in_range(&self) -> bool {
+        self.registers[SECONDS] < 60 && self.registers[MINUTES] < 60 && self.registers[HOURS] < 24
+    }
+
+    fn set_days(&mut self, days: u16) {
+        self.registers[DAY_LOW] = days as u8;
+        self.registers[DAY_HIGH] = (self.registers[DAY_HIGH] & !1) | ((days >> 8) as u8 & 1);
+    }
+
+    fn tick(&mut self) {
+        let registers = &mut self.registers;
+        registers[SECONDS] = (registers[SECONDS] + 1) & REGISTER_MASKS[SECONDS];
+        if registers[SECONDS] != 60 {
+            return;
+        }
+        registers[SECONDS] = 0;
+        registers[MINUTES] = (registers[MINUTES] + 1) & REGISTER_MASKS[MINUTES];
+        if registers[MINUTES] != 60 {
+            return;
+        }
+        registers[MINUTES] = 0;
+        registers[HOURS] = (registers[HOURS] + 1) & REGISTER_MASKS[HOURS];
+        if registers[HOURS] != 24 {
+            return;
+        }
+        registers[HOURS] = 0;
+        let days = self.days() + 1;
+        if days == 512 {
+            self.registers[DAY_HIGH] |= DAY_CARRY;
+        }
+        self.set_days(days % 512);
+    }
+}
@@ -0,0 +1,340 @@
+use crate::gb::cartridge::Cartridge;
+use crate::gb::mbc::rtc::system_time;
+use crate::gb::mbc::{load_ram, rom_offset, Mbc};
+
+
+/// The TAMA5's internal RAM
+pub const TAMA5_RAM_SIZE: usize = 32;
+/// Size of the RTC footer appended to TAMA5 saves
+pub const TAMA5_FOOTER_SIZE: usize = 10 + 8;
+
+// nibble registers, selected through $A001 and written through $A000
+const BANK_LOW: usize = 0x0;
+const BANK_HIGH: usize = 0x1;
+const WRITE_LOW: usize = 0x4;
+const WRITE_HIGH: usize = 0x5;
+/// Bit 0: address bit 4, bits 1-3: command
+const ADDRESS_HIGH: usize = 0x6;
+/// Writing it executes the command
+const ADDRESS_LOW: usize = 0x7;
+/// Reads $1 once the chip is ready
+const ACTIVE: usize = 0xA;
+const READ_LOW: usize = 0xC;
+const READ_HIGH: usize = 0xD;
+
+const COMMAND_RAM_WRITE: u8 = 0x0;
+const COMMAND_RAM_READ: u8 = 0x1;
+const COMMAND_RTC_WRITE: u8 = 0x2;
+const COMMAND_RTC_READ: u8 = 0x3;
+
+// RTC nibbles (BCD) addressed by RTC commands; page 1 (address bit 4) holds the alarm
+const RTC_SECOND_ONES: u8 = 0x00;
+const RTC_SECOND_TENS: u8 = 0x01;
+const RTC_MINUTE_ONES: u8 = 0x02;
+const RTC_MINUTE_TENS: u8 = 0x03;
+const RTC_HOUR_ONES: u8 = 0x04;
+const RTC_HOUR_TENS: u8 = 0x05;
+const RTC_WEEKDAY: u8 = 0x06;
+const RTC_DAY_ONES: u8 = 0x07;
+const RTC_DAY_TENS: u8 = 0x08;
+const RTC_MONTH_ONES: u8 = 0x09;
+const RTC_MONTH_TENS: u8 = 0x0A;
+const RTC_YEAR_ONES: u8 = 0x0B;
+const RTC_YEAR_TENS: u8 = 0x0C;
+const ALARM_MINUTE_ONES: u8 = 0x12;
+const ALARM_MINUTE_TENS: u8 = 0x13;
+const ALARM_HOUR_ONES: u8 = 0x14;
+const ALARM_HOUR_TENS: u8 = 0x15;
+/// Bit 0: alarm enabled, bit 1: alarm went off (write 0 to clear)
+const ALARM_CONTROL: u8 = 0x16;
+
+/// Calendar kept by the TAMA5's RTC; the year is 0-99 and every fourth year is a leap year
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tama5Time {
+    pub year: u8,
+    /// 1-12
+    pub month: u8,
+    /// 1-31
+    pub day: u8,
+    /// 0-6
+    pub weekday: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl Default for Tama5Time {
+    fn default() -> Self {
+        Self { year: 0, month: 1, day: 1, weekday: 0, hour: 0, minute: 0, second: 0 }
+    }
+}
+
+impl Tama5Time {
+    fn days_in_month(&self) -> u8 {
+        match self.month {
+            2 if self.year.is_multiple_of(4) => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+
+    fn seconds_of_day(&self) -> u64 {
+        (self.hour as u64 * 60 + self.minute as u64) * 60 + self.second as u64
+    }
+
+    fn next_day(&mut self) {
+        self.weekday = (self.weekday + 1) % 7;
+        self.day += 1;
+        if self.day > self.days_in_month() {
+            self.day = 1;
+            self.month += 1;
+            if self.month > 12 {
+                self.month = 1;
+                self.year = (self.year + 1) % 100;
+            }
+        }
+    }
+
+    fn advance(&mut self, seconds: u64) {
+        let total = self.seconds_of_day() + seconds;
+        for _ in 0..total / 86400 {
+            self.next_day();
+        }
+        let time = total % 86400;
+        self.hour = (time / 3600) as u8;
+        self.minute = (time / 60 % 60) as u8;
+        self.second = (time % 60) as u8;
+    }
+}
+
+/// Bandai TAMA5 (`BandaiTAMA5`, Tamagotchi 3): every access goes through a nibble-wide
+/// register file at $A000 (data) / $A001 (register select), which banks the ROM, reaches
+/// the 32 bytes of internal RAM and the TAMA6 RTC with its alarm.
+///
+/// RAM and RTC are accessed by setting the value and address registers, then writing the
+/// low address nibble: commands 0/1 write/read RAM, 2/3 write/read an RTC nibble.
+pub struct Tama5 {
+    rom: Vec<u8>,
+    ram: [u8; TAMA5_RAM_SIZE],
+    clock: Box<dyn Fn() -> u64>,
+
+    registers: [u8; 16],
+    selected: usize,
+    time: Tama5Time,
+    /// Host time `time` was last brought up to date
+    last_sync: u64,
+    /// Alarm hour and minute
+    alarm: (u8, u8),
+    alarm_enabled: bool,
+    alarm_fired: bool,
+}
+
+impl Tama5 {
+    pub fn new(cartridge: &Cartridge) -> Self {
+        Self::with_clock(cartridge, system_time)
+    }
+
+    /// Create the controller with a custom time source for the RTC
+    pub fn with_clock(cartridge: &Cartridge, clock: impl Fn() -> u64 + 'static) -> Self {
+        Self {
+            rom: cartridge.rom_data.clone(),
+            ram: [0; TAMA5_RAM_SIZE],
+            last_sync: clock(),
+            clock: Box::new(clock),
+            registers: [0; 16],
+            selected: 0,
+            time: Tama5Time::default(),
+            alarm: (0, 0),
+            alarm_enabled: false,
+            alarm_fired: false,
+        }
+    }
+
+    /// Current RTC time
+    pub fn time(&mut self) -> Tama5Time {
+        self.sync();
+        self.time
+    }
+
+    /// The alarm time was reached while enabled; the game clears it through the alarm control nibble
+    pub fn alarm(&mut self) -> bool {
+        self.sync();
+        self.alarm_fired
+    }
+
+    fn sync(&mut self) {
+        let now = (self.clock)();
+        let elapsed = now.saturating_sub(self.last_sync);
+        self.last_sync = now;
+        if elapsed == 0 {
+            return;
+        }
+
+        if self.alarm_enabled {
+            let alarm = (self.alarm.0 as u64 * 60 + self.alarm.1 as u64) * 60;
+            let start = self.time.seconds_of_day();
+            // seconds until the clock next shows the alarm time
+            let until = (alarm + 86400 - start - 1) % 86400 + 1;
+            if elapsed >= until {
+                self.alarm_fired = true;
+            }
+        }
+        self.time.advance(elapsed);
+    }
+
+    fn rom_bank(&self) -> usize {
+        (self.registers[BANK_LOW] as usize & 0x0F) | ((self.registers[BANK_HIGH] as usize & 1) << 4)
+    }
+
+    fn execute(&mut self) {
+        let address = ((self.registers[ADDRESS_HIGH] & 1) << 4) | self.registers[ADDRESS_LOW];
+        let value = (self.registers[WRITE_HIGH] << 4) | self.registers[WRITE_LOW];
+        let result = match self.registers[ADDRESS_HIGH] >> 1 {
+            COMMAND_RAM_WRITE => {
+                self.ram[address as usize] = value;
+                return;
+            }
+            COMMAND_RAM_READ => self.ram[address as usize],
+            COMMAND_RTC_WRITE => {
+                self.sync();
+                self.write_rtc(address, self.registers[WRITE_LOW]);
+                return;
+            }
+            COMMAND_RTC_READ => {
+                self.sync();
+                self.read_rtc(address)
+            }
+            _ => return,
+        };
+        self.registers[READ_LOW] = result & 0x0F;
+        self.registers[READ_HIGH] = result >> 4;
+    }
+
+    fn read_rtc(&self, address: u8) -> u8 {
+        let time = &self.time;
+        match address {
+            RTC_SECOND_ONES => time.second % 10,
+            RTC_SECOND_TENS => time.second / 10,
+            RTC_MINUTE_ONES => time.minute % 10,
+            RTC_MINUTE_TENS => time.minute / 10,
+            RTC_HOUR_ONES => time.hour % 10,
+            RTC_HOUR_TENS => time.hour / 10,
+            RTC_WEEKDAY => time.weekday,
+            RTC_DAY_ONES => time.day % 10,
+            RTC_DAY_TENS => time.day / 10,
+            RTC_MONTH_ONES => time.month % 10,
+            RTC_MONTH_TENS => time.month / 10,
+            RTC_YEAR_ONES => time.year % 10,
+            RTC_YEAR_TENS => time.year / 10,
+            ALARM_MINUTE_ONES => self.alarm.1 % 10,
+            ALARM_MINUTE_TENS => self.alarm.1 / 10,
+            ALARM_HOUR_ONES => self.alarm.0 % 10,
+            ALARM_HOUR_TENS => self.alarm.0 / 10,
+            ALARM_CONTROL => self.alarm_enabled as u8 | (self.alarm_fired as u8) << 1,
+            _ => 0,
+        }
+    }
+
+    fn write_rtc(&mut self, address: u8, nibble: u8) {
+        // BCD digits are stored into binary fields, clamped to the field's range
+        let set_ones = |field: &mut u8, limit: u8| *field = (*field / 10 * 10 + nibble % 10).min(limit);
+        let set_tens = |field: &mut u8, limit: u8| *field = (nibble % 10 * 10 + *field % 10).min(limit);
+        let time = &mut self.time;
+        match address {
+            RTC_SECOND_ONES => set_ones(&mut time.second, 59),
+            RTC_SECOND_TENS => set_tens(&mut time.second, 59),
+            RTC_MINUTE_ONES => set_ones(&mut time.minute, 59),
+            RTC_MINUTE_TENS => set_tens(&mut time.minute, 59),
+            RTC_HOUR_ONES => set_ones(&mut time.hour, 23),
+            RTC_HOUR_TENS => set_tens(&mut time.hour, 23),
+            RTC_WEEKDAY => time.weekday = nibble % 7,
+            RTC_DAY_ONES => set_ones(&mut time.day, 31),
+            RTC_DAY_TENS => set_tens(&mut time.day, 31),
+            RTC_MONTH_ONES => set_ones(&mut time.month, 12),
+            RTC_MONTH_TENS => set_tens(&mut time.month, 12),
+            RTC_YEAR_ONES => set_ones(&mut time.year, 99),
+            RTC_YEAR_TENS => set_tens(&mut time.year, 99),
+            ALARM_MINUTE_ONES => set_ones(&mut self.alarm.1, 59),
+            ALARM_MINUTE_TENS => set_tens(&mut self.alarm.1, 59),
+            ALARM_HOUR_ONES => set_ones(&mut self.alarm.0, 23),
+            ALARM_HOUR_TENS => set_tens(&mut self.alarm.0, 23),
+            ALARM_CONTROL => {
+                self.alarm_enabled = nibble & 1 != 0;
+                self.alarm_fired &= nibble & 2 != 0;
+            }
+            _ => {}
+        }
+        let time = &mut self.time;
+        time.day = time.day.max(1);
+        time.month = time.month.max(1);
+    }
+}
+
+impl Mbc for Tama5 {
+    fn read(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x3FFF => self.rom.get(addr as usize).copied().unwrap_or(0xFF),
+            0x4000..=0x7FFF if !self.rom.is_empty() => {
+                self.rom[rom_offset(&self.rom, self.rom_bank(), addr)]
+            }
+            0xA000 => match self.selected {
+                ACTIVE => 0xF1,
+                READ_LOW | READ_HIGH => 0xF0 | self.registers[self.selected],
+                _ => 0xFF,
+            },
+            _ => 0xFF,
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0xA000 => {
+                self.registers[self.selected] = value & 0x0F;
+                if self.selected == ADDRESS_LOW {
+                    self.execute();
+                }
+            }
+            0xA001 => self.selected = value as usize & 0x0F,
+            _ => {}
+        }
+    }
+
+    /// Internal RAM followed by the RTC footer: year, month, day, weekday, hour, minute and
+    /// second, alarm hour and minute, the alarm control bits, then the host time they were
+    /// valid at (64-bit little-endian)
+    fn save_data(&self) -> Option<Vec<u8>> {
+        let time = &self.time;
+        let mut data = self.ram.to_vec();
+        data.extend_from_slice(&[time.year, time.month, time.day, time.weekday]);
+        data.extend_from_slice(&[time.hour, time.minute, time.second]);
+        data.extend_from_slice(&[self.alarm.0, self.alarm.1]);
+        data.push(self.alarm_enabled as u8 | (self.alarm_fired as u8) << 1);
+        data.extend_from_slice(&self.last_sync.to_le_bytes());
+        Some(data)
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        load_ram(&mut self.ram, data);
+        // a save without a footer keeps the fresh clock
+        let footer = match data.len().checked_sub(TAMA5_RAM_SIZE) {
+            Some(TAMA5_FOOTER_SIZE) => &data[TAMA5_RAM_SIZE..],
+            _ => return,
+        };
+        self.time = Tama5Time {
+            year: footer[0] % 100,
+            month: footer[1].clamp(1, 12),
+            day: footer[2].clamp(1, 31),
+            weekday: footer[3] % 7,
+            hour: footer[4].min(23),
+            minute: footer[5].min(59),
+            second: footer[6].min(59),
+        };
+        self.alarm = (footer[7].min(23), footer[8].min(59));
+        self.alarm_enabled = footer[9] & 1 != 0;
+        self.alarm_fired = footer[9] & 2 != 0;
+        // catch up with the time spent powered off, which may set off the alarm
+        self.last_sync = u64::from_le_bytes(footer[10..18].try_into().unwrap());
+        self.sync();
+    }
+}
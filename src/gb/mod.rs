@@ -1,3 +1,7 @@
 pub mod header;
 pub mod error;
-pub mod cartridge;
\ No newline at end of file
+pub mod cartridge;
+pub mod licensee;
+pub mod mbc;
+pub mod photo;
+pub mod timing;
\ No newline at end of file
@@ -0,0 +1,170 @@
+use crate::gb::error::PhotoError;
+
+
+/// Photos a Pocket Camera save holds
+pub const PHOTO_SLOTS: usize = 30;
+pub const PHOTO_WIDTH: usize = 128;
+pub const PHOTO_HEIGHT: usize = 112;
+
+/// Slot n occupies the 4 KiB at $2000 + n * $1000 of the save
+const SLOT_BASE: usize = 0x2000;
+const SLOT_SIZE: usize = 0x1000;
+/// Each slot: the picture, a 32x32 thumbnail, then the photo's metadata block
+const IMAGE_SIZE: usize = 0xE00;
+const THUMBNAIL_OFFSET: usize = 0xE00;
+const METADATA_OFFSET: usize = 0xF00;
+const METADATA_SIZE: usize = 0x100;
+/// Album position of each slot, $FF for an empty or deleted slot
+const ALBUM_INDEX_OFFSET: usize = 0x11B2;
+
+const SAVE_SIZE: usize = SLOT_BASE + PHOTO_SLOTS * SLOT_SIZE;
+
+/// Shades for the 2-bit colour values, lightest first
+const GRAY_LEVELS: [u8; 4] = [0xFF, 0xAA, 0x55, 0x00];
+
+/// One photo slot decoded from a Pocket Camera save
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Photo {
+    /// Slot number, 0-29
+    pub slot: usize,
+    /// Position in the camera's album, or `None` when the slot is empty or the photo was deleted
+    pub album_index: Option<u8>,
+    /// 2-bit colour per pixel (0 = white, 3 = black), row-major, `PHOTO_WIDTH` x `PHOTO_HEIGHT`
+    pub pixels: Vec<u8>,
+    /// The slot's raw metadata block (owner, comment and frame data as the camera wrote them)
+    pub metadata: Vec<u8>,
+}
+
+impl Photo {
+    /// True when the photo no longer appears in the album. The picture data is usually still there.
+    pub fn is_deleted(&self) -> bool {
+        self.album_index.is_none()
+    }
+
+    /// 8-bit grayscale pixels, row-major
+    pub fn grayscale(&self) -> Vec<u8> {
+        self.pixels.iter().map(|&color| GRAY_LEVELS[color as usize & 3]).collect()
+    }
+
+    /// Binary PGM (P5) image
+    pub fn to_pgm(&self) -> Vec<u8> {
+        let mut pgm = format!("P5\n{} {}\n255\n", PHOTO_WIDTH, PHOTO_HEIGHT).into_bytes();
+        pgm.extend(self.grayscale());
+        pgm
+    }
+
+    /// 8-bit grayscale PNG image
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut png = b"\x89PNG\r\n\x1A\n".to_vec();
+
+        let mut header = Vec::with_capacity(13);
+        header.extend((PHOTO_WIDTH as u32).to_be_bytes());
+        header.extend((PHOTO_HEIGHT as u32).to_be_bytes());
+        // bit depth 8, grayscale, deflate, adaptive filtering, no interlace
+        header.extend([8, 0, 0, 0, 0]);
+        png_chunk(&mut png, b"IHDR", &header);
+
+        // every scanline starts with filter type 0
+        let scanlines: Vec<u8> = self
+            .grayscale()
+            .chunks(PHOTO_WIDTH)
+            .flat_map(|row| std::iter::once(0).chain(row.iter().copied()))
+            .collect();
+        png_chunk(&mut png, b"IDAT", &zlib_stored(&scanlines));
+        png_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+}
+
+/// Decode the 30 photo slots of a Pocket Camera save (128 KiB battery RAM)
+pub fn extract_photos(save: &[u8]) -> Result<Vec<Photo>, PhotoError> {
+    if save.len() < SAVE_SIZE {
+        return Err(PhotoError::SaveTooSmall { found: save.len(), expected: SAVE_SIZE });
+    }
+
+    let photos = (0..PHOTO_SLOTS)
+        .map(|slot| {
+            let data = &save[SLOT_BASE + slot * SLOT_SIZE..SLOT_BASE + (slot + 1) * SLOT_SIZE];
+            let album_index = match save[ALBUM_INDEX_OFFSET + slot] {
+                0xFF => None,
+                index => Some(index),
+            };
+            Photo {
+                slot,
+                album_index,
+                pixels: decode_tiles(&data[..IMAGE_SIZE], PHOTO_WIDTH, PHOTO_HEIGHT),
+                metadata: data[METADATA_OFFSET..METADATA_OFFSET + METADATA_SIZE].to_vec(),
+            }
+        })
+        .collect();
+    Ok(photos)
+}
+
+/// Decode the 32x32 thumbnail of a slot, in the same pixel format as `Photo::pixels`
+pub fn extract_thumbnail(save: &[u8], slot: usize) -> Option<Vec<u8>> {
+    if slot >= PHOTO_SLOTS {
+        return None;
+    }
+    let start = SLOT_BASE + slot * SLOT_SIZE + THUMBNAIL_OFFSET;
+    save.get(start..start + 0x100).map(|tiles| decode_tiles(tiles, 32, 32))
+}
+
+/// Convert 2bpp tile data laid out row by row into one colour value per pixel
+fn decode_tiles(tiles: &[u8], width: usize, height: usize) -> Vec<u8> {
+    let mut pixels = vec![0; width * height];
+    for (index, pixel) in pixels.iter_mut().enumerate() {
+        let (x, y) = (index % width, index / width);
+        let tile = (y / 8) * (width / 8) + x / 8;
+        let offset = tile * 16 + (y % 8) * 2;
+        let bit = 7 - (x % 8);
+        *pixel = (tiles[offset] >> bit & 1) | (tiles[offset + 1] >> bit & 1) << 1;
+    }
+    pixels
+}
+
+fn png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend(kind);
+    png.extend(data);
+    let crc = crc32(&png[start..]);
+    png.extend(crc.to_be_bytes());
+}
+
+/// zlib stream made of uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(0xFFFF).peekable();
+    if blocks.peek().is_none() {
+        out.extend([1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        out.push(blocks.peek().is_none() as u8);
+        let len = block.len() as u16;
+        out.extend(len.to_le_bytes());
+        out.extend((!len).to_le_bytes());
+        out.extend(block);
+    }
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
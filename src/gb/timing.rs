@@ -0,0 +1,60 @@
+const DOTS_PER_SCANLINE: u16 = 456;
+const SCANLINES: u16 = 154;
+const VBLANK_SCANLINES: u16 = 10;
+/// The DIV register counts every 256 CPU clocks (T-cycles)
+const DIV_CLOCK_DIVIDER: f64 = 256.0;
+
+/// Clock rates of a Game Boy model. Frame layout is the same on all of them: 456 dots per
+/// line, 144 visible lines and 10 of vblank.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimingParameters {
+    /// CPU clock (T-cycles) in Hz; instructions take multiples of 4
+    pub cpu_clock: f64,
+    /// PPU dots per second
+    pub ppu_clock: f64,
+}
+
+impl TimingParameters {
+    /// DMG, MGB, and the CGB in normal speed: a 2^22 Hz crystal
+    pub const DMG: Self = Self { cpu_clock: 4_194_304.0, ppu_clock: 4_194_304.0 };
+
+    /// CGB after a speed switch (KEY1): the CPU, timers and serial clock double while the
+    /// PPU, audio and HDMA keep their speed
+    pub const CGB_DOUBLE_SPEED: Self = Self { cpu_clock: 8_388_608.0, ppu_clock: 4_194_304.0 };
+
+    /// Super Game Boy: clocked from the SNES master clock (21.477272 MHz / 5), so games run
+    /// about 2.4% fast
+    pub const SGB: Self = Self { cpu_clock: 21_477_272.0 / 5.0, ppu_clock: 21_477_272.0 / 5.0 };
+
+    /// Super Game Boy 2: its own crystal, at handheld speed
+    pub const SGB2: Self = Self::DMG;
+
+    /// CPU machine cycles (M-cycles) per second
+    pub fn machine_clock(&self) -> f64 {
+        self.cpu_clock / 4.0
+    }
+
+    /// Rate the DIV register increments at
+    pub fn div_clock(&self) -> f64 {
+        self.cpu_clock / DIV_CLOCK_DIVIDER
+    }
+
+    pub fn dots_per_frame(&self) -> u32 {
+        DOTS_PER_SCANLINE as u32 * SCANLINES as u32
+    }
+
+    /// CPU clocks per frame: 70224, or twice that in double speed
+    pub fn cpu_cycles_per_frame(&self) -> f64 {
+        self.dots_per_frame() as f64 * self.cpu_clock / self.ppu_clock
+    }
+
+    /// Frames per second: about 59.73, or 61.17 on the Super Game Boy
+    pub fn frame_rate(&self) -> f64 {
+        self.ppu_clock / self.dots_per_frame() as f64
+    }
+
+    /// CPU clocks from the start of vblank to the first line of the next frame
+    pub fn vblank_cpu_cycles(&self) -> f64 {
+        (DOTS_PER_SCANLINE * VBLANK_SCANLINES) as f64 * self.cpu_clock / self.ppu_clock
+    }
+}
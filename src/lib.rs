@@ -1,2 +1,4 @@
 pub mod nes;
-pub mod gb;
\ No newline at end of file
+pub mod gb;
+pub mod save;
+pub mod validation;
\ No newline at end of file
@@ -2,7 +2,11 @@ use std::path::Path;
 use std::io::Read;
 
 use crate::nes::error::RomParseError;
+use crate::nes::dump::{classify_trailing, declares_misc_rom};
 use crate::nes::header::InesHeader;
+use crate::nes::mapper;
+use crate::save::SaveRam;
+use crate::validation::{LoadPolicy, Problem, ValidationReport};
 
 
 const TRAINER_SIZE: usize = 512;
@@ -14,35 +18,49 @@ pub struct Cartridge {
     pub prg_rom: Vec<u8>,
     pub chr_rom: Vec<u8>,
     pub misc_rom: Option<Vec<u8>>,
+    /// Problems found while loading
+    pub report: ValidationReport,
 }
 
 impl Cartridge {
     pub fn load_rom_file(path: impl AsRef<Path>) -> Result<Self, RomParseError> {
-        let bytes = std::fs::read(path)?;
-        let header = InesHeader::from_bytes(&bytes)?;
-
-        let trainer_data = Self::extract_trainer(&bytes, &header)?;
-        let (prg_rom, chr_rom) = Self::extract_prg_chr(&bytes, &header)?;
-        let misc_rom = Self::extract_misc(&bytes, &header);
+        Self::load_rom_file_with_policy(path, LoadPolicy::Strict)
+    }
 
-        Ok(Cartridge {
-            ines_header: header,
-            trainer: trainer_data,
-            prg_rom,
-            chr_rom,
-            misc_rom,
-        })
+    pub fn load_rom_file_with_policy(path: impl AsRef<Path>, policy: LoadPolicy) -> Result<Self, RomParseError> {
+        let bytes = std::fs::read(path)?;
+        Self::from_bytes(bytes, policy)
     }
 
     pub fn load_rom_data<R: Read>(data: &mut R) -> Result<Self, RomParseError> {
+        Self::load_rom_data_with_policy(data, LoadPolicy::Strict)
+    }
+
+    pub fn load_rom_data_with_policy<R: Read>(data: &mut R, policy: LoadPolicy) -> Result<Self, RomParseError> {
         let mut bytes = Vec::new();
         data.read_to_end(&mut bytes)?;
+        Self::from_bytes(bytes, policy)
+    }
 
-        let header = InesHeader::from_bytes(&bytes)?;
+    fn from_bytes(mut bytes: Vec<u8>, policy: LoadPolicy) -> Result<Self, RomParseError> {
+        let (header, mut report) = InesHeader::from_bytes_with_report(&bytes)?;
+
+        let trainer_offset = if header.flags_6.trainer() { TRAINER_SIZE } else { 0 };
+        let expected = HEADER_SIZE + trainer_offset + header.prg_rom_size as usize + header.chr_rom_size as usize;
+        let size = bytes.len();
+        if size < expected {
+            let problem = Problem::Underdump { expected, found: size };
+            report.error(policy, size, problem, RomParseError::InvalidRomSize)?;
+            // unprogrammed ROM reads as 0xFF
+            bytes.resize(expected, 0xFF);
+        }
 
         let trainer_data = Self::extract_trainer(&bytes, &header)?;
         let (prg_rom, chr_rom) = Self::extract_prg_chr(&bytes, &header)?;
-        let misc_rom = Self::extract_misc(&bytes, &header);
+        let misc_rom = Self::extract_misc(&bytes, &header, &prg_rom, &chr_rom);
+        if size > expected && !declares_misc_rom(&bytes, &header) {
+            report.warn(expected, Problem::Overdump { expected, found: size });
+        }
 
         Ok(Cartridge {
             ines_header: header,
@@ -50,9 +68,19 @@ impl Cartridge {
             prg_rom,
             chr_rom,
             misc_rom,
+            report,
         })
     }
 
+    /// Battery-backed memory to keep in a `.sav` file, laid out like the mapper's
+    /// `save_data`: PRG-NVRAM followed by CHR-NVRAM as `CartridgeMemory` allocates them,
+    /// or the EEPROM or internal RAM of boards that have one. `None` when nothing survives
+    /// power-off.
+    pub fn save_ram(&self) -> Option<SaveRam> {
+        let size = mapper::save_size(&self.ines_header);
+        (size > 0).then(|| SaveRam::new(size))
+    }
+
     fn extract_trainer(data: &[u8], header: &InesHeader) -> Result<Option<Vec<u8>>, RomParseError> {
         if header.flags_6.trainer() {
             if data.len() < HEADER_SIZE + TRAINER_SIZE {
@@ -82,9 +110,10 @@ impl Cartridge {
         Ok((prg_rom, chr_rom))
     }   
 
-    fn extract_misc(data: &[u8], header: &InesHeader) -> Option<Vec<u8>> {
-        // This data follows PRG and CHR ROMs, if present and is not indicated in the header.
-        // This data depends on the console type and mapper type, so we will just extract it as raw bytes for now.
+    fn extract_misc(data: &[u8], header: &InesHeader, prg_rom: &[u8], chr_rom: &[u8]) -> Option<Vec<u8>> {
+        // This data follows PRG and CHR ROMs. NES 2.0 declares it; otherwise it is usually an
+        // overdump, which is left out. What remains depends on the console type and mapper
+        // type, so we will just extract it as raw bytes for now.
         let trainer_offset = if header.flags_6.trainer() { TRAINER_SIZE } else { 0 };
         let prg_start = HEADER_SIZE + trainer_offset;
         let prg_end = prg_start + (header.prg_rom_size as usize);
@@ -92,9 +121,11 @@ impl Cartridge {
         let chr_end = chr_start + (header.chr_rom_size as usize);
 
         if data.len() > chr_end {
-            Some(data[chr_end..].to_vec())
+            let misc = &data[chr_end..];
+            let kind = classify_trailing(misc, prg_rom, chr_rom, declares_misc_rom(data, header));
+            (!kind.is_junk()).then(|| misc.to_vec())
         } else {
             None
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,169 @@
+use crate::nes::error::RomParseError;
+use crate::nes::header::{encode_nes2_rom_size, HeaderFormat, InesHeader};
+
+
+const HEADER_SIZE: usize = 16;
+const TRAINER_SIZE: usize = 512;
+const PRG_UNIT: usize = 16 * 1024;
+const CHR_UNIT: usize = 8 * 1024;
+
+/// What the bytes after CHR-ROM turned out to be
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrailingData {
+    /// Miscellaneous ROM declared by a NES 2.0 header (byte 14)
+    MiscRom,
+    /// The same byte repeated, left by a dumper reading past the end of the chips
+    Padding(u8),
+    /// A mix of 0x00 and 0xFF bytes only, like blank or erased areas of a chip
+    Filler,
+    /// Another copy of PRG-ROM, CHR-ROM or both, from dumping a mirrored address range
+    MirroredPrg,
+    MirroredChr,
+    MirroredPrgChr,
+    /// Printable text such as a title or a dumping group's signature, possibly padded
+    Text(String),
+    /// Nothing recognisable; kept since it might be real data
+    Unknown,
+}
+
+impl TrailingData {
+    /// True if the data adds nothing to the game and can be dropped
+    pub fn is_junk(&self) -> bool {
+        !matches!(self, TrailingData::MiscRom | TrailingData::Unknown)
+    }
+}
+
+/// How a `.nes` file's contents compare to what its header describes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DumpAnalysis {
+    /// File size the header implies: header, trainer, PRG-ROM and CHR-ROM
+    pub expected_size: usize,
+    /// Bytes missing from the end of an underdump
+    pub missing: usize,
+    /// Bytes after CHR-ROM and what they are
+    pub trailing: Option<(usize, TrailingData)>,
+    /// Smallest size PRG-ROM repeats itself in: equal to its size unless the upper half
+    /// duplicates the lower (repeatedly, down to 16 KiB)
+    pub prg_unique_size: usize,
+    /// Same for CHR-ROM, down to 8 KiB
+    pub chr_unique_size: usize,
+}
+
+impl DumpAnalysis {
+    /// True if the file holds exactly what the header says, without duplicated halves
+    pub fn is_canonical(&self, header: &InesHeader) -> bool {
+        self.missing == 0
+            && self.trailing.as_ref().is_none_or(|(_, data)| !data.is_junk())
+            && self.prg_unique_size == header.prg_rom_size as usize
+            && self.chr_unique_size == header.chr_rom_size as usize
+    }
+}
+
+/// Compare a `.nes` file against its header
+pub fn analyze(bytes: &[u8]) -> Result<DumpAnalysis, RomParseError> {
+    let header = InesHeader::from_bytes(bytes)?;
+    let (prg_start, chr_start, chr_end) = layout(&header);
+
+    let prg = &bytes[prg_start.min(bytes.len())..chr_start.min(bytes.len())];
+    let chr = &bytes[chr_start.min(bytes.len())..chr_end.min(bytes.len())];
+    let trailing = (bytes.len() > chr_end).then(|| {
+        let data = &bytes[chr_end..];
+        (data.len(), classify_trailing(data, prg, chr, declares_misc_rom(bytes, &header)))
+    });
+
+    Ok(DumpAnalysis {
+        expected_size: chr_end,
+        missing: chr_end.saturating_sub(bytes.len()),
+        trailing,
+        prg_unique_size: unique_size(prg, PRG_UNIT),
+        chr_unique_size: unique_size(chr, CHR_UNIT),
+    })
+}
+
+/// Rewrite a `.nes` file in canonical form for deduplication: junk after CHR-ROM is dropped
+/// and PRG/CHR-ROM made of repeated halves are cut to one copy, with the header's sizes
+/// updated. Underdumps cannot be repaired and are returned unchanged.
+pub fn canonicalize(bytes: &[u8]) -> Result<Vec<u8>, RomParseError> {
+    let header = InesHeader::from_bytes(bytes)?;
+    let analysis = analyze(bytes)?;
+    if analysis.missing > 0 {
+        return Ok(bytes.to_vec());
+    }
+    let (prg_start, chr_start, chr_end) = layout(&header);
+
+    let mut canonical = bytes[..prg_start].to_vec();
+    canonical.extend_from_slice(&bytes[prg_start..prg_start + analysis.prg_unique_size]);
+    canonical.extend_from_slice(&bytes[chr_start..chr_start + analysis.chr_unique_size]);
+    if analysis.trailing.as_ref().is_some_and(|(_, data)| !data.is_junk()) {
+        canonical.extend_from_slice(&bytes[chr_end..]);
+    }
+
+    if header.format == HeaderFormat::Nes2 {
+        let (prg_lsb, prg_msb) = encode_nes2_rom_size(analysis.prg_unique_size, PRG_UNIT);
+        let (chr_lsb, chr_msb) = encode_nes2_rom_size(analysis.chr_unique_size, CHR_UNIT);
+        canonical[4] = prg_lsb;
+        canonical[5] = chr_lsb;
+        canonical[9] = chr_msb << 4 | prg_msb;
+    } else {
+        canonical[4] = (analysis.prg_unique_size / PRG_UNIT) as u8;
+        canonical[5] = (analysis.chr_unique_size / CHR_UNIT) as u8;
+    }
+    Ok(canonical)
+}
+
+/// Classify the bytes after CHR-ROM
+pub(crate) fn classify_trailing(data: &[u8], prg: &[u8], chr: &[u8], misc_declared: bool) -> TrailingData {
+    if misc_declared {
+        return TrailingData::MiscRom;
+    }
+    if data.iter().all(|&byte| byte == data[0]) {
+        return TrailingData::Padding(data[0]);
+    }
+    if repeats(data, prg) {
+        return TrailingData::MirroredPrg;
+    }
+    if repeats(data, chr) {
+        return TrailingData::MirroredChr;
+    }
+    let prg_chr = [prg, chr].concat();
+    if repeats(data, &prg_chr) {
+        return TrailingData::MirroredPrgChr;
+    }
+
+    // text is often padded out to a round size
+    let Some(text_end) = data.iter().rposition(|&byte| byte != 0x00 && byte != 0xFF) else {
+        return TrailingData::Filler;
+    };
+    let text = &data[..=text_end];
+    if text.iter().all(|&byte| byte.is_ascii_graphic() || byte.is_ascii_whitespace()) {
+        return TrailingData::Text(String::from_utf8_lossy(text).into_owned());
+    }
+    TrailingData::Unknown
+}
+
+/// NES 2.0 headers count the miscellaneous ROMs after CHR in byte 14
+pub(crate) fn declares_misc_rom(bytes: &[u8], header: &InesHeader) -> bool {
+    header.format == HeaderFormat::Nes2 && bytes[14] & 0x03 != 0
+}
+
+/// Offsets of PRG-ROM, CHR-ROM and the end of CHR-ROM
+fn layout(header: &InesHeader) -> (usize, usize, usize) {
+    let trainer = if header.flags_6.trainer() { TRAINER_SIZE } else { 0 };
+    let prg_start = HEADER_SIZE + trainer;
+    let chr_start = prg_start + header.prg_rom_size as usize;
+    (prg_start, chr_start, chr_start + header.chr_rom_size as usize)
+}
+
+/// `data` is made of copies of `rom`, the last one possibly cut short
+fn repeats(data: &[u8], rom: &[u8]) -> bool {
+    !rom.is_empty() && data.chunks(rom.len()).all(|chunk| chunk == &rom[..chunk.len()])
+}
+
+/// Halve `rom` while its upper half repeats the lower one, stopping at `unit`
+fn unique_size(rom: &[u8], unit: usize) -> usize {
+    let mut size = rom.len();
+    while size >= 2 * unit && size.is_multiple_of(2 * unit) && rom[..size / 2] == rom[size / 2..size] {
+        size /= 2;
+    }
+    size
+}
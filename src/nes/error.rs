@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use crate::nes::header::Mirroring;
+
 
 #[derive(Error, Debug)]
 pub enum RomParseError {
@@ -9,6 +11,10 @@ pub enum RomParseError {
     HeaderInvalidMagic,
     #[error("invalid ROM size, inconsistent with header")]
     InvalidRomSize,
+    #[error("unsupported mapper {0}")]
+    UnsupportedMapper(u16),
+    #[error("mapper {0} cannot provide {1:?} mirroring")]
+    UnsupportedMirroring(u16, Mirroring),
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
 }
\ No newline at end of file
@@ -1,14 +1,19 @@
 use bitfield_struct::bitfield;
 
 use crate::nes::error::RomParseError;
+use crate::nes::timing::TimingParameters;
+use crate::nes::vs::{VsHardwareType, VsPpuType};
+use crate::validation::{Problem, ValidationReport};
 
 const NES_MAGIC: &[u8; 4] = b"NES\x1A";
 
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HeaderFormat {
-    INes,  // iNES v1
-    Nes2,  // NES 2.0
+    ArchaicINes,  // only bytes 4-6 are meaningful, 7-15 are garbage
+    INes07,       // byte 7 holds the mapper high nibble, 8-15 are garbage
+    INes,         // iNES v1
+    Nes2,         // NES 2.0
 }
 
 
@@ -18,7 +23,38 @@ pub enum RamSize {
     Nes2{ram: u32, nvram: u32},
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Nametable arrangement of a cartridge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mirroring {
+    /// $2000 = $2400 and $2800 = $2C00 (vertical arrangement, for vertical scrolling)
+    Horizontal,
+    /// $2000 = $2800 and $2400 = $2C00 (horizontal arrangement, for horizontal scrolling)
+    Vertical,
+    /// All four nametables show the first page of CIRAM
+    SingleScreenA,
+    /// All four nametables show the second page of CIRAM
+    SingleScreenB,
+    /// Extra VRAM on the cartridge gives four independent nametables
+    FourScreen,
+    /// The mapper switches the arrangement at runtime; the header bit is ignored
+    MapperControlled,
+}
+
+impl Mirroring {
+    /// CIRAM page backing each of the four nametables, or `None` when CIRAM alone cannot
+    /// form the arrangement (four-screen VRAM, or whatever the mapper selects)
+    pub fn nametable_pages(self) -> Option<[u8; 4]> {
+        match self {
+            Mirroring::Horizontal => Some([0, 0, 1, 1]),
+            Mirroring::Vertical => Some([0, 1, 0, 1]),
+            Mirroring::SingleScreenA => Some([0, 0, 0, 0]),
+            Mirroring::SingleScreenB => Some([1, 1, 1, 1]),
+            Mirroring::FourScreen | Mirroring::MapperControlled => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum TimingMode {
     NTSC = 0,
@@ -72,9 +108,9 @@ impl ConsoleType {
 #[bitfield(u8)]
 pub struct VsSystemType {
     #[bits(4)]
-    pub ppu_type: u8,    // bits 0-3
+    pub ppu_type: VsPpuType,    // bits 0-3
     #[bits(4)]
-    pub hardware_type: u8, // bits 4-7
+    pub hardware_type: VsHardwareType, // bits 4-7
 }
 
 #[bitfield(u8)]
@@ -121,6 +157,41 @@ pub struct Flags8Nes2 {
     pub submapper: u8,        // bits 4-7
 }
 
+// Unofficial iNES byte 9, rarely set
+#[bitfield(u8)]
+pub struct Flags9Ines {
+    pub pal: bool,            // bit 0 (0 = NTSC, 1 = PAL)
+    #[bits(7)]
+    __: u8,                   // bits 1-7 (reserved)
+}
+
+// Unofficial iNES byte 10, written by some tools and honored by few emulators
+#[bitfield(u8)]
+pub struct Flags10Ines {
+    #[bits(2)]
+    pub tv_system: u8,        // bits 0-1 (0 = NTSC, 2 = PAL, 1/3 = dual compatible)
+    #[bits(2)]
+    __: u8,                   // bits 2-3
+    pub no_prg_ram: bool,     // bit 4 (1 = nothing at $6000-$7FFF)
+    pub bus_conflicts: bool,  // bit 5
+    #[bits(2)]
+    __: u8,                   // bits 6-7
+}
+
+/// Byte 9, read according to the header format
+#[derive(Debug, Clone, Copy)]
+pub enum Flags9 {
+    Ines(Flags9Ines),
+    Nes2(Flags9Nes2),
+}
+
+/// Byte 10, read according to the header format
+#[derive(Debug, Clone, Copy)]
+pub enum Flags10 {
+    Ines(Flags10Ines),
+    Nes2(Flags10Nes2),
+}
+
 #[bitfield(u8)]
 pub struct Flags10Nes2 {
     #[bits(4)]
@@ -155,7 +226,7 @@ pub enum Flags13Nes2 {
 
 impl Flags13Nes2 {
     // This has to be a const fn
-    const fn into_bits(self) -> u8 {
+    pub const fn into_bits(self) -> u8 {
         match self {
             Flags13Nes2::VsSystemType(vs) => vs.into_bits(),
             Flags13Nes2::ExtendedConsoleType(ec) => ec.into_bits(),
@@ -203,10 +274,11 @@ pub struct InesHeader {
     pub flags_6: Flags6,
     /// Parsed flags from byte 7
     pub flags_7: Flags7,
-    /// Parsed flags from byte 9 (NES 2.0 specific)
-    pub flags_9: Flags9Nes2,
-    /// Parsed flags from byte 10 (NES 2.0 specific)
-    pub flags_10: Flags10Nes2,
+    /// Parsed flags from byte 9 (ROM size MSBs for NES 2.0, TV system for iNES)
+    pub flags_9: Flags9,
+    /// Parsed flags from byte 10 (RAM sizes for NES 2.0, unofficial TV system, PRG-RAM and
+    /// bus conflict bits for iNES)
+    pub flags_10: Flags10,
     /// Parsed flags from byte 11 (NES 2.0 specific)
     pub flags_11: Flags11Nes2,
     /// Parsed flags from byte 12 (NES 2.0 specific)
@@ -216,11 +288,76 @@ pub struct InesHeader {
 }
 
 impl InesHeader {
+    /// Nametable arrangement given by the header bits and the mapper number.
+    ///
+    /// The alternative nametable bit means four-screen VRAM on most boards, but selects
+    /// one-screen mirroring on UNROM 512 (mapper 30, where four-screen needs both bits) and
+    /// Magic Floor (mapper 218).
+    pub fn mirroring(&self) -> Mirroring {
+        let vertical = self.flags_6.nametable();
+        match (self.mapper, self.flags_6.alternative_nametable()) {
+            (30, true) if vertical => Mirroring::FourScreen,
+            (30, true) => Mirroring::MapperControlled,
+            // Magic Floor ties CIRAM A10 to PPU A12 or A13, which stay 0 or 1 for nametable fetches
+            (218, true) if vertical => Mirroring::SingleScreenB,
+            (218, true) => Mirroring::SingleScreenA,
+            (_, true) => Mirroring::FourScreen,
+            (mapper, false) if mapper_controls_mirroring(mapper) => Mirroring::MapperControlled,
+            _ if vertical => Mirroring::Vertical,
+            _ => Mirroring::Horizontal,
+        }
+    }
+
+    /// TV system the game is made for. NES 2.0 states it in byte 12; iNES 1.0 headers may
+    /// have the PAL bit in byte 9 or the unofficial TV system in byte 10, and are taken to
+    /// be NTSC when neither says otherwise.
+    pub fn timing_mode(&self) -> TimingMode {
+        match (self.flags_9, self.flags_10) {
+            (Flags9::Ines(flags_9), _) if flags_9.pal() => TimingMode::PAL,
+            (Flags9::Ines(_), Flags10::Ines(flags_10)) => match flags_10.tv_system() {
+                0 => TimingMode::NTSC,
+                2 => TimingMode::PAL,
+                _ => TimingMode::MultipleRegions,
+            },
+            _ => self.flags_12.timing_mode(),
+        }
+    }
+
+    /// Clock rates and frame layout of the console the game is made for
+    pub fn timing_parameters(&self) -> TimingParameters {
+        TimingParameters::for_mode(self.timing_mode())
+    }
+
+    /// False if an iNES 1.0 header says the board has nothing at $6000-$7FFF (byte 10 bit 4)
+    pub fn prg_ram_present(&self) -> bool {
+        match self.flags_10 {
+            Flags10::Ines(flags_10) => !flags_10.no_prg_ram(),
+            Flags10::Nes2(_) => true,
+        }
+    }
+
+    /// The board has bus conflicts: writes to ROM are ANDed with the byte stored there.
+    /// NES 2.0 gives this as submapper 2 of UxROM, CNROM and AxROM; iNES 1.0 in byte 10 bit 5.
+    pub fn bus_conflicts(&self) -> bool {
+        match self.flags_10 {
+            Flags10::Ines(flags_10) => flags_10.bus_conflicts(),
+            Flags10::Nes2(_) => matches!(self.mapper, 2 | 3 | 7) && self.submapper == 2,
+        }
+    }
+
     /// Parse a 16-byte iNES/NES2 header from `bytes` (must be at least 16 bytes long).
     ///
     /// This will detect NES 2.0 via the magic bits in header[7] and populate the
-    /// extended PRG/CHR sizes when present.
+    /// extended PRG/CHR sizes when present. Older headers are told apart as archaic iNES,
+    /// iNES 0.7 or iNES 1.0, and the bytes they leave undefined are ignored.
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, RomParseError> {
+        Self::from_bytes_with_report(bytes).map(|(header, _)| header)
+    }
+
+    /// Parse a header and report the garbage worked around in it. Nothing in a header with
+    /// the right magic is beyond repair, so the report only holds warnings.
+    pub fn from_bytes_with_report(bytes: &[u8]) -> Result<(Self, ValidationReport), RomParseError> {
+        let mut report = ValidationReport::new();
         if bytes.len() < 16 {
             return Err(RomParseError::HeaderTooShort);
         }
@@ -229,16 +366,29 @@ impl InesHeader {
             return Err(RomParseError::HeaderInvalidMagic);
         }
 
+        // Older versions of the iNES emulator ignored bytes 7-15, and several ROM management
+        // tools wrote messages in there ("DiskDude!" being the most common), which would add
+        // to the mapper number. Bytes the detected format does not define are zeroed.
+        let format = detect_format(bytes);
+        let garbage = match format {
+            HeaderFormat::ArchaicINes => 7,
+            HeaderFormat::INes07 => 8,
+            HeaderFormat::INes => 11,
+            HeaderFormat::Nes2 => 16,
+        };
+        let mut masked: [u8; 16] = bytes[..16].try_into().unwrap();
+        if let Some(offset) = (garbage..16).find(|&offset| masked[offset] != 0) {
+            report.warn(offset, Problem::ReservedBytesNotZero);
+            masked[garbage..].fill(0);
+        }
+        let bytes = &masked;
+
         // Parse flags 6 (mapper low nibble and mirroring)
         let flags_6 = Flags6::from_bits(bytes[6]);
         // Parse flags 7 (mapper high nibble and format)
         let flags_7 = Flags7::from_bits(bytes[7]);
         // Parse flags 8 (NES 2.0 mapper/submapper)
         let flags_8 = Flags8Nes2::from_bits(bytes[8]);
-        // Parse flags 9 (NES 2.0 extended ROM size MSBs)
-        let flags_9 = Flags9Nes2::from_bits(bytes[9]);
-        // Parse flags 10 (NES 2.0 RAM size shift counts)
-        let flags_10 = Flags10Nes2::from_bits(bytes[10]);
         // Parse flags 11 (NES 2.0 RAM size shift counts)
         let flags_11 = Flags11Nes2::from_bits(bytes[11]);
         // Parse flags 12 (NES 2.0 timing mode)
@@ -246,21 +396,19 @@ impl InesHeader {
         // Parse flags 13 (NES 2.0 console type)
         let flags_13 = Flags13Nes2::from_bits(bytes[13], flags_7);
 
-        // Detect NES 2.0: bits 2-3 of byte 7 equal 2 (binary 10)
-        let is_nes2 = flags_7.format() == 2;
+        if format == HeaderFormat::Nes2 {
+            // Parse flags 9 (NES 2.0 extended ROM size MSBs)
+            let flags_9 = Flags9Nes2::from_bits(bytes[9]);
+            // Parse flags 10 (NES 2.0 RAM size shift counts)
+            let flags_10 = Flags10Nes2::from_bits(bytes[10]);
 
-        if is_nes2 {
-            // Calculate extended ROM sizes using MSB from flags 9
-            let prg_lsb = bytes[4] as u32;
-            let chr_lsb = bytes[5] as u32;
-            let prg_chunks = ((flags_9.prg_rom_msb() as u32) << 8) | prg_lsb;
-            let chr_chunks = ((flags_9.chr_rom_msb() as u32) << 8) | chr_lsb;
-
-            // Convert chunks to bytes:
-            // PRG ROM: 16 KiB units
-            // CHR ROM: 8 KiB units
-            let prg_rom_size = prg_chunks * 16 * 1024;
-            let chr_rom_size = chr_chunks * 8 * 1024;
+            // Calculate extended ROM sizes using MSB from flags 9, in 16 KiB units for PRG ROM
+            // and 8 KiB units for CHR ROM
+            let rom_size = |lsb, msb, unit| {
+                u32::try_from(nes2_rom_size(lsb, msb, unit)).map_err(|_| RomParseError::InvalidRomSize)
+            };
+            let prg_rom_size = rom_size(bytes[4], flags_9.prg_rom_msb(), 16 * 1024)?;
+            let chr_rom_size = rom_size(bytes[5], flags_9.chr_rom_msb(), 8 * 1024)?;
 
             // NES 2.0 mapper combines bits from flags 6, 7, and 8
             let mapper = ((flags_8.mapper_high2() as u16) << 8) |
@@ -294,7 +442,14 @@ impl InesHeader {
                 64 << flags_11.chr_nvram_shift()  // NES 2.0 uses 64-byte units
             };
 
-            Ok(Self {
+            // unused bits of the timing, misc ROM count and default expansion device bytes
+            for (offset, reserved) in [(12, 0xFC), (14, 0xFC), (15, 0xC0)] {
+                if bytes[offset] & reserved != 0 {
+                    report.warn(offset, Problem::ReservedBytesNotZero);
+                }
+            }
+
+            let header = Self {
                 format: HeaderFormat::Nes2,
                 prg_rom_size,
                 chr_rom_size,
@@ -304,25 +459,18 @@ impl InesHeader {
                 chr_ram_size: RamSize::Nes2 { ram: chr_ram_size, nvram: chr_nvram_size },
                 flags_6,
                 flags_7,
-                flags_9,
-                flags_10,
+                flags_9: Flags9::Nes2(flags_9),
+                flags_10: Flags10::Nes2(flags_10),
                 flags_11,
                 flags_12,
                 flags_13,
-            })
+            };
+            Ok((header, report))
         } else {
             // iNES format: simpler mapper and RAM size handling
-
-            //Older versions of the iNES emulator ignored bytes 7-15, and several ROM management tools wrote messages in there.
-            // Commonly, these will be filled with "DiskDude!", which results in 64 being added to the mapper number.
-            // A general rule of thumb: if the last 4 bytes are not all zero, and the header is not marked for NES 2.0 format,
-            // an emulator should either mask off the upper 4 bits of the mapper number or simply refuse to load the ROM
-            let diskdude_signature = &bytes[12..16];
-            let mapper = if diskdude_signature != [0, 0, 0, 0] {
-                flags_6.mapper_low() as u16
-            } else {
-                ((flags_7.mapper_high() as u16) >> 4) | (flags_6.mapper_low() as u16)
-            };
+            let flags_9 = Flags9Ines::from_bits(bytes[9]);
+            let flags_10 = Flags10Ines::from_bits(bytes[10]);
+            let mapper = ((flags_7.mapper_high() as u16) << 4) | (flags_6.mapper_low() as u16);
 
             // Convert chunks to bytes:
             // PRG ROM: 16 KiB units
@@ -330,12 +478,9 @@ impl InesHeader {
             let prg_rom_size = (bytes[4] as u32) * 16 * 1024;
             let chr_rom_size = (bytes[5] as u32) * 8 * 1024;
 
-            // In iNES format, byte 8 specifies RAM size in 8KB units
-            let prg_ram_size = if bytes[8] == 0 {
-                8 * 1024  // Default to 8KB if unspecified
-            } else {
-                (bytes[8] as u32) * 8 * 1024  // Convert to bytes
-            };
+            // In iNES format, byte 8 specifies RAM size in 8KB units. 0 leaves it unspecified;
+            // CartridgeMemory then picks the usual size for the mapper
+            let prg_ram_size = (bytes[8] as u32) * 8 * 1024;
 
             // In iNES format, if chr_rom_size is 0, we assume 8KB of CHR RAM as default 
             // the mapper may determine the CHR RAM size
@@ -345,8 +490,8 @@ impl InesHeader {
                 0  // CHR RAM not used
             };
 
-            Ok(Self {
-                format: HeaderFormat::INes,
+            let header = Self {
+                format,
                 prg_rom_size,
                 chr_rom_size,
                 mapper,
@@ -355,12 +500,67 @@ impl InesHeader {
                 chr_ram_size: RamSize::Ines(chr_ram_size),
                 flags_6,
                 flags_7,
-                flags_9,
-                flags_10,
+                flags_9: Flags9::Ines(flags_9),
+                flags_10: Flags10::Ines(flags_10),
                 flags_11,
                 flags_12,
                 flags_13,
-            })
+            };
+            Ok((header, report))
         }
     }
-}
\ No newline at end of file
+}
+
+/// Tell the header formats apart the way the NESdev wiki recommends: NES 2.0 is only
+/// trusted if the sizes it gives fit in the file, and bytes 12-15 must be clear for iNES 1.0.
+fn detect_format(bytes: &[u8]) -> HeaderFormat {
+    match bytes[7] & 0x0C {
+        0x08 if nes2_sizes_fit(bytes) => HeaderFormat::Nes2,
+        0x00 if bytes[12..16] == [0; 4] => HeaderFormat::INes,
+        0x00 => HeaderFormat::INes07,
+        _ => HeaderFormat::ArchaicINes,
+    }
+}
+
+/// The NES 2.0 ROM sizes, including byte 9, fit in `bytes`. A bare header has nothing to
+/// check against and passes.
+fn nes2_sizes_fit(bytes: &[u8]) -> bool {
+    if bytes.len() <= 16 {
+        return true;
+    }
+    let trainer = if bytes[6] & 0x04 != 0 { 512 } else { 0 };
+    let prg = nes2_rom_size(bytes[4], bytes[9] & 0x0F, 16 * 1024);
+    let chr = nes2_rom_size(bytes[5], bytes[9] >> 4, 8 * 1024);
+    16 + trainer + prg + chr <= bytes.len() as u128
+}
+
+/// NES 2.0 ROM size in bytes from its size byte and byte 9 nibble. A nibble of 0xF selects
+/// exponent-multiplier notation, 2^E * (2M+1) bytes with E and M packed as EEEEEEMM.
+fn nes2_rom_size(lsb: u8, msb: u8, unit: u128) -> u128 {
+    match msb {
+        0x0F => (1u128 << (lsb >> 2)) * ((lsb & 0x03) as u128 * 2 + 1),
+        msb => ((msb as u128) << 8 | lsb as u128) * unit,
+    }
+}
+
+/// Inverse of `nes2_rom_size`, returning the size byte and byte 9 nibble: a count of
+/// `unit`s up to 0xEFF, otherwise the exponent form. Sizes read from a header, or halved
+/// from one, always fit one or the other.
+pub(crate) fn encode_nes2_rom_size(size: usize, unit: usize) -> (u8, u8) {
+    if size.is_multiple_of(unit) && size / unit <= 0xEFF {
+        let units = size / unit;
+        return (units as u8, (units >> 8) as u8);
+    }
+    let exponent = size.trailing_zeros();
+    let multiplier = size >> exponent;
+    ((exponent as u8) << 2 | (multiplier / 2) as u8 & 0x03, 0x0F)
+}
+
+/// Mappers with a mirroring register (or nametables banked through CHR registers)
+fn mapper_controls_mirroring(mapper: u16) -> bool {
+    matches!(
+        mapper,
+        1 | 4 | 5 | 7 | 9 | 10 | 16 | 18 | 19 | 21 | 22 | 23 | 24 | 25 | 26 | 32 | 33 | 48 | 64
+            | 65 | 67 | 68 | 69 | 75 | 80 | 82 | 85 | 118 | 119 | 153 | 157 | 159
+    )
+}
@@ -0,0 +1,237 @@
+use crate::nes::cartridge::Cartridge;
+use crate::nes::header::{InesHeader, Mirroring, RamSize};
+use crate::nes::mapper::eeprom::{EepromKind, I2cEeprom};
+use crate::nes::mapper::{bank_offset, ciram_index, Ciram, Mapper};
+use crate::nes::memory::{self, chr_nvram_len, CartridgeMemory};
+
+
+const PRG_BANK_SIZE: usize = 16 * 1024;
+const CHR_BANK_SIZE: usize = 1024;
+
+/// Bandai FCG-1/FCG-2 and LZ93D50 boards: iNES mappers 16, 153, 157 and 159
+///
+/// - Mapper 16 submapper 4 is the FCG-1/2 (registers at $6000, no EEPROM), submapper 5 the
+///   LZ93D50 with a 24C02; submapper 0 decodes registers at both $6000 and $8000.
+/// - Mapper 153 is the LZ93D50 with 8 KiB of battery-backed WRAM and a 256 KiB PRG outer bank.
+/// - Mapper 157 is the Datach Joint ROM System (24C02).
+/// - Mapper 159 is the LZ93D50 with an X24C01.
+pub struct BandaiFcg {
+    memory: CartridgeMemory,
+    eeprom: Option<I2cEeprom>,
+
+    /// Registers decoded at $6000-$7FFF
+    registers_at_6000: bool,
+    /// Registers decoded at $8000-$FFFF
+    registers_at_8000: bool,
+    /// LZ93D50 latches the IRQ counter; the FCG-1/2 writes it directly
+    irq_latched: bool,
+    /// Mapper 153: CHR registers select a 256 KiB PRG outer bank instead of CHR
+    outer_prg_bank: bool,
+
+    chr_banks: [u8; 8],
+    prg_bank: u8,
+    mirroring: u8,
+    irq_enabled: bool,
+    irq_counter: u16,
+    irq_latch: u16,
+    irq_pending: bool,
+    /// $800D bit 5 on mapper 153
+    prg_ram_enabled: bool,
+    eeprom_read_enabled: bool,
+}
+
+/// EEPROM on the board. NES 2.0 states its size as PRG-NVRAM; iNES relies on the mapper number.
+fn eeprom_kind(header: &InesHeader) -> Option<EepromKind> {
+    match (header.mapper, header.submapper, header.prg_ram_size) {
+        (153, _, _) | (16, 4, _) => None,
+        (_, _, RamSize::Nes2 { nvram: 128, .. }) => Some(EepromKind::X24C01),
+        (_, _, RamSize::Nes2 { nvram: 256, .. }) => Some(EepromKind::C24C02),
+        (_, _, RamSize::Nes2 { nvram: 0, .. }) => None,
+        (159, _, _) => Some(EepromKind::X24C01),
+        _ => Some(EepromKind::C24C02),
+    }
+}
+
+/// Volatile and battery-backed PRG-RAM. Only the LZ93D50 with WRAM (mapper 153) has any;
+/// the others' NVRAM is the EEPROM.
+fn prg_ram_sizes(header: &InesHeader) -> (usize, usize) {
+    if header.mapper == 153 { memory::prg_ram_sizes(header) } else { (0, 0) }
+}
+
+/// Size of the data `save_data` returns: the EEPROM, or the NVRAM of boards without one
+pub(crate) fn save_size(header: &InesHeader) -> usize {
+    match eeprom_kind(header) {
+        Some(kind) => kind.size(),
+        None => prg_ram_sizes(header).1 + chr_nvram_len(header),
+    }
+}
+
+impl BandaiFcg {
+    pub fn new(cartridge: &Cartridge) -> Self {
+        let header = &cartridge.ines_header;
+
+        let (ram, nvram) = prg_ram_sizes(header);
+        let memory = CartridgeMemory::with_prg_ram(cartridge, ram, nvram);
+
+        Self {
+            memory,
+            eeprom: eeprom_kind(header).map(I2cEeprom::new),
+            registers_at_6000: header.mapper == 16 && header.submapper != 5,
+            registers_at_8000: !(header.mapper == 16 && header.submapper == 4),
+            irq_latched: !(header.mapper == 16 && header.submapper == 4),
+            outer_prg_bank: header.mapper == 153,
+            chr_banks: [0; 8],
+            prg_bank: 0,
+            mirroring: 0,
+            irq_enabled: false,
+            irq_counter: 0,
+            irq_latch: 0,
+            irq_pending: false,
+            prg_ram_enabled: false,
+            eeprom_read_enabled: false,
+        }
+    }
+
+    fn prg_offset(&self, addr: u16) -> usize {
+        // mapper 153 wires bit 0 of the CHR registers to PRG A18
+        let outer = if self.outer_prg_bank {
+            (self.chr_banks.iter().fold(0, |acc, bank| acc | bank) as usize & 1) << 4
+        } else {
+            0
+        };
+        let bank = match addr {
+            0x8000..=0xBFFF => outer | (self.prg_bank as usize & 0x0F),
+            _ if self.outer_prg_bank => outer | 0x0F,
+            _ => (self.memory.prg_rom.len() / PRG_BANK_SIZE).max(1) - 1,
+        };
+        bank_offset(&self.memory.prg_rom, bank, PRG_BANK_SIZE, addr as usize)
+    }
+
+    fn chr_offset(&self, addr: u16) -> usize {
+        if self.outer_prg_bank {
+            return addr as usize % self.memory.chr.len();
+        }
+        let bank = self.chr_banks[addr as usize / CHR_BANK_SIZE];
+        bank_offset(&self.memory.chr, bank as usize, CHR_BANK_SIZE, addr as usize)
+    }
+
+    fn prg_ram_accessible(&self) -> bool {
+        self.prg_ram_enabled && !self.memory.prg_ram.is_empty()
+    }
+
+    fn nametable_mirroring(&self) -> Mirroring {
+        match self.mirroring & 3 {
+            0 => Mirroring::Vertical,
+            1 => Mirroring::Horizontal,
+            2 => Mirroring::SingleScreenA,
+            _ => Mirroring::SingleScreenB,
+        }
+    }
+
+    fn write_register(&mut self, register: u16, value: u8) {
+        match register {
+            0x0..=0x7 => self.chr_banks[register as usize] = value,
+            0x8 => self.prg_bank = value,
+            0x9 => self.mirroring = value,
+            0xA => {
+                self.irq_enabled = value & 0x01 != 0;
+                self.irq_pending = false;
+                if self.irq_latched {
+                    self.irq_counter = self.irq_latch;
+                }
+            }
+            0xB | 0xC => {
+                let shift = (register - 0xB) * 8;
+                let target = if self.irq_latched { &mut self.irq_latch } else { &mut self.irq_counter };
+                *target = (*target & !(0xFF << shift)) | ((value as u16) << shift);
+            }
+            0xD => {
+                self.prg_ram_enabled = value & 0x20 != 0;
+                self.eeprom_read_enabled = value & 0x80 != 0;
+                if let Some(eeprom) = &mut self.eeprom {
+                    eeprom.write_lines(value & 0x20 != 0, value & 0x40 != 0);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Mapper for BandaiFcg {
+    fn cpu_read(&mut self, addr: u16) -> Option<u8> {
+        match addr {
+            0x6000..=0x7FFF if self.outer_prg_bank => self
+                .prg_ram_accessible()
+                .then(|| self.memory.prg_ram[(addr as usize - 0x6000) % self.memory.prg_ram.len()]),
+            // EEPROM data out appears on D4
+            0x6000..=0x7FFF => self.eeprom.as_ref().map(|eeprom| {
+                let sda = !self.eeprom_read_enabled || eeprom.data_out();
+                (sda as u8) << 4
+            }),
+            0x8000..=0xFFFF if !self.memory.prg_rom.is_empty() => {
+                Some(self.memory.prg_rom[self.prg_offset(addr)])
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x6000..=0x7FFF if self.outer_prg_bank && self.prg_ram_accessible() => {
+                let len = self.memory.prg_ram.len();
+                self.memory.prg_ram[(addr as usize - 0x6000) % len] = value;
+            }
+            0x6000..=0x7FFF if self.outer_prg_bank => {}
+            0x6000..=0x7FFF if self.registers_at_6000 => self.write_register(addr & 0x0F, value),
+            0x8000..=0xFFFF if self.registers_at_8000 => self.write_register(addr & 0x0F, value),
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16, ciram: &Ciram) -> u8 {
+        match addr & 0x3FFF {
+            0x0000..=0x1FFF => self.memory.chr.get(self.chr_offset(addr)).copied().unwrap_or(0),
+            addr => ciram[ciram_index(addr, self.nametable_mirroring())],
+        }
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8, ciram: &mut Ciram) {
+        match addr & 0x3FFF {
+            0x0000..=0x1FFF => {
+                if self.memory.chr_is_ram {
+                    let offset = self.chr_offset(addr);
+                    self.memory.chr[offset] = value;
+                }
+            }
+            addr => ciram[ciram_index(addr, self.nametable_mirroring())] = value,
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.irq_pending
+    }
+
+    fn cpu_clock(&mut self) {
+        if !self.irq_enabled {
+            return;
+        }
+        if self.irq_counter == 0 {
+            self.irq_pending = true;
+        }
+        self.irq_counter = self.irq_counter.wrapping_sub(1);
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        match &self.eeprom {
+            Some(eeprom) => Some(eeprom.data().to_vec()),
+            None => self.memory.save_data(),
+        }
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        match &mut self.eeprom {
+            Some(eeprom) => eeprom.load(data),
+            None => self.memory.load_save_data(data),
+        }
+    }
+}
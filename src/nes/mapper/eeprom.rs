@@ -0,0 +1,225 @@
+/// Serial EEPROM chips found on Bandai boards
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EepromKind {
+    /// Xicor X24C01, 128 bytes: 7-bit address sent directly after START, bits sent LSB first
+    X24C01,
+    /// 24C02, 256 bytes: standard I²C device address byte, then an 8-bit word address, MSB first
+    C24C02,
+}
+
+impl EepromKind {
+    pub fn size(self) -> usize {
+        match self {
+            EepromKind::X24C01 => 128,
+            EepromKind::C24C02 => 256,
+        }
+    }
+
+    fn page_mask(self) -> u8 {
+        match self {
+            EepromKind::X24C01 => 0x03,
+            EepromKind::C24C02 => 0x07,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EepromMode {
+    Idle,
+    /// Receiving the 24C02 device address byte
+    Device,
+    /// Receiving the word address
+    Address,
+    /// Receiving data bytes
+    Write,
+    /// Sending data bytes
+    Read,
+}
+
+/// A bit-banged I²C EEPROM, driven through its SCL and SDA lines
+#[derive(Debug, Clone)]
+pub struct I2cEeprom {
+    kind: EepromKind,
+    data: Vec<u8>,
+    mode: EepromMode,
+    address: u8,
+    shift: u8,
+    /// Clock pulses seen in the current 9-bit frame (8 data bits + acknowledge)
+    clocks: u8,
+    /// The chip is driving the acknowledge bit for a byte it received
+    acknowledging: bool,
+    /// Master acknowledged the byte just read
+    continue_read: bool,
+    scl: bool,
+    sda: bool,
+    /// Level the chip drives on SDA (true = released)
+    output: bool,
+}
+
+impl I2cEeprom {
+    pub fn new(kind: EepromKind) -> Self {
+        Self {
+            kind,
+            data: vec![0xFF; kind.size()],
+            mode: EepromMode::Idle,
+            address: 0,
+            shift: 0,
+            clocks: 0,
+            acknowledging: false,
+            continue_read: false,
+            scl: false,
+            sda: true,
+            output: true,
+        }
+    }
+
+    pub fn kind(&self) -> EepromKind {
+        self.kind
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Replace the contents, truncating or padding with $FF to the chip size
+    pub fn load(&mut self, data: &[u8]) {
+        let len = data.len().min(self.data.len());
+        self.data[..len].copy_from_slice(&data[..len]);
+        self.data[len..].fill(0xFF);
+    }
+
+    /// Level of SDA as driven by the chip
+    pub fn data_out(&self) -> bool {
+        self.output
+    }
+
+    /// Update the SCL and SDA lines as driven by the host
+    pub fn write_lines(&mut self, scl: bool, sda: bool) {
+        if self.scl && scl && self.sda && !sda {
+            self.start();
+        } else if self.scl && scl && !self.sda && sda {
+            self.stop();
+        } else if !self.scl && scl {
+            self.rising_edge(sda);
+        } else if self.scl && !scl {
+            self.falling_edge();
+        }
+        self.scl = scl;
+        self.sda = sda;
+    }
+
+    fn start(&mut self) {
+        self.mode = match self.kind {
+            EepromKind::X24C01 => EepromMode::Address,
+            EepromKind::C24C02 => EepromMode::Device,
+        };
+        self.shift = 0;
+        self.clocks = 0;
+        self.acknowledging = false;
+        self.output = true;
+    }
+
+    fn stop(&mut self) {
+        self.mode = EepromMode::Idle;
+        self.output = true;
+    }
+
+    fn rising_edge(&mut self, sda: bool) {
+        if self.mode == EepromMode::Idle {
+            return;
+        }
+        self.clocks += 1;
+        if self.clocks > 8 {
+            // acknowledge clock: when reading, the master acks to ask for another byte
+            if self.mode == EepromMode::Read && !self.acknowledging {
+                self.continue_read = !sda;
+            }
+            return;
+        }
+        if self.mode != EepromMode::Read {
+            let bit = sda as u8;
+            match self.kind {
+                EepromKind::X24C01 => self.shift |= bit << (self.clocks - 1),
+                EepromKind::C24C02 => self.shift = (self.shift << 1) | bit,
+            }
+        }
+    }
+
+    fn falling_edge(&mut self) {
+        if self.mode == EepromMode::Idle {
+            return;
+        }
+        if self.acknowledging {
+            if self.clocks > 8 {
+                // acknowledge done: release SDA, or put the first bit of a read on it
+                self.acknowledging = false;
+                self.clocks = 0;
+                self.shift = 0;
+                self.output = if self.mode == EepromMode::Read { self.read_bit(0) } else { true };
+            }
+            return;
+        }
+        match (self.mode, self.clocks) {
+            (EepromMode::Read, 1..=7) => self.output = self.read_bit(self.clocks),
+            // release SDA for the master's acknowledge
+            (EepromMode::Read, 8) => self.output = true,
+            (EepromMode::Read, _) => {
+                self.clocks = 0;
+                if self.continue_read {
+                    self.address = ((self.address as usize + 1) % self.data.len()) as u8;
+                    self.output = self.read_bit(0);
+                } else {
+                    self.mode = EepromMode::Idle;
+                    self.output = true;
+                }
+            }
+            (_, 8) => {
+                self.receive_byte();
+                if self.mode != EepromMode::Idle {
+                    self.acknowledging = true;
+                    self.output = false;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Bit `index` (in transmission order) of the byte at the current address
+    fn read_bit(&self, index: u8) -> bool {
+        let byte = self.data[self.address as usize % self.data.len()];
+        match self.kind {
+            EepromKind::X24C01 => byte & (1 << index) != 0,
+            EepromKind::C24C02 => byte & (0x80 >> index) != 0,
+        }
+    }
+
+    fn receive_byte(&mut self) {
+        let byte = self.shift;
+        match (self.kind, self.mode) {
+            (EepromKind::C24C02, EepromMode::Device) => {
+                if byte & 0xF0 != 0xA0 {
+                    self.mode = EepromMode::Idle;
+                } else if byte & 1 != 0 {
+                    self.mode = EepromMode::Read;
+                } else {
+                    self.mode = EepromMode::Address;
+                }
+            }
+            (EepromKind::X24C01, EepromMode::Address) => {
+                self.address = byte & 0x7F;
+                self.mode = if byte & 0x80 != 0 { EepromMode::Read } else { EepromMode::Write };
+            }
+            (_, EepromMode::Address) => {
+                self.address = byte;
+                self.mode = EepromMode::Write;
+            }
+            (_, EepromMode::Write) => {
+                let len = self.data.len();
+                self.data[self.address as usize % len] = byte;
+                let mask = self.kind.page_mask();
+                self.address = (self.address & !mask) | (self.address.wrapping_add(1) & mask);
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use crate::nes::cartridge::Cartridge;
+use crate::nes::header::Mirroring;
+use crate::nes::mapper::{bank_offset, ciram_index, Ciram, ExpansionAudio, Mapper};
+use crate::nes::memory::CartridgeMemory;
+
+
+const PRG_BANK_SIZE: usize = 8 * 1024;
+const CHR_BANK_SIZE: usize = 1024;
+
+/// Sunsoft 5B expansion audio registers (a YM2149F core), written through the
+/// $C000 address / $E000 data port
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sunsoft5BAudio {
+    /// Last value written to the address port
+    pub address: u8,
+    /// The internal register file ($00-$0F)
+    pub registers: [u8; 16],
+}
+
+impl Sunsoft5BAudio {
+    /// 12-bit tone period of channel `index` (0-2)
+    pub fn tone_period(&self, index: usize) -> u16 {
+        self.registers[index * 2] as u16 | ((self.registers[index * 2 + 1] as u16 & 0x0F) << 8)
+    }
+
+    /// 5-bit noise period ($06)
+    pub fn noise_period(&self) -> u8 {
+        self.registers[0x06] & 0x1F
+    }
+
+    /// Tone enabled for channel `index`; the $07 mixer bits are active low
+    pub fn tone_enabled(&self, index: usize) -> bool {
+        self.registers[0x07] & (1 << index) == 0
+    }
+
+    /// Noise enabled for channel `index`; the $07 mixer bits are active low
+    pub fn noise_enabled(&self, index: usize) -> bool {
+        self.registers[0x07] & (8 << index) == 0
+    }
+
+    /// 4-bit volume of channel `index` ($08-$0A)
+    pub fn volume(&self, index: usize) -> u8 {
+        self.registers[0x08 + index] & 0x0F
+    }
+
+    /// Channel `index` follows the envelope instead of its fixed volume
+    pub fn uses_envelope(&self, index: usize) -> bool {
+        self.registers[0x08 + index] & 0x10 != 0
+    }
+
+    /// 16-bit envelope period ($0B-$0C)
+    pub fn envelope_period(&self) -> u16 {
+        u16::from_le_bytes([self.registers[0x0B], self.registers[0x0C]])
+    }
+
+    /// Envelope shape ($0D)
+    pub fn envelope_shape(&self) -> u8 {
+        self.registers[0x0D] & 0x0F
+    }
+
+    fn write_data(&mut self, value: u8) {
+        // the upper address nibble must be zero for the write to land
+        if self.address & 0xF0 == 0 {
+            self.registers[self.address as usize] = value;
+        }
+    }
+}
+
+/// Sunsoft FME-7, 5A and 5B, iNES mapper 69
+pub struct Fme7 {
+    memory: CartridgeMemory,
+
+    /// $8000 command
+    command: u8,
+    /// Commands $0-$7
+    chr_banks: [u8; 8],
+    /// Command $8: bank at $6000 plus RAM select/enable bits
+    prg_bank_6000: u8,
+    /// Commands $9-$B
+    prg_banks: [u8; 3],
+    /// Command $C
+    mirroring: u8,
+    irq_enabled: bool,
+    irq_counter_enabled: bool,
+    irq_counter: u16,
+    irq_pending: bool,
+    audio: Sunsoft5BAudio,
+}
+
+impl Fme7 {
+    pub fn new(cartridge: &Cartridge) -> Self {
+        Self {
+            memory: CartridgeMemory::new(cartridge),
+            command: 0,
+            chr_banks: [0; 8],
+            prg_bank_6000: 0,
+            prg_banks: [0; 3],
+            mirroring: 0,
+            irq_enabled: false,
+            irq_counter_enabled: false,
+            irq_counter: 0,
+            irq_pending: false,
+            audio: Sunsoft5BAudio::default(),
+        }
+    }
+
+    /// Expansion audio register state
+    pub fn audio(&self) -> &Sunsoft5BAudio {
+        &self.audio
+    }
+
+    fn prg_offset(&self, addr: u16) -> usize {
+        let bank = match (addr - 0x8000) as usize / PRG_BANK_SIZE {
+            slot @ 0..=2 => self.prg_banks[slot] as usize & 0x3F,
+            _ => (self.memory.prg_rom.len() / PRG_BANK_SIZE).max(1) - 1,
+        };
+        bank_offset(&self.memory.prg_rom, bank, PRG_BANK_SIZE, addr as usize)
+    }
+
+    fn read_6000(&self, addr: u16) -> Option<u8> {
+        let bank = self.prg_bank_6000 as usize & 0x3F;
+        match self.prg_bank_6000 & 0xC0 {
+            // ROM selected
+            0x00 | 0x80 if !self.memory.prg_rom.is_empty() => {
+                let rom = &self.memory.prg_rom;
+                Some(rom[bank_offset(rom, bank, PRG_BANK_SIZE, addr as usize)])
+            }
+            // RAM selected and enabled
+            0xC0 if !self.memory.prg_ram.is_empty() => {
+                let ram = &self.memory.prg_ram;
+                Some(ram[bank_offset(ram, bank, PRG_BANK_SIZE, addr as usize)])
+            }
+            _ => None,
+        }
+    }
+
+    fn chr_offset(&self, addr: u16) -> usize {
+        let bank = self.chr_banks[addr as usize / CHR_BANK_SIZE];
+        bank_offset(&self.memory.chr, bank as usize, CHR_BANK_SIZE, addr as usize)
+    }
+
+    fn nametable_mirroring(&self) -> Mirroring {
+        match self.mirroring & 3 {
+            0 => Mirroring::Vertical,
+            1 => Mirroring::Horizontal,
+            2 => Mirroring::SingleScreenA,
+            _ => Mirroring::SingleScreenB,
+        }
+    }
+
+    fn write_parameter(&mut self, value: u8) {
+        match self.command {
+            0x0..=0x7 => self.chr_banks[self.command as usize] = value,
+            0x8 => self.prg_bank_6000 = value,
+            0x9..=0xB => self.prg_banks[(self.command - 0x9) as usize] = value,
+            0xC => self.mirroring = value,
+            0xD => {
+                self.irq_enabled = value & 0x01 != 0;
+                self.irq_counter_enabled = value & 0x80 != 0;
+                self.irq_pending = false;
+            }
+            0xE => self.irq_counter = (self.irq_counter & 0xFF00) | value as u16,
+            _ => self.irq_counter = (self.irq_counter & 0x00FF) | ((value as u16) << 8),
+        }
+    }
+}
+
+impl Mapper for Fme7 {
+    fn cpu_read(&mut self, addr: u16) -> Option<u8> {
+        match addr {
+            0x6000..=0x7FFF => self.read_6000(addr),
+            0x8000..=0xFFFF if !self.memory.prg_rom.is_empty() => {
+                Some(self.memory.prg_rom[self.prg_offset(addr)])
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x6000..=0x7FFF if self.prg_bank_6000 & 0xC0 == 0xC0 && !self.memory.prg_ram.is_empty() => {
+                let bank = self.prg_bank_6000 as usize & 0x3F;
+                let offset = bank_offset(&self.memory.prg_ram, bank, PRG_BANK_SIZE, addr as usize);
+                self.memory.prg_ram[offset] = value;
+            }
+            0x8000..=0x9FFF => self.command = value & 0x0F,
+            0xA000..=0xBFFF => self.write_parameter(value),
+            0xC000..=0xDFFF => self.audio.address = value,
+            0xE000..=0xFFFF => self.audio.write_data(value),
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16, ciram: &Ciram) -> u8 {
+        match addr & 0x3FFF {
+            0x0000..=0x1FFF => self.memory.chr.get(self.chr_offset(addr)).copied().unwrap_or(0),
+            addr => ciram[ciram_index(addr, self.nametable_mirroring())],
+        }
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8, ciram: &mut Ciram) {
+        match addr & 0x3FFF {
+            0x0000..=0x1FFF => {
+                if self.memory.chr_is_ram {
+                    let offset = self.chr_offset(addr);
+                    self.memory.chr[offset] = value;
+                }
+            }
+            addr => ciram[ciram_index(addr, self.nametable_mirroring())] = value,
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.irq_pending
+    }
+
+    fn cpu_clock(&mut self) {
+        if !self.irq_counter_enabled {
+            return;
+        }
+        self.irq_counter = self.irq_counter.wrapping_sub(1);
+        if self.irq_counter == 0xFFFF && self.irq_enabled {
+            self.irq_pending = true;
+        }
+    }
+
+    fn expansion_audio(&self) -> Option<ExpansionAudio<'_>> {
+        Some(ExpansionAudio::Sunsoft5B(&self.audio))
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        self.memory.save_data()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        self.memory.load_save_data(data);
+    }
+}
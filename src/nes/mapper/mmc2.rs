@@ -0,0 +1,136 @@
+use crate::nes::cartridge::Cartridge;
+use crate::nes::header::Mirroring;
+use crate::nes::mapper::{bank_offset, ciram_index, Ciram, Mapper};
+use crate::nes::memory::CartridgeMemory;
+
+
+const CHR_BANK_SIZE: usize = 4 * 1024;
+
+/// Nintendo MMC2 (PxROM, iNES mapper 9) and MMC4 (FxROM, iNES mapper 10).
+///
+/// Each 4 KiB pattern table has two CHR banks, selected by a latch that flips when the PPU
+/// fetches tile $FD or $FE from that table. The switch happens after the fetch, so the
+/// triggering tile itself still comes from the old bank.
+pub struct Mmc2 {
+    memory: CartridgeMemory,
+    /// MMC4 has 16 KiB PRG banking, PRG-RAM and wider latch trigger ranges
+    mmc4: bool,
+
+    prg_bank: u8,
+    /// [table][latch]: the $FD and $FE banks for each pattern table
+    chr_banks: [[u8; 2]; 2],
+    /// Per pattern table: false = $FD, true = $FE
+    latches: [bool; 2],
+    horizontal: bool,
+}
+
+impl Mmc2 {
+    pub fn new(cartridge: &Cartridge) -> Self {
+        let mmc4 = cartridge.ines_header.mapper == 10;
+
+        Self {
+            memory: CartridgeMemory::new(cartridge),
+            mmc4,
+            prg_bank: 0,
+            chr_banks: [[0; 2]; 2],
+            latches: [true; 2],
+            horizontal: false,
+        }
+    }
+
+    fn prg_offset(&self, addr: u16) -> usize {
+        // one switchable bank at $8000, the rest fixed to the end of PRG-ROM
+        let bank_size = if self.mmc4 { 0x4000 } else { 0x2000 };
+        let banks = (self.memory.prg_rom.len() / bank_size).max(1);
+        let slot = (addr as usize - 0x8000) / bank_size;
+        let slots = 0x8000 / bank_size;
+        let bank = if slot == 0 { self.prg_bank as usize } else { banks.saturating_sub(slots - slot) };
+        bank_offset(&self.memory.prg_rom, bank, bank_size, addr as usize)
+    }
+
+    fn chr_offset(&self, addr: u16) -> usize {
+        let table = (addr as usize >> 12) & 1;
+        let bank = self.chr_banks[table][self.latches[table] as usize];
+        bank_offset(&self.memory.chr, bank as usize, CHR_BANK_SIZE, addr as usize)
+    }
+
+    fn nametable_mirroring(&self) -> Mirroring {
+        if self.horizontal { Mirroring::Horizontal } else { Mirroring::Vertical }
+    }
+
+    /// Flip the CHR latches on fetches of tiles $FD/$FE
+    fn observe(&mut self, addr: u16) {
+        let addr = addr & 0x3FFF;
+        let table = (addr >> 12) as usize;
+        // MMC2 only watches the exact $0FD8/$0FE8 fetch for the first table
+        let exact = !self.mmc4 && table == 0;
+        match addr & 0x0FF8 {
+            0x0FD8 if table < 2 && (!exact || addr & 7 == 0) => self.latches[table] = false,
+            0x0FE8 if table < 2 && (!exact || addr & 7 == 0) => self.latches[table] = true,
+            _ => {}
+        }
+    }
+}
+
+impl Mapper for Mmc2 {
+    fn cpu_read(&mut self, addr: u16) -> Option<u8> {
+        match addr {
+            0x6000..=0x7FFF if !self.memory.prg_ram.is_empty() => {
+                Some(self.memory.prg_ram[(addr as usize - 0x6000) % self.memory.prg_ram.len()])
+            }
+            0x8000..=0xFFFF if !self.memory.prg_rom.is_empty() => {
+                Some(self.memory.prg_rom[self.prg_offset(addr)])
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x6000..=0x7FFF if !self.memory.prg_ram.is_empty() => {
+                let len = self.memory.prg_ram.len();
+                self.memory.prg_ram[(addr as usize - 0x6000) % len] = value;
+            }
+            0xA000..=0xAFFF => self.prg_bank = value & 0x0F,
+            0xB000..=0xBFFF => self.chr_banks[0][0] = value & 0x1F,
+            0xC000..=0xCFFF => self.chr_banks[0][1] = value & 0x1F,
+            0xD000..=0xDFFF => self.chr_banks[1][0] = value & 0x1F,
+            0xE000..=0xEFFF => self.chr_banks[1][1] = value & 0x1F,
+            0xF000..=0xFFFF => self.horizontal = value & 0x01 != 0,
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16, ciram: &Ciram) -> u8 {
+        let value = match addr & 0x3FFF {
+            0x0000..=0x1FFF => self.memory.chr.get(self.chr_offset(addr)).copied().unwrap_or(0),
+            addr => ciram[ciram_index(addr, self.nametable_mirroring())],
+        };
+        self.observe(addr);
+        value
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8, ciram: &mut Ciram) {
+        match addr & 0x3FFF {
+            0x0000..=0x1FFF => {
+                if self.memory.chr_is_ram {
+                    let offset = self.chr_offset(addr);
+                    self.memory.chr[offset] = value;
+                }
+            }
+            addr => ciram[ciram_index(addr, self.nametable_mirroring())] = value,
+        }
+    }
+
+    fn ppu_address(&mut self, addr: u16) {
+        self.observe(addr);
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        self.memory.save_data()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        self.memory.load_save_data(data);
+    }
+}
@@ -0,0 +1,490 @@
+use crate::nes::cartridge::Cartridge;
+use crate::nes::header::Mirroring;
+use crate::nes::mapper::{bank_offset, ciram_index, Ciram, Mapper};
+use crate::nes::memory::CartridgeMemory;
+
+
+const PRG_BANK_SIZE: usize = 8 * 1024;
+const EXRAM_SIZE: usize = 1024;
+
+// PPU reads per scanline once the MMC5 has seen the three identical nametable fetches:
+// 32 background tiles (4 reads each), then 8 sprites (4 reads each), then the 2 prefetched tiles.
+const BG_FETCH_END: u16 = 32 * 4;
+const SPRITE_FETCH_END: u16 = BG_FETCH_END + 8 * 4;
+const PREFETCH_END: u16 = SPRITE_FETCH_END + 2 * 4;
+
+/// Where a nametable slot reads from ($5105)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NametableSource {
+    CiramA,
+    CiramB,
+    ExRam,
+    Fill,
+}
+
+impl NametableSource {
+    fn from_bits(value: u8) -> Self {
+        match value & 0b11 {
+            0 => Self::CiramA,
+            1 => Self::CiramB,
+            2 => Self::ExRam,
+            _ => Self::Fill,
+        }
+    }
+}
+
+/// Nintendo MMC5 (ExROM), iNES mapper 5
+pub struct Mmc5 {
+    memory: CartridgeMemory,
+    exram: [u8; EXRAM_SIZE],
+
+    /// $5100
+    prg_mode: u8,
+    /// $5101
+    chr_mode: u8,
+    /// $5102 and $5103, writes to PRG-RAM require 0b10 and 0b01
+    prg_ram_protect: [u8; 2],
+    /// $5104
+    exram_mode: u8,
+    /// $5105
+    nametable_mapping: u8,
+    /// $5106
+    fill_tile: u8,
+    /// $5107, already expanded to a full attribute byte
+    fill_attribute: u8,
+    /// $5113
+    prg_ram_bank: u8,
+    /// $5114-$5117
+    prg_banks: [u8; 4],
+    /// $5120-$5127, sprite set (or everything in 8x8 mode)
+    chr_banks_a: [u16; 8],
+    /// $5128-$512B, background set in 8x16 mode
+    chr_banks_b: [u16; 4],
+    /// $5130
+    chr_upper: u8,
+    last_chr_write_b: bool,
+
+    /// $5200
+    split_control: u8,
+    /// $5201
+    split_scroll: u8,
+    /// $5202
+    split_bank: u8,
+    split_y: u8,
+
+    /// $5203
+    irq_compare: u8,
+    irq_enabled: bool,
+    irq_pending: bool,
+    in_frame: bool,
+    scanline: u8,
+
+    /// $5205 and $5206
+    multiplicand: u8,
+    multiplier: u8,
+
+    /// Snooped from PPUCTRL/PPUMASK
+    sprite_8x16: bool,
+    rendering_enabled: bool,
+
+    // scanline detection state
+    last_ppu_addr: u16,
+    ppu_addr_matches: u8,
+    fetch_count: u16,
+    idle_cycles: u8,
+
+    // per-tile state for extended attributes and split screen
+    ex_attribute: u8,
+    split_tile: Option<u8>,
+}
+
+impl Mmc5 {
+    pub fn new(cartridge: &Cartridge) -> Self {
+        Self {
+            memory: CartridgeMemory::new(cartridge),
+            exram: [0; EXRAM_SIZE],
+            prg_mode: 3,
+            chr_mode: 0,
+            prg_ram_protect: [0; 2],
+            exram_mode: 0,
+            nametable_mapping: 0,
+            fill_tile: 0,
+            fill_attribute: 0,
+            prg_ram_bank: 0,
+            prg_banks: [0, 0, 0, 0xFF],
+            chr_banks_a: [0; 8],
+            chr_banks_b: [0; 4],
+            chr_upper: 0,
+            last_chr_write_b: false,
+            split_control: 0,
+            split_scroll: 0,
+            split_bank: 0,
+            split_y: 0,
+            irq_compare: 0,
+            irq_enabled: false,
+            irq_pending: false,
+            in_frame: false,
+            scanline: 0,
+            multiplicand: 0xFF,
+            multiplier: 0xFF,
+            sprite_8x16: false,
+            rendering_enabled: false,
+            last_ppu_addr: 0,
+            ppu_addr_matches: 0,
+            fetch_count: 0,
+            idle_cycles: 0,
+            ex_attribute: 0,
+            split_tile: None,
+        }
+    }
+
+    /// Offset into PRG-RAM for an 8 KiB bank number as written to $5113-$5116
+    fn prg_ram_offset(&self, bank: u8, addr: u16) -> Option<usize> {
+        let banks = self.memory.prg_ram.len() / PRG_BANK_SIZE;
+        let bank = match banks {
+            0 => return None,
+            // 16 KiB boards use two 8 KiB chips selected by bit 2
+            2 => (bank >> 2) & 1,
+            _ => bank & 7,
+        } as usize;
+        Some((bank % banks) * PRG_BANK_SIZE + (addr as usize & (PRG_BANK_SIZE - 1)))
+    }
+
+    /// Resolve $8000-$FFFF to (register index, 8 KiB bank) for the current PRG mode
+    fn prg_bank(&self, addr: u16) -> (usize, u8) {
+        let slot = ((addr - 0x8000) as usize) / PRG_BANK_SIZE;
+        let (register, size_shift) = match self.prg_mode & 3 {
+            0 => (3, 2),
+            1 => (if slot < 2 { 1 } else { 3 }, 1),
+            2 => match slot {
+                0 | 1 => (1, 1),
+                2 => (2, 0),
+                _ => (3, 0),
+            },
+            _ => (slot, 0),
+        };
+        let mask = (1u8 << size_shift) - 1;
+        let bank = (self.prg_banks[register] & 0x7F & !mask) | (slot as u8 & mask);
+        (register, bank)
+    }
+
+    /// Resolve $8000-$FFFF to PRG-ROM (`true`) or PRG-RAM (`false`) and an offset
+    fn prg_offset(&self, addr: u16) -> Option<(bool, usize)> {
+        let (register, bank) = self.prg_bank(addr);
+        // $5117 always selects ROM
+        if register == 3 || self.prg_banks[register] & 0x80 != 0 {
+            if self.memory.prg_rom.is_empty() {
+                return None;
+            }
+            Some((true, bank_offset(&self.memory.prg_rom, bank as usize, PRG_BANK_SIZE, addr as usize)))
+        } else {
+            self.prg_ram_offset(bank, addr).map(|offset| (false, offset))
+        }
+    }
+
+    fn prg_ram_writable(&self) -> bool {
+        self.prg_ram_protect == [0b10, 0b01]
+    }
+
+    fn sprite_fetch(&self) -> bool {
+        (BG_FETCH_END..SPRITE_FETCH_END).contains(&self.fetch_count)
+    }
+
+    /// Offset into CHR for a pattern table address, honoring the A/B register sets
+    fn chr_offset(&self, addr: u16) -> usize {
+        let addr = addr as usize & 0x1FFF;
+
+        if self.in_frame && self.rendering_enabled && !self.sprite_fetch() {
+            if self.split_tile.is_some() {
+                // split region uses its own 4 KiB page and vertical scroll
+                let addr = (addr & 0xFF8) | (self.split_y as usize & 7);
+                return bank_offset(&self.memory.chr, self.split_bank as usize, 0x1000, addr);
+            }
+            if self.exram_mode == 1 {
+                let bank = (self.ex_attribute as usize & 0x3F) | ((self.chr_upper as usize & 3) << 6);
+                return bank_offset(&self.memory.chr, bank, 0x1000, addr);
+            }
+        }
+
+        let use_b = if self.in_frame && self.rendering_enabled && self.sprite_8x16 {
+            !self.sprite_fetch()
+        } else {
+            self.last_chr_write_b
+        };
+
+        let mode = self.chr_mode as usize & 3;
+        let size = 0x2000 >> mode;
+        let step = 8 >> mode;
+        if use_b {
+            let slot = if mode == 0 { 0 } else { (addr & 0xFFF) / size };
+            let bank = self.chr_banks_b[(slot + 1) * step.min(4) - 1];
+            bank_offset(&self.memory.chr, bank as usize, size, addr)
+        } else {
+            let slot = addr / size;
+            let bank = self.chr_banks_a[(slot + 1) * step - 1];
+            bank_offset(&self.memory.chr, bank as usize, size, addr)
+        }
+    }
+
+    /// Watch PPU reads for three identical nametable fetches, which mark the start of a scanline
+    fn observe_ppu_read(&mut self, addr: u16) {
+        self.idle_cycles = 0;
+
+        if (0x2000..=0x2FFF).contains(&addr) && addr == self.last_ppu_addr {
+            self.ppu_addr_matches += 1;
+            if self.ppu_addr_matches == 2 {
+                self.start_scanline();
+                self.last_ppu_addr = addr;
+                return;
+            }
+        } else {
+            self.ppu_addr_matches = 0;
+        }
+        self.last_ppu_addr = addr;
+        self.fetch_count = self.fetch_count.saturating_add(1);
+    }
+
+    fn start_scanline(&mut self) {
+        self.ppu_addr_matches = 0;
+        self.fetch_count = 0;
+
+        if !self.in_frame {
+            self.in_frame = true;
+            self.scanline = 0;
+            self.irq_pending = false;
+            self.split_y = self.split_scroll;
+        } else {
+            self.scanline = self.scanline.wrapping_add(1);
+            if self.scanline == self.irq_compare {
+                self.irq_pending = true;
+            }
+            self.split_y = self.split_y.wrapping_add(1);
+            if self.split_y == 240 {
+                self.split_y = 0;
+            }
+        }
+    }
+
+    fn end_frame(&mut self) {
+        self.in_frame = false;
+        self.ppu_addr_matches = 0;
+        self.last_ppu_addr = 0;
+        self.split_tile = None;
+    }
+
+    /// Tile column of the background fetch in progress, if any
+    fn fetch_tile(&self) -> Option<u8> {
+        match self.fetch_count {
+            // the first two tiles of a line were prefetched at the end of the previous one
+            n if n < BG_FETCH_END => Some((n / 4) as u8 + 2),
+            n if (SPRITE_FETCH_END..PREFETCH_END).contains(&n) => Some(((n - SPRITE_FETCH_END) / 4) as u8),
+            _ => None,
+        }
+    }
+
+    fn in_split_region(&self, tile: u8) -> bool {
+        if self.split_control & 0x80 == 0 || self.exram_mode > 1 {
+            return false;
+        }
+        let threshold = self.split_control & 0x1F;
+        if self.split_control & 0x40 != 0 {
+            tile >= threshold
+        } else {
+            tile < threshold
+        }
+    }
+
+    fn nametable_read(&mut self, addr: u16, ciram: &Ciram) -> u8 {
+        let offset = addr as usize & 0x3FF;
+        let is_attribute = offset >= 0x3C0;
+        let background = self.in_frame && self.rendering_enabled && self.fetch_tile().is_some();
+
+        if background && !is_attribute {
+            let tile = self.fetch_tile().unwrap_or(0);
+            self.split_tile = self.in_split_region(tile).then_some(tile & 0x1F);
+            if let Some(tile) = self.split_tile {
+                let row = (self.split_y as usize / 8) % 30;
+                return self.exram[row * 32 + tile as usize];
+            }
+            self.ex_attribute = self.exram[offset];
+        }
+
+        if background && is_attribute {
+            if let Some(tile) = self.split_tile {
+                let row = (self.split_y as usize / 8) % 30;
+                let byte = self.exram[0x3C0 + (row / 4) * 8 + tile as usize / 4];
+                let shift = ((row & 2) << 1) | (tile as usize & 2);
+                return ((byte >> shift) & 3) * 0x55;
+            }
+            if self.exram_mode == 1 {
+                return (self.ex_attribute >> 6) * 0x55;
+            }
+        }
+
+        let table = (addr >> 10) & 3;
+        match NametableSource::from_bits(self.nametable_mapping >> (table * 2)) {
+            NametableSource::CiramA => ciram[ciram_index(addr, Mirroring::SingleScreenA)],
+            NametableSource::CiramB => ciram[ciram_index(addr, Mirroring::SingleScreenB)],
+            NametableSource::ExRam if self.exram_mode <= 1 => self.exram[offset],
+            NametableSource::ExRam => 0,
+            NametableSource::Fill if is_attribute => self.fill_attribute,
+            NametableSource::Fill => self.fill_tile,
+        }
+    }
+
+    fn nametable_write(&mut self, addr: u16, value: u8, ciram: &mut Ciram) {
+        let table = (addr >> 10) & 3;
+        match NametableSource::from_bits(self.nametable_mapping >> (table * 2)) {
+            NametableSource::CiramA => ciram[ciram_index(addr, Mirroring::SingleScreenA)] = value,
+            NametableSource::CiramB => ciram[ciram_index(addr, Mirroring::SingleScreenB)] = value,
+            NametableSource::ExRam if self.exram_mode <= 1 => self.exram[addr as usize & 0x3FF] = value,
+            NametableSource::ExRam | NametableSource::Fill => {}
+        }
+    }
+
+    fn write_register(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x5100 => self.prg_mode = value & 3,
+            0x5101 => self.chr_mode = value & 3,
+            0x5102 => self.prg_ram_protect[0] = value & 3,
+            0x5103 => self.prg_ram_protect[1] = value & 3,
+            0x5104 => self.exram_mode = value & 3,
+            0x5105 => self.nametable_mapping = value,
+            0x5106 => self.fill_tile = value,
+            0x5107 => self.fill_attribute = (value & 3) * 0x55,
+            0x5113 => self.prg_ram_bank = value & 7,
+            0x5114..=0x5117 => self.prg_banks[(addr - 0x5114) as usize] = value,
+            0x5120..=0x5127 => {
+                self.chr_banks_a[(addr - 0x5120) as usize] = value as u16 | ((self.chr_upper as u16) << 8);
+                self.last_chr_write_b = false;
+            }
+            0x5128..=0x512B => {
+                self.chr_banks_b[(addr - 0x5128) as usize] = value as u16 | ((self.chr_upper as u16) << 8);
+                self.last_chr_write_b = true;
+            }
+            0x5130 => self.chr_upper = value & 3,
+            0x5200 => self.split_control = value,
+            0x5201 => self.split_scroll = value,
+            0x5202 => self.split_bank = value,
+            0x5203 => self.irq_compare = value,
+            0x5204 => self.irq_enabled = value & 0x80 != 0,
+            0x5205 => self.multiplicand = value,
+            0x5206 => self.multiplier = value,
+            0x5C00..=0x5FFF => {
+                let offset = (addr - 0x5C00) as usize;
+                match self.exram_mode {
+                    // nametable modes only accept writes while rendering
+                    0 | 1 => self.exram[offset] = if self.in_frame { value } else { 0 },
+                    2 => self.exram[offset] = value,
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Mapper for Mmc5 {
+    fn cpu_read(&mut self, addr: u16) -> Option<u8> {
+        match addr {
+            0x5204 => {
+                let status = ((self.irq_pending as u8) << 7) | ((self.in_frame as u8) << 6);
+                self.irq_pending = false;
+                Some(status)
+            }
+            0x5205 => Some((self.multiplicand as u16 * self.multiplier as u16) as u8),
+            0x5206 => Some(((self.multiplicand as u16 * self.multiplier as u16) >> 8) as u8),
+            0x5C00..=0x5FFF if self.exram_mode >= 2 => Some(self.exram[(addr - 0x5C00) as usize]),
+            0x6000..=0x7FFF => self
+                .prg_ram_offset(self.prg_ram_bank, addr)
+                .map(|offset| self.memory.prg_ram[offset]),
+            0x8000..=0xFFFF => {
+                // the NMI vector fetch marks the end of rendering
+                if addr == 0xFFFA || addr == 0xFFFB {
+                    self.end_frame();
+                }
+                self.prg_offset(addr).map(|(rom, offset)| {
+                    if rom { self.memory.prg_rom[offset] } else { self.memory.prg_ram[offset] }
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x2000..=0x3FFF => match addr & 7 {
+                0 => self.sprite_8x16 = value & 0x20 != 0,
+                1 => {
+                    self.rendering_enabled = value & 0x18 != 0;
+                    if !self.rendering_enabled {
+                        self.end_frame();
+                    }
+                }
+                _ => {}
+            },
+            0x5000..=0x5FFF => self.write_register(addr, value),
+            0x6000..=0x7FFF if self.prg_ram_writable() => {
+                if let Some(offset) = self.prg_ram_offset(self.prg_ram_bank, addr) {
+                    self.memory.prg_ram[offset] = value;
+                }
+            }
+            0x8000..=0xFFFF if self.prg_ram_writable() => {
+                if let Some((false, offset)) = self.prg_offset(addr) {
+                    self.memory.prg_ram[offset] = value;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16, ciram: &Ciram) -> u8 {
+        let addr = addr & 0x3FFF;
+        self.observe_ppu_read(addr);
+
+        match addr {
+            0x0000..=0x1FFF => {
+                let offset = self.chr_offset(addr);
+                self.memory.chr.get(offset).copied().unwrap_or(0)
+            }
+            _ => self.nametable_read(addr & 0x2FFF, ciram),
+        }
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8, ciram: &mut Ciram) {
+        let addr = addr & 0x3FFF;
+        match addr {
+            0x0000..=0x1FFF => {
+                if self.memory.chr_is_ram {
+                    let offset = self.chr_offset(addr);
+                    if let Some(byte) = self.memory.chr.get_mut(offset) {
+                        *byte = value;
+                    }
+                }
+            }
+            _ => self.nametable_write(addr & 0x2FFF, value, ciram),
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.irq_enabled && self.irq_pending
+    }
+
+    fn cpu_clock(&mut self) {
+        // the PPU stops fetching once rendering ends; three idle M2 cycles clear the in-frame flag
+        if self.in_frame {
+            self.idle_cycles += 1;
+            if self.idle_cycles >= 3 {
+                self.end_frame();
+            }
+        }
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        self.memory.save_data()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        self.memory.load_save_data(data);
+    }
+}
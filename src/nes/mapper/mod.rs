@@ -0,0 +1,139 @@
+pub mod bandai;
+pub mod eeprom;
+pub mod fme7;
+pub mod mmc2;
+pub mod mmc5;
+pub mod namco163;
+pub mod vrc;
+pub mod vrc1;
+pub mod vrc24;
+pub mod vrc3;
+pub mod vrc6;
+pub mod vrc7;
+
+use crate::nes::cartridge::Cartridge;
+use crate::nes::error::RomParseError;
+use crate::nes::header::{InesHeader, Mirroring};
+use crate::nes::mapper::fme7::Sunsoft5BAudio;
+use crate::nes::mapper::namco163::Namco163Audio;
+use crate::nes::mapper::vrc6::Vrc6Audio;
+use crate::nes::mapper::vrc7::Vrc7Audio;
+use crate::nes::memory::{chr_nvram_len, prg_ram_sizes};
+
+
+/// Size of the console's internal nametable RAM (CIRAM)
+pub const CIRAM_SIZE: usize = 0x800;
+
+/// The console's 2 KiB of nametable RAM, which the cartridge decides how to map
+pub type Ciram = [u8; CIRAM_SIZE];
+
+/// Register state of a cartridge sound chip, for the host's audio mixer
+#[derive(Debug, Clone, Copy)]
+pub enum ExpansionAudio<'a> {
+    Vrc6(&'a Vrc6Audio),
+    Vrc7(&'a Vrc7Audio),
+    Sunsoft5B(&'a Sunsoft5BAudio),
+    Namco163(&'a Namco163Audio),
+}
+
+/// Cartridge hardware as seen from the CPU and PPU buses.
+///
+/// The host forwards bus accesses here; the mapper owns PRG/CHR storage and the
+/// nametable mapping, and keeps any internal state (bank registers, IRQ counters).
+pub trait Mapper {
+    /// Read from the CPU bus. Returns `None` when the cartridge does not drive the bus (open bus).
+    fn cpu_read(&mut self, addr: u16) -> Option<u8>;
+
+    /// Write to the CPU bus.
+    ///
+    /// Every CPU write is forwarded, not just $4020-$FFFF, because some boards snoop
+    /// PPU register writes (MMC5 watches $2000/$2001).
+    fn cpu_write(&mut self, addr: u16, value: u8);
+
+    /// Read from the PPU bus ($0000-$2FFF, pattern tables and nametables).
+    fn ppu_read(&mut self, addr: u16, ciram: &Ciram) -> u8;
+
+    /// Write to the PPU bus ($0000-$2FFF, pattern tables and nametables).
+    fn ppu_write(&mut self, addr: u16, value: u8, ciram: &mut Ciram);
+
+    /// Level of the cartridge IRQ line (true = asserted)
+    fn irq(&self) -> bool {
+        false
+    }
+
+    /// Observe an address the PPU drives on its bus without going through `ppu_read`,
+    /// such as the VRAM address set through $2006 or fetches the host serves from its own
+    /// cache. Boards that react to fetch patterns (MMC2/MMC4 CHR latches) watch these too.
+    fn ppu_address(&mut self, _addr: u16) {}
+
+    /// Called once per CPU cycle (M2), for boards that count cycles or detect bus idling.
+    fn cpu_clock(&mut self) {}
+
+    /// Expansion audio registers, for boards that carry a sound chip
+    fn expansion_audio(&self) -> Option<ExpansionAudio<'_>> {
+        None
+    }
+
+    /// Battery-backed contents to write to a save file, or `None` when nothing on the board
+    /// survives power-off. Only memory the header marks as non-volatile is included.
+    fn save_data(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Restore contents previously returned by `save_data`.
+    fn load_save_data(&mut self, _data: &[u8]) {}
+}
+
+/// Create the mapper implementation for a loaded cartridge.
+pub fn new_mapper(cartridge: &Cartridge) -> Result<Box<dyn Mapper>, RomParseError> {
+    match cartridge.ines_header.mapper {
+        5 => Ok(Box::new(mmc5::Mmc5::new(cartridge))),
+        9 | 10 => Ok(Box::new(mmc2::Mmc2::new(cartridge))),
+        16 | 153 | 157 | 159 => Ok(Box::new(bandai::BandaiFcg::new(cartridge))),
+        19 => Ok(Box::new(namco163::Namco163::new(cartridge))),
+        21 | 22 | 23 | 25 => Ok(Box::new(vrc24::Vrc24::new(cartridge))),
+        24 | 26 => Ok(Box::new(vrc6::Vrc6::new(cartridge))),
+        69 => Ok(Box::new(fme7::Fme7::new(cartridge))),
+        73 => Ok(Box::new(vrc3::Vrc3::new(cartridge)?)),
+        75 => Ok(Box::new(vrc1::Vrc1::new(cartridge))),
+        85 => Ok(Box::new(vrc7::Vrc7::new(cartridge))),
+        mapper => Err(RomParseError::UnsupportedMapper(mapper)),
+    }
+}
+
+/// Size of the data the mapper's `save_data` returns, worked out from the header alone;
+/// 0 when nothing on the board survives power-off. Boards without a mapper implementation
+/// are taken to save their NVRAM like most do.
+pub(crate) fn save_size(header: &InesHeader) -> usize {
+    match header.mapper {
+        // VRC1 boards have no PRG-RAM
+        75 => 0,
+        16 | 153 | 157 | 159 => bandai::save_size(header),
+        19 => namco163::save_size(header),
+        _ => prg_ram_sizes(header).1 + chr_nvram_len(header),
+    }
+}
+
+/// Map a nametable address onto CIRAM for one of the arrangements CIRAM can form on its own.
+pub(crate) fn ciram_index(addr: u16, mirroring: Mirroring) -> usize {
+    let pages = mirroring
+        .nametable_pages()
+        .expect("four-screen and mapper-controlled mirroring have no fixed CIRAM pages");
+    let table = ((addr >> 10) & 3) as usize;
+    ((pages[table] as usize & 1) << 10) | (addr as usize & 0x3FF)
+}
+
+/// Offset into `data` for `bank` of `bank_size` bytes, wrapping banks past the end of the chip.
+pub(crate) fn bank_offset(data: &[u8], bank: usize, bank_size: usize, addr: usize) -> usize {
+    let banks = (data.len() / bank_size).max(1);
+    (bank % banks) * bank_size + (addr % bank_size)
+}
+
+/// Header mirroring for boards with solder-pad (fixed) nametable arrangement. These boards
+/// have no VRAM for four screens, so a header asking for them is refused.
+pub(crate) fn header_mirroring(header: &InesHeader) -> Result<Mirroring, RomParseError> {
+    match header.mirroring() {
+        mirroring if mirroring.nametable_pages().is_some() => Ok(mirroring),
+        mirroring => Err(RomParseError::UnsupportedMirroring(header.mapper, mirroring)),
+    }
+}
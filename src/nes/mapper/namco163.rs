@@ -0,0 +1,292 @@
+use crate::nes::cartridge::Cartridge;
+use crate::nes::header::{InesHeader, RamSize};
+use crate::nes::mapper::{bank_offset, Ciram, ExpansionAudio, Mapper};
+use crate::nes::memory::{self, CartridgeMemory};
+
+
+const PRG_BANK_SIZE: usize = 8 * 1024;
+const CHR_BANK_SIZE: usize = 1024;
+const INTERNAL_RAM_SIZE: usize = 128;
+/// Channel registers occupy the top of the internal RAM
+const CHANNEL_REGISTERS: usize = 0x40;
+
+/// Decoded registers of one Namco 163 wavetable channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Namco163Channel {
+    /// 18-bit frequency
+    pub frequency: u32,
+    /// 24-bit phase accumulator
+    pub phase: u32,
+    /// Waveform length in 4-bit samples
+    pub length: u16,
+    /// Waveform start, in 4-bit samples
+    pub wave_address: u8,
+    /// 4-bit volume
+    pub volume: u8,
+}
+
+/// Namco 163 sound: the 128 bytes of internal RAM hold both the waveforms and the channel registers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Namco163Audio {
+    pub ram: [u8; INTERNAL_RAM_SIZE],
+    /// $E000 bit 6: sound output disabled
+    pub disabled: bool,
+}
+
+impl Namco163Audio {
+    /// Number of channels the game enabled (1-8); the highest-numbered channels are the active ones
+    pub fn active_channels(&self) -> usize {
+        ((self.ram[0x7F] >> 4) & 7) as usize + 1
+    }
+
+    /// Decode channel `index` (0-7), whose registers live at $40 + index * 8
+    pub fn channel(&self, index: usize) -> Namco163Channel {
+        let regs = &self.ram[CHANNEL_REGISTERS + index * 8..CHANNEL_REGISTERS + index * 8 + 8];
+        Namco163Channel {
+            frequency: regs[0] as u32 | (regs[2] as u32) << 8 | ((regs[4] as u32 & 3) << 16),
+            phase: regs[1] as u32 | (regs[3] as u32) << 8 | (regs[5] as u32) << 16,
+            length: 256 - (regs[4] & 0xFC) as u16,
+            wave_address: regs[6],
+            volume: regs[7] & 0x0F,
+        }
+    }
+
+    /// 4-bit sample at `address` (in samples) of the waveform RAM
+    pub fn sample(&self, address: u8) -> u8 {
+        let byte = self.ram[(address as usize >> 1) % INTERNAL_RAM_SIZE];
+        if address & 1 == 0 { byte & 0x0F } else { byte >> 4 }
+    }
+}
+
+/// Where a 1 KiB PPU window reads from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChrSource {
+    Chr(usize),
+    Ciram(u8),
+}
+
+/// Namco 163, iNES mapper 19
+///
+/// Battery persistence follows the header: NES 2.0 PRG-NVRAM of 128 bytes means only the
+/// internal sound RAM is battery-backed, 8 KiB (or more) means the WRAM and internal RAM
+/// both are. For iNES, the battery flag covers both.
+pub struct Namco163 {
+    memory: CartridgeMemory,
+    internal_ram_battery: bool,
+
+    /// $8000-$DFFF: eight pattern table banks then four nametable banks
+    chr_banks: [u8; 12],
+    prg_banks: [u8; 3],
+    /// $E800 bits 6-7: pattern table halves ignore CIRAM selection values
+    chr_ram_disable: [bool; 2],
+    /// $F800 write protection
+    write_protect: u8,
+    /// $F800 address port with auto-increment in bit 7
+    ram_address: u8,
+    irq_counter: u16,
+    irq_enabled: bool,
+    audio: Namco163Audio,
+}
+
+fn internal_ram_battery(header: &InesHeader) -> bool {
+    match header.prg_ram_size {
+        RamSize::Nes2 { nvram, .. } => nvram > 0,
+        RamSize::Ines(_) => header.flags_6.battery_backed(),
+    }
+}
+
+/// Volatile and battery-backed WRAM
+fn prg_ram_sizes(header: &InesHeader) -> (usize, usize) {
+    // a PRG-NVRAM size under 8 KiB is the internal RAM, not battery-backed WRAM
+    match memory::prg_ram_sizes(header) {
+        (ram, nvram) if nvram < PRG_BANK_SIZE => (ram, 0),
+        sizes => sizes,
+    }
+}
+
+/// Size of the data `save_data` returns: battery-backed WRAM, then the internal RAM
+pub(crate) fn save_size(header: &InesHeader) -> usize {
+    let (_, nvram) = prg_ram_sizes(header);
+    let internal_ram = if internal_ram_battery(header) { INTERNAL_RAM_SIZE } else { 0 };
+    nvram + internal_ram
+}
+
+impl Namco163 {
+    pub fn new(cartridge: &Cartridge) -> Self {
+        let header = &cartridge.ines_header;
+        let (ram, nvram) = prg_ram_sizes(header);
+
+        Self {
+            memory: CartridgeMemory::with_prg_ram(cartridge, ram, nvram),
+            internal_ram_battery: internal_ram_battery(header),
+            chr_banks: [0; 12],
+            prg_banks: [0; 3],
+            chr_ram_disable: [false; 2],
+            write_protect: 0,
+            ram_address: 0,
+            irq_counter: 0,
+            irq_enabled: false,
+            audio: Namco163Audio { ram: [0; INTERNAL_RAM_SIZE], disabled: false },
+        }
+    }
+
+    /// Expansion audio register state
+    pub fn audio(&self) -> &Namco163Audio {
+        &self.audio
+    }
+
+    fn prg_offset(&self, addr: u16) -> usize {
+        let bank = match (addr - 0x8000) as usize / PRG_BANK_SIZE {
+            slot @ 0..=2 => self.prg_banks[slot] as usize & 0x3F,
+            _ => (self.memory.prg_rom.len() / PRG_BANK_SIZE).max(1) - 1,
+        };
+        bank_offset(&self.memory.prg_rom, bank, PRG_BANK_SIZE, addr as usize)
+    }
+
+    /// Source of the 1 KiB window containing a PPU address ($0000-$2FFF)
+    fn chr_source(&self, addr: u16) -> ChrSource {
+        let window = (addr as usize / CHR_BANK_SIZE) & 0x0F;
+        let (value, ciram_allowed) = if window < 8 {
+            (self.chr_banks[window], !self.chr_ram_disable[window / 4])
+        } else {
+            (self.chr_banks[8 + (window & 3)], true)
+        };
+        if value >= 0xE0 && ciram_allowed {
+            ChrSource::Ciram(value & 1)
+        } else {
+            ChrSource::Chr(value as usize)
+        }
+    }
+
+    fn wram_writable(&self, addr: u16) -> bool {
+        // the upper nibble must be %0100, each low bit protects a 2 KiB window
+        let window = (addr as usize - 0x6000) / 0x800;
+        self.write_protect & 0xF0 == 0x40 && self.write_protect & (1 << window) == 0
+    }
+
+    fn read_internal_ram(&mut self) -> u8 {
+        let value = self.audio.ram[(self.ram_address & 0x7F) as usize];
+        self.advance_ram_address();
+        value
+    }
+
+    fn write_internal_ram(&mut self, value: u8) {
+        self.audio.ram[(self.ram_address & 0x7F) as usize] = value;
+        self.advance_ram_address();
+    }
+
+    fn advance_ram_address(&mut self) {
+        if self.ram_address & 0x80 != 0 {
+            self.ram_address = 0x80 | (self.ram_address.wrapping_add(1) & 0x7F);
+        }
+    }
+}
+
+impl Mapper for Namco163 {
+    fn cpu_read(&mut self, addr: u16) -> Option<u8> {
+        match addr {
+            0x4800..=0x4FFF => Some(self.read_internal_ram()),
+            0x5000..=0x57FF => Some(self.irq_counter as u8),
+            0x5800..=0x5FFF => {
+                Some(((self.irq_counter >> 8) as u8 & 0x7F) | ((self.irq_enabled as u8) << 7))
+            }
+            0x6000..=0x7FFF if !self.memory.prg_ram.is_empty() => {
+                Some(self.memory.prg_ram[(addr as usize - 0x6000) % self.memory.prg_ram.len()])
+            }
+            0x8000..=0xFFFF if !self.memory.prg_rom.is_empty() => {
+                Some(self.memory.prg_rom[self.prg_offset(addr)])
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x4800..=0x4FFF => self.write_internal_ram(value),
+            0x5000..=0x57FF => self.irq_counter = (self.irq_counter & 0x7F00) | value as u16,
+            0x5800..=0x5FFF => {
+                self.irq_counter = (self.irq_counter & 0x00FF) | ((value as u16 & 0x7F) << 8);
+                self.irq_enabled = value & 0x80 != 0;
+            }
+            0x6000..=0x7FFF if !self.memory.prg_ram.is_empty() && self.wram_writable(addr) => {
+                let len = self.memory.prg_ram.len();
+                self.memory.prg_ram[(addr as usize - 0x6000) % len] = value;
+            }
+            0x8000..=0xDFFF => self.chr_banks[(addr as usize - 0x8000) / 0x800] = value,
+            0xE000..=0xE7FF => {
+                self.prg_banks[0] = value & 0x3F;
+                self.audio.disabled = value & 0x40 != 0;
+            }
+            0xE800..=0xEFFF => {
+                self.prg_banks[1] = value & 0x3F;
+                self.chr_ram_disable = [value & 0x40 != 0, value & 0x80 != 0];
+            }
+            0xF000..=0xF7FF => self.prg_banks[2] = value & 0x3F,
+            0xF800..=0xFFFF => {
+                self.write_protect = value;
+                self.ram_address = value;
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16, ciram: &Ciram) -> u8 {
+        match self.chr_source(addr & 0x2FFF) {
+            ChrSource::Ciram(page) => ciram[((page as usize) << 10) | (addr as usize & 0x3FF)],
+            ChrSource::Chr(bank) => {
+                let offset = bank_offset(&self.memory.chr, bank, CHR_BANK_SIZE, addr as usize);
+                self.memory.chr.get(offset).copied().unwrap_or(0)
+            }
+        }
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8, ciram: &mut Ciram) {
+        match self.chr_source(addr & 0x2FFF) {
+            ChrSource::Ciram(page) => ciram[((page as usize) << 10) | (addr as usize & 0x3FF)] = value,
+            ChrSource::Chr(bank) if self.memory.chr_is_ram => {
+                let offset = bank_offset(&self.memory.chr, bank, CHR_BANK_SIZE, addr as usize);
+                self.memory.chr[offset] = value;
+            }
+            ChrSource::Chr(_) => {}
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.irq_enabled && self.irq_counter == 0x7FFF
+    }
+
+    fn cpu_clock(&mut self) {
+        if self.irq_enabled && self.irq_counter < 0x7FFF {
+            self.irq_counter += 1;
+        }
+    }
+
+    fn expansion_audio(&self) -> Option<ExpansionAudio<'_>> {
+        Some(ExpansionAudio::Namco163(&self.audio))
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        let wram = &self.memory.prg_ram[..self.memory.prg_nvram_len];
+        if wram.is_empty() && !self.internal_ram_battery {
+            return None;
+        }
+        let mut data = wram.to_vec();
+        if self.internal_ram_battery {
+            data.extend_from_slice(&self.audio.ram);
+        }
+        Some(data)
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        let mut rest = data;
+        if self.memory.prg_nvram_len > 0 {
+            let len = self.memory.prg_nvram_len.min(rest.len());
+            self.memory.load_save_data(&rest[..len]);
+            rest = &rest[len..];
+        }
+        if self.internal_ram_battery {
+            let len = INTERNAL_RAM_SIZE.min(rest.len());
+            self.audio.ram[..len].copy_from_slice(&rest[..len]);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use crate::nes::header::InesHeader;
+
+
+/// PPU dots per scanline, in thirds of a CPU cycle, for the IRQ prescaler
+const PRESCALER_RELOAD: i16 = 341;
+
+/// Which CPU address lines a board wires to the chip's two register-select pins.
+///
+/// Different boards connect the VRC2/VRC4/VRC6/VRC7 register-select pins to different CPU
+/// address lines; the NES 2.0 submapper says which. When the submapper is 0 the known
+/// wirings for that mapper number are OR-ed together, which works for every licensed game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressLines {
+    /// CPU address lines feeding register-select bit 0
+    pub a0: u16,
+    /// CPU address lines feeding register-select bit 1
+    pub a1: u16,
+}
+
+impl AddressLines {
+    pub const fn new(a0: u16, a1: u16) -> Self {
+        Self { a0, a1 }
+    }
+
+    /// Address lines for a VRC2/VRC4 board (mappers 21, 22, 23 and 25)
+    pub fn vrc24(header: &InesHeader) -> Self {
+        match (header.mapper, header.submapper) {
+            (21, 1) => Self::new(0x02, 0x04), // VRC4a
+            (21, 2) => Self::new(0x40, 0x80), // VRC4c
+            (21, _) => Self::new(0x42, 0x84),
+            (22, _) => Self::new(0x02, 0x01), // VRC2a
+            (23, 1) | (23, 3) => Self::new(0x01, 0x02), // VRC4f, VRC2b
+            (23, 2) => Self::new(0x04, 0x08), // VRC4e
+            (23, _) => Self::new(0x05, 0x0A),
+            (25, 1) | (25, 3) => Self::new(0x02, 0x01), // VRC4b, VRC2c
+            (25, 2) => Self::new(0x08, 0x04), // VRC4d
+            (25, _) => Self::new(0x0A, 0x05),
+            _ => Self::new(0x01, 0x02),
+        }
+    }
+
+    /// Address lines for a VRC6 board (mapper 24 is VRC6a, mapper 26 is VRC6b with A0/A1 swapped)
+    pub fn vrc6(header: &InesHeader) -> Self {
+        if header.mapper == 26 {
+            Self::new(0x02, 0x01)
+        } else {
+            Self::new(0x01, 0x02)
+        }
+    }
+
+    /// Normalize a CPU address to $x000-$x003, as the chip sees it
+    pub fn register(&self, addr: u16) -> u16 {
+        let a0 = (addr & self.a0 != 0) as u16;
+        let a1 = (addr & self.a1 != 0) as u16;
+        (addr & 0xF000) | (a1 << 1) | a0
+    }
+}
+
+/// The IRQ counter shared by VRC4, VRC6 and VRC7.
+///
+/// In scanline mode a prescaler divides CPU cycles by 113⅔ so the 8-bit counter steps once
+/// per scanline; in cycle mode the counter steps every CPU cycle. The IRQ fires when the
+/// counter overflows from $FF, which also reloads it from the latch.
+#[derive(Debug, Clone, Default)]
+pub struct VrcIrq {
+    latch: u8,
+    counter: u8,
+    prescaler: i16,
+    enabled: bool,
+    enable_after_ack: bool,
+    cycle_mode: bool,
+    pending: bool,
+}
+
+impl VrcIrq {
+    /// VRC4 latch low nibble
+    pub fn write_latch_low(&mut self, value: u8) {
+        self.latch = (self.latch & 0xF0) | (value & 0x0F);
+    }
+
+    /// VRC4 latch high nibble
+    pub fn write_latch_high(&mut self, value: u8) {
+        self.latch = (self.latch & 0x0F) | (value << 4);
+    }
+
+    /// VRC6/VRC7 full latch write
+    pub fn write_latch(&mut self, value: u8) {
+        self.latch = value;
+    }
+
+    pub fn write_control(&mut self, value: u8) {
+        self.enable_after_ack = value & 0x01 != 0;
+        self.enabled = value & 0x02 != 0;
+        self.cycle_mode = value & 0x04 != 0;
+        self.pending = false;
+        if self.enabled {
+            self.counter = self.latch;
+            self.prescaler = PRESCALER_RELOAD;
+        }
+    }
+
+    pub fn acknowledge(&mut self) {
+        self.pending = false;
+        self.enabled = self.enable_after_ack;
+    }
+
+    pub fn pending(&self) -> bool {
+        self.pending
+    }
+
+    /// Advance by one CPU cycle
+    pub fn clock(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        if self.cycle_mode {
+            self.step();
+        } else {
+            self.prescaler -= 3;
+            if self.prescaler <= 0 {
+                self.prescaler += PRESCALER_RELOAD;
+                self.step();
+            }
+        }
+    }
+
+    fn step(&mut self) {
+        if self.counter == 0xFF {
+            self.counter = self.latch;
+            self.pending = true;
+        } else {
+            self.counter += 1;
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use crate::nes::cartridge::Cartridge;
+use crate::nes::header::Mirroring;
+use crate::nes::mapper::{bank_offset, ciram_index, Ciram, Mapper};
+use crate::nes::memory::CartridgeMemory;
+
+
+const PRG_BANK_SIZE: usize = 8 * 1024;
+const CHR_BANK_SIZE: usize = 4 * 1024;
+
+/// Konami VRC1, iNES mapper 75
+pub struct Vrc1 {
+    memory: CartridgeMemory,
+
+    prg_banks: [u8; 3],
+    /// 5-bit 4 KiB banks; bit 4 comes from $9000
+    chr_banks: [u8; 2],
+    horizontal: bool,
+}
+
+impl Vrc1 {
+    pub fn new(cartridge: &Cartridge) -> Self {
+        Self {
+            memory: CartridgeMemory::new(cartridge),
+            prg_banks: [0; 3],
+            chr_banks: [0; 2],
+            horizontal: false,
+        }
+    }
+
+    fn prg_offset(&self, addr: u16) -> usize {
+        let bank = match (addr - 0x8000) as usize / PRG_BANK_SIZE {
+            slot @ 0..=2 => self.prg_banks[slot] as usize & 0x0F,
+            _ => (self.memory.prg_rom.len() / PRG_BANK_SIZE).max(1) - 1,
+        };
+        bank_offset(&self.memory.prg_rom, bank, PRG_BANK_SIZE, addr as usize)
+    }
+
+    fn chr_offset(&self, addr: u16) -> usize {
+        let bank = self.chr_banks[addr as usize / CHR_BANK_SIZE];
+        bank_offset(&self.memory.chr, bank as usize, CHR_BANK_SIZE, addr as usize)
+    }
+
+    fn nametable_mirroring(&self) -> Mirroring {
+        if self.horizontal { Mirroring::Horizontal } else { Mirroring::Vertical }
+    }
+}
+
+impl Mapper for Vrc1 {
+    fn cpu_read(&mut self, addr: u16) -> Option<u8> {
+        match addr {
+            0x8000..=0xFFFF if !self.memory.prg_rom.is_empty() => {
+                Some(self.memory.prg_rom[self.prg_offset(addr)])
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr & 0xF000 {
+            0x8000 => self.prg_banks[0] = value,
+            0x9000 => {
+                self.horizontal = value & 0x01 != 0;
+                self.chr_banks[0] = (self.chr_banks[0] & 0x0F) | ((value & 0x02) << 3);
+                self.chr_banks[1] = (self.chr_banks[1] & 0x0F) | ((value & 0x04) << 2);
+            }
+            0xA000 => self.prg_banks[1] = value,
+            0xC000 => self.prg_banks[2] = value,
+            0xE000 => self.chr_banks[0] = (self.chr_banks[0] & 0x10) | (value & 0x0F),
+            0xF000 => self.chr_banks[1] = (self.chr_banks[1] & 0x10) | (value & 0x0F),
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16, ciram: &Ciram) -> u8 {
+        match addr & 0x3FFF {
+            0x0000..=0x1FFF => self.memory.chr.get(self.chr_offset(addr)).copied().unwrap_or(0),
+            addr => ciram[ciram_index(addr, self.nametable_mirroring())],
+        }
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8, ciram: &mut Ciram) {
+        match addr & 0x3FFF {
+            0x0000..=0x1FFF => {
+                if self.memory.chr_is_ram {
+                    let offset = self.chr_offset(addr);
+                    self.memory.chr[offset] = value;
+                }
+            }
+            addr => ciram[ciram_index(addr, self.nametable_mirroring())] = value,
+        }
+    }
+}
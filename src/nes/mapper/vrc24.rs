@@ -0,0 +1,167 @@
+use crate::nes::cartridge::Cartridge;
+use crate::nes::header::Mirroring;
+use crate::nes::mapper::vrc::{AddressLines, VrcIrq};
+use crate::nes::mapper::{bank_offset, ciram_index, Ciram, Mapper};
+use crate::nes::memory::CartridgeMemory;
+
+
+const PRG_BANK_SIZE: usize = 8 * 1024;
+const CHR_BANK_SIZE: usize = 1024;
+
+/// Konami VRC2 and VRC4, iNES mappers 21, 22, 23 and 25
+pub struct Vrc24 {
+    memory: CartridgeMemory,
+
+    lines: AddressLines,
+    /// VRC2 lacks the IRQ, PRG swap mode and one-screen mirroring of the VRC4
+    vrc2: bool,
+    /// VRC2a ignores the lowest CHR bank bit
+    chr_shift: u8,
+
+    prg_banks: [u8; 2],
+    prg_swap: bool,
+    mirroring: u8,
+    chr_banks: [u16; 8],
+    irq: VrcIrq,
+    /// VRC2 one-bit latch at $6000-$6FFF on boards without work RAM
+    microwire_latch: u8,
+}
+
+impl Vrc24 {
+    pub fn new(cartridge: &Cartridge) -> Self {
+        let header = &cartridge.ines_header;
+        let vrc2 = header.mapper == 22 || (matches!(header.mapper, 23 | 25) && header.submapper == 3);
+
+        Self {
+            memory: CartridgeMemory::new(cartridge),
+            lines: AddressLines::vrc24(header),
+            vrc2,
+            chr_shift: if header.mapper == 22 { 1 } else { 0 },
+            prg_banks: [0; 2],
+            prg_swap: false,
+            mirroring: 0,
+            chr_banks: [0; 8],
+            irq: VrcIrq::default(),
+            microwire_latch: 0,
+        }
+    }
+
+    fn prg_offset(&self, addr: u16) -> usize {
+        let last = (self.memory.prg_rom.len() / PRG_BANK_SIZE).max(1) - 1;
+        let bank = match (addr - 0x8000) as usize / PRG_BANK_SIZE {
+            0 if self.prg_swap => last.saturating_sub(1),
+            0 => self.prg_banks[0] as usize,
+            1 => self.prg_banks[1] as usize,
+            2 if self.prg_swap => self.prg_banks[0] as usize,
+            2 => last.saturating_sub(1),
+            _ => last,
+        };
+        bank_offset(&self.memory.prg_rom, bank, PRG_BANK_SIZE, addr as usize)
+    }
+
+    fn chr_offset(&self, addr: u16) -> usize {
+        let bank = self.chr_banks[addr as usize / CHR_BANK_SIZE] >> self.chr_shift;
+        bank_offset(&self.memory.chr, bank as usize, CHR_BANK_SIZE, addr as usize)
+    }
+
+    fn nametable_mirroring(&self) -> Mirroring {
+        if self.vrc2 {
+            return if self.mirroring & 1 == 0 { Mirroring::Vertical } else { Mirroring::Horizontal };
+        }
+        match self.mirroring & 3 {
+            0 => Mirroring::Vertical,
+            1 => Mirroring::Horizontal,
+            2 => Mirroring::SingleScreenA,
+            _ => Mirroring::SingleScreenB,
+        }
+    }
+
+    fn write_chr_bank(&mut self, register: u16, value: u8) {
+        // $B000-$E003: two registers per bank, low nibble then high bits
+        let index = (((register >> 12) - 0xB) * 2 + ((register >> 1) & 1)) as usize;
+        let bank = &mut self.chr_banks[index];
+        if register & 1 == 0 {
+            *bank = (*bank & !0x0F) | (value as u16 & 0x0F);
+        } else {
+            let high_mask = if self.vrc2 { 0x0F } else { 0x1F };
+            *bank = (*bank & 0x0F) | ((value as u16 & high_mask) << 4);
+        }
+    }
+}
+
+impl Mapper for Vrc24 {
+    fn cpu_read(&mut self, addr: u16) -> Option<u8> {
+        match addr {
+            0x6000..=0x7FFF if !self.memory.prg_ram.is_empty() => {
+                Some(self.memory.prg_ram[(addr as usize - 0x6000) % self.memory.prg_ram.len()])
+            }
+            0x6000..=0x6FFF if self.vrc2 => Some(self.microwire_latch),
+            0x8000..=0xFFFF if !self.memory.prg_rom.is_empty() => {
+                Some(self.memory.prg_rom[self.prg_offset(addr)])
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x6000..=0x7FFF if !self.memory.prg_ram.is_empty() => {
+                let len = self.memory.prg_ram.len();
+                self.memory.prg_ram[(addr as usize - 0x6000) % len] = value;
+            }
+            0x6000..=0x6FFF if self.vrc2 => self.microwire_latch = value & 1,
+            0x8000..=0xFFFF => {
+                let register = self.lines.register(addr);
+                match register {
+                    0x8000..=0x8003 => self.prg_banks[0] = value & 0x1F,
+                    0x9000..=0x9001 => self.mirroring = value,
+                    0x9002..=0x9003 if self.vrc2 => self.mirroring = value,
+                    0x9002..=0x9003 => self.prg_swap = value & 0x02 != 0,
+                    0xA000..=0xA003 => self.prg_banks[1] = value & 0x1F,
+                    0xB000..=0xE003 => self.write_chr_bank(register, value),
+                    0xF000 if !self.vrc2 => self.irq.write_latch_low(value),
+                    0xF001 if !self.vrc2 => self.irq.write_latch_high(value),
+                    0xF002 if !self.vrc2 => self.irq.write_control(value),
+                    0xF003 if !self.vrc2 => self.irq.acknowledge(),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16, ciram: &Ciram) -> u8 {
+        match addr & 0x3FFF {
+            0x0000..=0x1FFF => self.memory.chr.get(self.chr_offset(addr)).copied().unwrap_or(0),
+            addr => ciram[ciram_index(addr, self.nametable_mirroring())],
+        }
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8, ciram: &mut Ciram) {
+        match addr & 0x3FFF {
+            0x0000..=0x1FFF => {
+                if self.memory.chr_is_ram {
+                    let offset = self.chr_offset(addr);
+                    self.memory.chr[offset] = value;
+                }
+            }
+            addr => ciram[ciram_index(addr, self.nametable_mirroring())] = value,
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.irq.pending()
+    }
+
+    fn cpu_clock(&mut self) {
+        self.irq.clock();
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        self.memory.save_data()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        self.memory.load_save_data(data);
+    }
+}
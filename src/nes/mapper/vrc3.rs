@@ -0,0 +1,147 @@
+use crate::nes::cartridge::Cartridge;
+use crate::nes::error::RomParseError;
+use crate::nes::header::Mirroring;
+use crate::nes::mapper::{bank_offset, ciram_index, header_mirroring, Ciram, Mapper};
+use crate::nes::memory::CartridgeMemory;
+
+
+const PRG_BANK_SIZE: usize = 16 * 1024;
+
+/// Konami VRC3, iNES mapper 73
+///
+/// Unlike the later VRCs, its IRQ counter is 16 bits wide and always counts CPU cycles.
+pub struct Vrc3 {
+    memory: CartridgeMemory,
+    mirroring: Mirroring,
+
+    prg_bank: u8,
+    irq_latch: u16,
+    irq_counter: u16,
+    irq_enabled: bool,
+    irq_enable_after_ack: bool,
+    /// Count only the low 8 bits
+    irq_8bit: bool,
+    irq_pending: bool,
+}
+
+impl Vrc3 {
+    /// Fails for four-screen headers, as the board's mirroring is soldered
+    pub fn new(cartridge: &Cartridge) -> Result<Self, RomParseError> {
+        Ok(Self {
+            memory: CartridgeMemory::new(cartridge),
+            mirroring: header_mirroring(&cartridge.ines_header)?,
+            prg_bank: 0,
+            irq_latch: 0,
+            irq_counter: 0,
+            irq_enabled: false,
+            irq_enable_after_ack: false,
+            irq_8bit: false,
+            irq_pending: false,
+        })
+    }
+
+    fn prg_offset(&self, addr: u16) -> usize {
+        let bank = match addr {
+            0x8000..=0xBFFF => self.prg_bank as usize & 0x07,
+            _ => (self.memory.prg_rom.len() / PRG_BANK_SIZE).max(1) - 1,
+        };
+        bank_offset(&self.memory.prg_rom, bank, PRG_BANK_SIZE, addr as usize)
+    }
+
+    fn write_latch_nibble(&mut self, nibble: u16, value: u8) {
+        let shift = nibble * 4;
+        self.irq_latch = (self.irq_latch & !(0xF << shift)) | ((value as u16 & 0xF) << shift);
+    }
+}
+
+impl Mapper for Vrc3 {
+    fn cpu_read(&mut self, addr: u16) -> Option<u8> {
+        match addr {
+            0x6000..=0x7FFF if !self.memory.prg_ram.is_empty() => {
+                Some(self.memory.prg_ram[(addr as usize - 0x6000) % self.memory.prg_ram.len()])
+            }
+            0x8000..=0xFFFF if !self.memory.prg_rom.is_empty() => {
+                Some(self.memory.prg_rom[self.prg_offset(addr)])
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x6000..=0x7FFF if !self.memory.prg_ram.is_empty() => {
+                let len = self.memory.prg_ram.len();
+                self.memory.prg_ram[(addr as usize - 0x6000) % len] = value;
+            }
+            0x8000..=0xBFFF => self.write_latch_nibble((addr - 0x8000) >> 12, value),
+            0xC000..=0xCFFF => {
+                self.irq_enable_after_ack = value & 0x01 != 0;
+                self.irq_enabled = value & 0x02 != 0;
+                self.irq_8bit = value & 0x04 != 0;
+                self.irq_pending = false;
+                if self.irq_enabled {
+                    self.irq_counter = self.irq_latch;
+                }
+            }
+            0xD000..=0xDFFF => {
+                self.irq_pending = false;
+                self.irq_enabled = self.irq_enable_after_ack;
+            }
+            0xF000..=0xFFFF => self.prg_bank = value,
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16, ciram: &Ciram) -> u8 {
+        match addr & 0x3FFF {
+            0x0000..=0x1FFF => {
+                self.memory.chr.get(addr as usize % self.memory.chr.len()).copied().unwrap_or(0)
+            }
+            addr => ciram[ciram_index(addr, self.mirroring)],
+        }
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8, ciram: &mut Ciram) {
+        match addr & 0x3FFF {
+            0x0000..=0x1FFF => {
+                if self.memory.chr_is_ram {
+                    let len = self.memory.chr.len();
+                    self.memory.chr[addr as usize % len] = value;
+                }
+            }
+            addr => ciram[ciram_index(addr, self.mirroring)] = value,
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.irq_pending
+    }
+
+    fn cpu_clock(&mut self) {
+        if !self.irq_enabled {
+            return;
+        }
+        if self.irq_8bit {
+            let low = self.irq_counter as u8;
+            if low == 0xFF {
+                self.irq_counter = (self.irq_counter & 0xFF00) | (self.irq_latch & 0x00FF);
+                self.irq_pending = true;
+            } else {
+                self.irq_counter = (self.irq_counter & 0xFF00) | (low + 1) as u16;
+            }
+        } else if self.irq_counter == 0xFFFF {
+            self.irq_counter = self.irq_latch;
+            self.irq_pending = true;
+        } else {
+            self.irq_counter += 1;
+        }
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        self.memory.save_data()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        self.memory.load_save_data(data);
+    }
+}
@@ -0,0 +1,243 @@
+use crate::nes::cartridge::Cartridge;
+use crate::nes::header::Mirroring;
+use crate::nes::mapper::vrc::{AddressLines, VrcIrq};
+use crate::nes::mapper::{bank_offset, ciram_index, Ciram, ExpansionAudio, Mapper};
+use crate::nes::memory::CartridgeMemory;
+
+
+const CHR_BANK_SIZE: usize = 1024;
+
+/// One VRC6 pulse channel ($9000-$9002 and $A000-$A002)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Vrc6Pulse {
+    /// 4-bit volume
+    pub volume: u8,
+    /// Duty cycle, 0-7 (duty is (n + 1) / 16)
+    pub duty: u8,
+    /// Mode bit: output the volume constantly, ignoring duty
+    pub constant: bool,
+    /// 12-bit period
+    pub period: u16,
+    pub enabled: bool,
+}
+
+/// The VRC6 sawtooth channel ($B000-$B002)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Vrc6Sawtooth {
+    /// 6-bit accumulator rate
+    pub accumulator_rate: u8,
+    /// 12-bit period
+    pub period: u16,
+    pub enabled: bool,
+}
+
+/// VRC6 expansion audio registers
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Vrc6Audio {
+    pub pulse: [Vrc6Pulse; 2],
+    pub sawtooth: Vrc6Sawtooth,
+    /// $9003 bit 0: halt all channels
+    pub halt: bool,
+    /// $9003 bits 1-2: periods are shifted right by this many bits (0, 4 or 8)
+    pub frequency_shift: u8,
+}
+
+impl Vrc6Audio {
+    /// Apply a write to a normalized audio register ($9000-$B002)
+    pub(crate) fn write(&mut self, register: u16, value: u8) {
+        match register {
+            0x9003 => {
+                self.halt = value & 0x01 != 0;
+                self.frequency_shift = if value & 0x04 != 0 {
+                    8
+                } else if value & 0x02 != 0 {
+                    4
+                } else {
+                    0
+                };
+            }
+            0x9000..=0x9002 | 0xA000..=0xA002 => {
+                let pulse = &mut self.pulse[((register >> 12) - 0x9) as usize];
+                match register & 3 {
+                    0 => {
+                        pulse.volume = value & 0x0F;
+                        pulse.duty = (value >> 4) & 0x07;
+                        pulse.constant = value & 0x80 != 0;
+                    }
+                    1 => pulse.period = (pulse.period & 0xF00) | value as u16,
+                    _ => {
+                        pulse.period = (pulse.period & 0x0FF) | ((value as u16 & 0x0F) << 8);
+                        pulse.enabled = value & 0x80 != 0;
+                    }
+                }
+            }
+            0xB000 => self.sawtooth.accumulator_rate = value & 0x3F,
+            0xB001 => self.sawtooth.period = (self.sawtooth.period & 0xF00) | value as u16,
+            0xB002 => {
+                self.sawtooth.period = (self.sawtooth.period & 0x0FF) | ((value as u16 & 0x0F) << 8);
+                self.sawtooth.enabled = value & 0x80 != 0;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Konami VRC6, iNES mappers 24 (VRC6a) and 26 (VRC6b)
+pub struct Vrc6 {
+    memory: CartridgeMemory,
+
+    lines: AddressLines,
+    /// $8000, 16 KiB bank
+    prg_bank_16k: u8,
+    /// $C000, 8 KiB bank
+    prg_bank_8k: u8,
+    /// $B003: PPU banking mode, mirroring and PRG-RAM enable
+    banking_control: u8,
+    /// $D000-$E003
+    chr_banks: [u8; 8],
+    irq: VrcIrq,
+    audio: Vrc6Audio,
+}
+
+impl Vrc6 {
+    pub fn new(cartridge: &Cartridge) -> Self {
+        let header = &cartridge.ines_header;
+
+        Self {
+            memory: CartridgeMemory::new(cartridge),
+            lines: AddressLines::vrc6(header),
+            prg_bank_16k: 0,
+            prg_bank_8k: 0,
+            banking_control: 0,
+            chr_banks: [0; 8],
+            irq: VrcIrq::default(),
+            audio: Vrc6Audio::default(),
+        }
+    }
+
+    /// Expansion audio register state
+    pub fn audio(&self) -> &Vrc6Audio {
+        &self.audio
+    }
+
+    fn prg_offset(&self, addr: u16) -> usize {
+        match addr {
+            0x8000..=0xBFFF => {
+                bank_offset(&self.memory.prg_rom, self.prg_bank_16k as usize & 0x0F, 0x4000, addr as usize)
+            }
+            0xC000..=0xDFFF => {
+                bank_offset(&self.memory.prg_rom, self.prg_bank_8k as usize & 0x1F, 0x2000, addr as usize)
+            }
+            _ => {
+                let last = (self.memory.prg_rom.len() / 0x2000).max(1) - 1;
+                bank_offset(&self.memory.prg_rom, last, 0x2000, addr as usize)
+            }
+        }
+    }
+
+    fn prg_ram_enabled(&self) -> bool {
+        !self.memory.prg_ram.is_empty() && self.banking_control & 0x80 != 0
+    }
+
+    fn chr_offset(&self, addr: u16) -> usize {
+        let slot = addr as usize / CHR_BANK_SIZE;
+        // registers always hold 1 KiB bank numbers; in 2 KiB banks PPU A10 replaces bit 0
+        let (register, size) = match self.banking_control & 3 {
+            0 => (slot, CHR_BANK_SIZE),
+            1 => (slot / 2, CHR_BANK_SIZE * 2),
+            _ if slot < 4 => (slot, CHR_BANK_SIZE),
+            _ => (4 + (slot - 4) / 2, CHR_BANK_SIZE * 2),
+        };
+        let bank = self.chr_banks[register] as usize * CHR_BANK_SIZE / size;
+        bank_offset(&self.memory.chr, bank, size, addr as usize)
+    }
+
+    fn nametable_mirroring(&self) -> Mirroring {
+        match (self.banking_control >> 2) & 3 {
+            0 => Mirroring::Vertical,
+            1 => Mirroring::Horizontal,
+            2 => Mirroring::SingleScreenA,
+            _ => Mirroring::SingleScreenB,
+        }
+    }
+}
+
+impl Mapper for Vrc6 {
+    fn cpu_read(&mut self, addr: u16) -> Option<u8> {
+        match addr {
+            0x6000..=0x7FFF if self.prg_ram_enabled() => {
+                Some(self.memory.prg_ram[(addr as usize - 0x6000) % self.memory.prg_ram.len()])
+            }
+            0x8000..=0xFFFF if !self.memory.prg_rom.is_empty() => {
+                Some(self.memory.prg_rom[self.prg_offset(addr)])
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x6000..=0x7FFF if self.prg_ram_enabled() => {
+                let len = self.memory.prg_ram.len();
+                self.memory.prg_ram[(addr as usize - 0x6000) % len] = value;
+            }
+            0x8000..=0xFFFF => {
+                let register = self.lines.register(addr);
+                match register {
+                    0x8000..=0x8003 => self.prg_bank_16k = value,
+                    0x9000..=0xB002 => self.audio.write(register, value),
+                    0xB003 => self.banking_control = value,
+                    0xC000..=0xC003 => self.prg_bank_8k = value,
+                    0xD000..=0xE003 => {
+                        let index = (((register >> 12) - 0xD) * 4 + (register & 3)) as usize;
+                        self.chr_banks[index] = value;
+                    }
+                    0xF000 => self.irq.write_latch(value),
+                    0xF001 => self.irq.write_control(value),
+                    0xF002 => self.irq.acknowledge(),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16, ciram: &Ciram) -> u8 {
+        match addr & 0x3FFF {
+            0x0000..=0x1FFF => self.memory.chr.get(self.chr_offset(addr)).copied().unwrap_or(0),
+            addr => ciram[ciram_index(addr, self.nametable_mirroring())],
+        }
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8, ciram: &mut Ciram) {
+        match addr & 0x3FFF {
+            0x0000..=0x1FFF => {
+                if self.memory.chr_is_ram {
+                    let offset = self.chr_offset(addr);
+                    self.memory.chr[offset] = value;
+                }
+            }
+            addr => ciram[ciram_index(addr, self.nametable_mirroring())] = value,
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.irq.pending()
+    }
+
+    fn cpu_clock(&mut self) {
+        self.irq.clock();
+    }
+
+    fn expansion_audio(&self) -> Option<ExpansionAudio<'_>> {
+        Some(ExpansionAudio::Vrc6(&self.audio))
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        self.memory.save_data()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        self.memory.load_save_data(data);
+    }
+}
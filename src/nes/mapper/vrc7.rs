@@ -0,0 +1,225 @@
+use crate::nes::cartridge::Cartridge;
+use crate::nes::header::Mirroring;
+use crate::nes::mapper::vrc::VrcIrq;
+use crate::nes::mapper::{bank_offset, ciram_index, Ciram, ExpansionAudio, Mapper};
+use crate::nes::memory::CartridgeMemory;
+
+
+const PRG_BANK_SIZE: usize = 8 * 1024;
+const CHR_BANK_SIZE: usize = 1024;
+/// The VRC7 sound core has six FM channels
+pub const VRC7_CHANNELS: usize = 6;
+
+/// Decoded state of one VRC7 FM channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Vrc7Channel {
+    /// 9-bit frequency number ($10-$15 and bit 0 of $20-$25)
+    pub fnum: u16,
+    /// 3-bit octave ($20-$25 bits 1-3)
+    pub octave: u8,
+    /// $20-$25 bit 4
+    pub key_on: bool,
+    /// $20-$25 bit 5
+    pub sustain: bool,
+    /// Instrument patch, 0 selects the custom instrument in $00-$07 ($30-$35 high nibble)
+    pub instrument: u8,
+    /// Attenuation ($30-$35 low nibble)
+    pub volume: u8,
+}
+
+/// VRC7 expansion audio registers, written through the $9010 address / $9030 data port
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vrc7Audio {
+    /// Last value written to the address port
+    pub address: u8,
+    /// The internal register file
+    pub registers: [u8; 0x40],
+    /// $E000 bit 6: audio held in reset
+    pub silenced: bool,
+}
+
+impl Default for Vrc7Audio {
+    fn default() -> Self {
+        Self { address: 0, registers: [0; 0x40], silenced: false }
+    }
+}
+
+impl Vrc7Audio {
+    /// The custom instrument patch ($00-$07)
+    pub fn custom_instrument(&self) -> &[u8] {
+        &self.registers[0x00..0x08]
+    }
+
+    /// Decode channel `index` (0-5)
+    pub fn channel(&self, index: usize) -> Vrc7Channel {
+        let low = self.registers[0x10 + index];
+        let control = self.registers[0x20 + index];
+        let patch = self.registers[0x30 + index];
+        Vrc7Channel {
+            fnum: low as u16 | ((control as u16 & 1) << 8),
+            octave: (control >> 1) & 7,
+            key_on: control & 0x10 != 0,
+            sustain: control & 0x20 != 0,
+            instrument: patch >> 4,
+            volume: patch & 0x0F,
+        }
+    }
+
+    fn write_data(&mut self, value: u8) {
+        if let Some(register) = self.registers.get_mut(self.address as usize) {
+            *register = value;
+        }
+    }
+}
+
+/// Konami VRC7, iNES mapper 85
+pub struct Vrc7 {
+    memory: CartridgeMemory,
+
+    /// CPU address lines selecting the second register of each pair (A3 on VRC7b, A4 on VRC7a)
+    register_line: u16,
+    prg_banks: [u8; 3],
+    chr_banks: [u8; 8],
+    /// $E000: mirroring, sound reset and PRG-RAM enable
+    control: u8,
+    irq: VrcIrq,
+    audio: Vrc7Audio,
+}
+
+impl Vrc7 {
+    pub fn new(cartridge: &Cartridge) -> Self {
+        let header = &cartridge.ines_header;
+        let register_line = match header.submapper {
+            1 => 0x08, // VRC7b
+            2 => 0x10, // VRC7a
+            _ => 0x18,
+        };
+
+        Self {
+            memory: CartridgeMemory::new(cartridge),
+            register_line,
+            prg_banks: [0; 3],
+            chr_banks: [0; 8],
+            control: 0,
+            irq: VrcIrq::default(),
+            audio: Vrc7Audio::default(),
+        }
+    }
+
+    /// Expansion audio register state
+    pub fn audio(&self) -> &Vrc7Audio {
+        &self.audio
+    }
+
+    fn prg_offset(&self, addr: u16) -> usize {
+        let bank = match (addr - 0x8000) as usize / PRG_BANK_SIZE {
+            slot @ 0..=2 => self.prg_banks[slot] as usize & 0x3F,
+            _ => (self.memory.prg_rom.len() / PRG_BANK_SIZE).max(1) - 1,
+        };
+        bank_offset(&self.memory.prg_rom, bank, PRG_BANK_SIZE, addr as usize)
+    }
+
+    fn prg_ram_enabled(&self) -> bool {
+        !self.memory.prg_ram.is_empty() && self.control & 0x80 != 0
+    }
+
+    fn chr_offset(&self, addr: u16) -> usize {
+        let bank = self.chr_banks[addr as usize / CHR_BANK_SIZE];
+        bank_offset(&self.memory.chr, bank as usize, CHR_BANK_SIZE, addr as usize)
+    }
+
+    fn nametable_mirroring(&self) -> Mirroring {
+        match self.control & 3 {
+            0 => Mirroring::Vertical,
+            1 => Mirroring::Horizontal,
+            2 => Mirroring::SingleScreenA,
+            _ => Mirroring::SingleScreenB,
+        }
+    }
+}
+
+impl Mapper for Vrc7 {
+    fn cpu_read(&mut self, addr: u16) -> Option<u8> {
+        match addr {
+            0x6000..=0x7FFF if self.prg_ram_enabled() => {
+                Some(self.memory.prg_ram[(addr as usize - 0x6000) % self.memory.prg_ram.len()])
+            }
+            0x8000..=0xFFFF if !self.memory.prg_rom.is_empty() => {
+                Some(self.memory.prg_rom[self.prg_offset(addr)])
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x6000..=0x7FFF if self.prg_ram_enabled() => {
+                let len = self.memory.prg_ram.len();
+                self.memory.prg_ram[(addr as usize - 0x6000) % len] = value;
+            }
+            // the audio ports decode A5 as well as A4
+            0x9010 => self.audio.address = value,
+            0x9030 => self.audio.write_data(value),
+            0x8000..=0xFFFF => {
+                let second = addr & self.register_line != 0;
+                match (addr & 0xF000, second) {
+                    (0x8000, false) => self.prg_banks[0] = value,
+                    (0x8000, true) => self.prg_banks[1] = value,
+                    (0x9000, false) => self.prg_banks[2] = value,
+                    (0xA000..=0xD000, _) => {
+                        let index = (((addr >> 12) - 0xA) * 2) as usize + second as usize;
+                        self.chr_banks[index] = value;
+                    }
+                    (0xE000, false) => {
+                        self.control = value;
+                        self.audio.silenced = value & 0x40 != 0;
+                    }
+                    (0xE000, true) => self.irq.write_latch(value),
+                    (0xF000, false) => self.irq.write_control(value),
+                    (0xF000, true) => self.irq.acknowledge(),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16, ciram: &Ciram) -> u8 {
+        match addr & 0x3FFF {
+            0x0000..=0x1FFF => self.memory.chr.get(self.chr_offset(addr)).copied().unwrap_or(0),
+            addr => ciram[ciram_index(addr, self.nametable_mirroring())],
+        }
+    }
+
+    fn ppu_write(&mut self, addr: u16, value: u8, ciram: &mut Ciram) {
+        match addr & 0x3FFF {
+            0x0000..=0x1FFF => {
+                if self.memory.chr_is_ram {
+                    let offset = self.chr_offset(addr);
+                    self.memory.chr[offset] = value;
+                }
+            }
+            addr => ciram[ciram_index(addr, self.nametable_mirroring())] = value,
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.irq.pending()
+    }
+
+    fn cpu_clock(&mut self) {
+        self.irq.clock();
+    }
+
+    fn expansion_audio(&self) -> Option<ExpansionAudio<'_>> {
+        Some(ExpansionAudio::Vrc7(&self.audio))
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        self.memory.save_data()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        self.memory.load_save_data(data);
+    }
+}
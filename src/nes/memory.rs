@@ -0,0 +1,159 @@
+use crate::nes::cartridge::Cartridge;
+use crate::nes::header::{InesHeader, RamSize};
+
+
+/// Size of the CPU window at $6000-$7FFF
+const PRG_RAM_WINDOW: usize = 0x2000;
+/// The trainer is loaded at $7000
+const TRAINER_OFFSET: usize = 0x1000;
+const DEFAULT_CHR_RAM_SIZE: usize = 8 * 1024;
+/// iNES cannot describe MMC5 work RAM, so assume the largest configuration like most
+/// emulators do
+const MMC5_DEFAULT_WRAM_SIZE: usize = 64 * 1024;
+
+/// ROM and RAM of a cartridge as a mapper sees it.
+///
+/// PRG-RAM and CHR-RAM are sized from the header's `RamSize` fields. Battery-backed memory
+/// occupies the start of each RAM, so `prg_ram[..prg_nvram_len]` is PRG-NVRAM and
+/// `chr[..chr_nvram_len]` is CHR-NVRAM.
+///
+/// A trainer sits at $7000, which is inside the PRG-NVRAM on battery-backed boards. It is
+/// copied again after a save is loaded, so the trainer always wins over the saved bytes;
+/// `save_data` still includes that range, as the game may use it once the trainer has run.
+#[derive(Debug, Clone)]
+pub struct CartridgeMemory {
+    pub prg_rom: Vec<u8>,
+    /// RAM at $6000-$7FFF (and wherever the mapper banks it), NVRAM first
+    pub prg_ram: Vec<u8>,
+    pub prg_nvram_len: usize,
+    /// CHR-ROM, or CHR-RAM when `chr_is_ram` is set
+    pub chr: Vec<u8>,
+    pub chr_is_ram: bool,
+    pub chr_nvram_len: usize,
+    trainer: Option<Vec<u8>>,
+}
+
+impl CartridgeMemory {
+    /// Allocate the cartridge's memory. iNES headers that leave the PRG-RAM size unspecified
+    /// get the usual amount for the mapper.
+    pub fn new(cartridge: &Cartridge) -> Self {
+        let (ram, nvram) = prg_ram_sizes(&cartridge.ines_header);
+        Self::with_prg_ram(cartridge, ram, nvram)
+    }
+
+    /// Allocate with PRG-RAM sizes the board determines itself, for mappers whose header
+    /// fields describe something else (an EEPROM, RAM inside the mapper chip).
+    pub(crate) fn with_prg_ram(cartridge: &Cartridge, ram: usize, nvram: usize) -> Self {
+        let header = &cartridge.ines_header;
+
+        let mut prg_ram = vec![0; nvram + ram];
+        if cartridge.trainer.is_some() && prg_ram.len() < PRG_RAM_WINDOW {
+            prg_ram.resize(PRG_RAM_WINDOW, 0);
+        }
+
+        let (chr, chr_is_ram, chr_nvram_len) = if !cartridge.chr_rom.is_empty() {
+            (cartridge.chr_rom.clone(), false, 0)
+        } else {
+            match header.chr_ram_size {
+                RamSize::Nes2 { ram, nvram } => {
+                    let size = ((ram + nvram) as usize).max(DEFAULT_CHR_RAM_SIZE);
+                    (vec![0; size], true, nvram as usize)
+                }
+                RamSize::Ines(_) => (vec![0; default_chr_ram(header.mapper)], true, 0),
+            }
+        };
+
+        let mut memory = Self {
+            prg_rom: cartridge.prg_rom.clone(),
+            prg_ram,
+            prg_nvram_len: nvram,
+            chr,
+            chr_is_ram,
+            chr_nvram_len,
+            trainer: cartridge.trainer.clone(),
+        };
+        memory.copy_trainer();
+        memory
+    }
+
+    fn copy_trainer(&mut self) {
+        if let Some(trainer) = &self.trainer {
+            self.prg_ram[TRAINER_OFFSET..TRAINER_OFFSET + trainer.len()].copy_from_slice(trainer);
+        }
+    }
+
+    /// PRG-NVRAM followed by CHR-NVRAM, or `None` when nothing is battery-backed
+    pub fn save_data(&self) -> Option<Vec<u8>> {
+        if self.prg_nvram_len == 0 && self.chr_nvram_len == 0 {
+            return None;
+        }
+        let mut data = self.prg_ram[..self.prg_nvram_len].to_vec();
+        data.extend_from_slice(&self.chr[..self.chr_nvram_len]);
+        Some(data)
+    }
+
+    /// Restore contents previously returned by `save_data`
+    pub fn load_save_data(&mut self, data: &[u8]) {
+        let prg_len = self.prg_nvram_len.min(data.len());
+        self.prg_ram[..prg_len].copy_from_slice(&data[..prg_len]);
+        let rest = &data[prg_len..];
+        let chr_len = self.chr_nvram_len.min(rest.len());
+        self.chr[..chr_len].copy_from_slice(&rest[..chr_len]);
+        self.copy_trainer();
+    }
+}
+
+/// Volatile and battery-backed PRG-RAM sizes in bytes. NES 2.0 states both; an iNES header
+/// states at most the total (byte 8, 0 = unspecified unless byte 10 says there is none), all
+/// of it battery-backed when the battery flag is set.
+pub(crate) fn prg_ram_sizes(header: &InesHeader) -> (usize, usize) {
+    let battery = header.flags_6.battery_backed();
+    match header.prg_ram_size {
+        RamSize::Nes2 { ram, nvram } => (ram as usize, nvram as usize),
+        // only the first 8 KiB chip of the assumed MMC5 work RAM is battery-backed, as on
+        // EKROM boards, rather than saving all 64 KiB
+        RamSize::Ines(0) if header.mapper == 5 && battery => {
+            (MMC5_DEFAULT_WRAM_SIZE - PRG_RAM_WINDOW, PRG_RAM_WINDOW)
+        }
+        RamSize::Ines(size) => {
+            let size = match size {
+                0 if !header.prg_ram_present() => 0,
+                0 => default_prg_ram(header),
+                size => size as usize,
+            };
+            if battery { (0, size) } else { (size, 0) }
+        }
+    }
+}
+
+/// Battery-backed CHR-RAM in bytes, which only NES 2.0 headers state
+pub(crate) fn chr_nvram_len(header: &InesHeader) -> usize {
+    match header.chr_ram_size {
+        RamSize::Nes2 { nvram, .. } if header.chr_rom_size == 0 => nvram as usize,
+        _ => 0,
+    }
+}
+
+/// PRG-RAM of the usual board for an iNES mapper that does not state it
+fn default_prg_ram(header: &InesHeader) -> usize {
+    match header.mapper {
+        5 => MMC5_DEFAULT_WRAM_SIZE,
+        // Bandai's FCG boards keep their saves in an EEPROM
+        16 | 157 | 159 => 0,
+        // discrete boards, MMC2, VRC1 and VRC2a have nothing at $6000-$7FFF unless the
+        // battery flag says a board variant adds it
+        0 | 2 | 3 | 7 | 9 | 11 | 13 | 22 | 66 | 71 | 75 | 87 | 140 if !header.flags_6.battery_backed() => 0,
+        _ => PRG_RAM_WINDOW,
+    }
+}
+
+/// CHR-RAM of the usual board for an iNES mapper without CHR-ROM
+fn default_chr_ram(mapper: u16) -> usize {
+    match mapper {
+        // CPROM
+        13 => 16 * 1024,
+        // UNROM 512 and GTROM
+        30 | 111 => 32 * 1024,
+        _ => DEFAULT_CHR_RAM_SIZE,
+    }
+}
@@ -1,3 +1,8 @@
 pub mod header;
 pub mod cartridge;
-pub mod error;
\ No newline at end of file
+pub mod dump;
+pub mod error;
+pub mod mapper;
+pub mod memory;
+pub mod timing;
+pub mod vs;
\ No newline at end of file
@@ -0,0 +1,114 @@
+use crate::nes::header::TimingMode;
+
+
+const DOTS_PER_SCANLINE: u16 = 341;
+const VISIBLE_SCANLINES: u16 = 240;
+
+/// Clock rates and frame layout of one console region
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimingParameters {
+    /// Crystal frequency in Hz, which the CPU and PPU clocks are divided from
+    pub master_clock: f64,
+    /// Master clock cycles per CPU cycle
+    pub cpu_divider: u8,
+    /// Master clock cycles per PPU dot
+    pub ppu_divider: u8,
+    /// Scanlines per frame, counting the pre-render line
+    pub scanlines: u16,
+    /// Idle lines between the visible picture and the start of vblank
+    pub post_render_scanlines: u16,
+    /// Lines from the vblank flag being set to the pre-render line, when the CPU may use
+    /// the PPU freely
+    pub vblank_scanlines: u16,
+    /// The pre-render line is one dot shorter on odd frames while rendering is enabled
+    pub skips_odd_frame_dot: bool,
+}
+
+impl TimingParameters {
+    /// RP2A03 and RP2C02, 236.25/11 MHz
+    pub const NTSC: Self = Self {
+        master_clock: 236_250_000.0 / 11.0,
+        cpu_divider: 12,
+        ppu_divider: 4,
+        scanlines: 262,
+        post_render_scanlines: 1,
+        vblank_scanlines: 20,
+        skips_odd_frame_dot: true,
+    };
+
+    /// RP2A07 and RP2C07, 26.6017125 MHz
+    pub const PAL: Self = Self {
+        master_clock: 26_601_712.5,
+        cpu_divider: 16,
+        ppu_divider: 5,
+        scanlines: 312,
+        post_render_scanlines: 1,
+        vblank_scanlines: 70,
+        skips_odd_frame_dot: false,
+    };
+
+    /// UMC famiclones: the PAL crystal and frame, with NTSC's CPU to PPU ratio and vblank
+    /// length. The extra lines are spent after the picture instead, so NTSC games run
+    /// unmodified.
+    pub const DENDY: Self = Self {
+        master_clock: 26_601_712.5,
+        cpu_divider: 15,
+        ppu_divider: 5,
+        scanlines: 312,
+        post_render_scanlines: 51,
+        vblank_scanlines: 20,
+        skips_odd_frame_dot: false,
+    };
+
+    /// Parameters of the console a game is run on. Games that work in several regions are
+    /// run as NTSC, like most emulators do.
+    pub const fn for_mode(mode: TimingMode) -> Self {
+        match mode {
+            TimingMode::NTSC | TimingMode::MultipleRegions => Self::NTSC,
+            TimingMode::PAL => Self::PAL,
+            TimingMode::Dendy => Self::DENDY,
+        }
+    }
+
+    pub fn cpu_clock(&self) -> f64 {
+        self.master_clock / self.cpu_divider as f64
+    }
+
+    pub fn ppu_clock(&self) -> f64 {
+        self.master_clock / self.ppu_divider as f64
+    }
+
+    /// PPU dots per CPU cycle: 3 on NTSC and Dendy, 3.2 on PAL
+    pub fn dots_per_cpu_cycle(&self) -> f64 {
+        self.cpu_divider as f64 / self.ppu_divider as f64
+    }
+
+    /// Average PPU dots per frame with rendering enabled
+    pub fn dots_per_frame(&self) -> f64 {
+        let dots = self.scanlines as f64 * DOTS_PER_SCANLINE as f64;
+        if self.skips_odd_frame_dot { dots - 0.5 } else { dots }
+    }
+
+    pub fn cpu_cycles_per_frame(&self) -> f64 {
+        self.dots_per_frame() / self.dots_per_cpu_cycle()
+    }
+
+    pub fn cpu_cycles_per_scanline(&self) -> f64 {
+        DOTS_PER_SCANLINE as f64 / self.dots_per_cpu_cycle()
+    }
+
+    /// Frames per second: about 60.0988 on NTSC and 50.0070 on PAL and Dendy
+    pub fn frame_rate(&self) -> f64 {
+        self.ppu_clock() / self.dots_per_frame()
+    }
+
+    /// Scanline the vblank flag is set and NMI fires on, counting the first visible line as 0
+    pub fn vblank_start_scanline(&self) -> u16 {
+        VISIBLE_SCANLINES + self.post_render_scanlines
+    }
+
+    /// CPU cycles from the start of vblank to the pre-render line
+    pub fn vblank_cpu_cycles(&self) -> f64 {
+        self.vblank_scanlines as f64 * self.cpu_cycles_per_scanline()
+    }
+}
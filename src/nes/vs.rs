@@ -0,0 +1,185 @@
+/// PPU of a Vs. System board, from the low nibble of NES 2.0 byte 13
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VsPpuType {
+    /// RP2C03 or RC2C03: the RGB PPU with the standard palette order
+    RP2C03,
+    /// RP2C04 variants, each with its own scrambled palette order
+    RP2C04_0001,
+    RP2C04_0002,
+    RP2C04_0003,
+    RP2C04_0004,
+    /// RC2C05 variants: $2000 and $2001 are swapped and $2002 returns an ID in its low bits
+    RC2C05_01,
+    RC2C05_02,
+    RC2C05_03,
+    RC2C05_04,
+    Reserved(u8),
+}
+
+impl VsPpuType {
+    // This has to be a const fn
+    pub const fn into_bits(self) -> u8 {
+        match self {
+            Self::RP2C03 => 0x0,
+            Self::RP2C04_0001 => 0x2,
+            Self::RP2C04_0002 => 0x3,
+            Self::RP2C04_0003 => 0x4,
+            Self::RP2C04_0004 => 0x5,
+            Self::RC2C05_01 => 0x8,
+            Self::RC2C05_02 => 0x9,
+            Self::RC2C05_03 => 0xA,
+            Self::RC2C05_04 => 0xB,
+            Self::Reserved(value) => value,
+        }
+    }
+
+    pub const fn from_bits(value: u8) -> Self {
+        match value & 0x0F {
+            0x0 => Self::RP2C03,
+            0x2 => Self::RP2C04_0001,
+            0x3 => Self::RP2C04_0002,
+            0x4 => Self::RP2C04_0003,
+            0x5 => Self::RP2C04_0004,
+            0x8 => Self::RC2C05_01,
+            0x9 => Self::RC2C05_02,
+            0xA => Self::RC2C05_03,
+            0xB => Self::RC2C05_04,
+            value => Self::Reserved(value),
+        }
+    }
+
+    /// Table mapping the PPU's palette indices to the RP2C03 index of the same color, for
+    /// the RP2C04 variants. Games written for one RP2C04 show wrong colors on any other PPU.
+    pub fn palette_lut(&self) -> Option<&'static [u8; 64]> {
+        match self {
+            Self::RP2C04_0001 => Some(&RP2C04_0001_PALETTE),
+            Self::RP2C04_0002 => Some(&RP2C04_0002_PALETTE),
+            Self::RP2C04_0003 => Some(&RP2C04_0003_PALETTE),
+            Self::RP2C04_0004 => Some(&RP2C04_0004_PALETTE),
+            _ => None,
+        }
+    }
+
+    /// Index into the RP2C03 palette for a color written to palette RAM
+    pub fn palette_index(&self, color: u8) -> u8 {
+        let color = color & 0x3F;
+        self.palette_lut().map_or(color, |lut| lut[color as usize])
+    }
+
+    /// True for the RC2C05, which decodes PPUCTRL at $2001 and PPUMASK at $2000
+    pub fn swaps_ctrl_and_mask(&self) -> bool {
+        matches!(self, Self::RC2C05_01 | Self::RC2C05_02 | Self::RC2C05_03 | Self::RC2C05_04)
+    }
+
+    /// PPU register (0-7) a CPU access to `addr` in $2000-$3FFF reaches
+    pub fn register(&self, addr: u16) -> u8 {
+        let register = (addr & 0x07) as u8;
+        match register {
+            0 | 1 if self.swaps_ctrl_and_mask() => register ^ 1,
+            _ => register,
+        }
+    }
+
+    /// Value the RC2C05 returns in bits 0-5 of PPUSTATUS, which games check to refuse to
+    /// run on the wrong board
+    pub fn status_id(&self) -> Option<u8> {
+        match self {
+            Self::RC2C05_01 | Self::RC2C05_04 => Some(0x1B),
+            Self::RC2C05_02 => Some(0x3D),
+            Self::RC2C05_03 => Some(0x1C),
+            _ => None,
+        }
+    }
+
+    /// PPUSTATUS as read by the CPU, given the vblank, sprite 0 and overflow flags in bits 5-7.
+    /// Other PPUs leave the open bus value in the low bits.
+    pub fn read_status(&self, flags: u8, open_bus: u8) -> u8 {
+        match self.status_id() {
+            Some(id) => (flags & 0xC0) | id,
+            None => (flags & 0xE0) | (open_bus & 0x1F),
+        }
+    }
+}
+
+/// Vs. System board and copy protection, from the high nibble of NES 2.0 byte 13
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VsHardwareType {
+    /// Vs. Unisystem, one CPU and PPU
+    Unisystem,
+    /// Unisystem with the RBI Baseball protection at $5E00-$5E01
+    UnisystemRbiBaseball,
+    /// Unisystem with the TKO Boxing protection at $5E00-$5E01
+    UnisystemTkoBoxing,
+    /// Unisystem with the Super Xevious protection at $54FF-$5678
+    UnisystemSuperXevious,
+    /// Unisystem with the Japanese Vs. Ice Climber's inverted coin and service inputs
+    UnisystemIceClimberJapan,
+    /// Vs. DualSystem: two CPUs and PPUs sharing RAM at $6000-$7FFF
+    DualSystem,
+    /// DualSystem with the Raid on Bungeling Bay protection
+    DualSystemRaidOnBungelingBay,
+    Reserved(u8),
+}
+
+impl VsHardwareType {
+    // This has to be a const fn
+    pub const fn into_bits(self) -> u8 {
+        match self {
+            Self::Unisystem => 0,
+            Self::UnisystemRbiBaseball => 1,
+            Self::UnisystemTkoBoxing => 2,
+            Self::UnisystemSuperXevious => 3,
+            Self::UnisystemIceClimberJapan => 4,
+            Self::DualSystem => 5,
+            Self::DualSystemRaidOnBungelingBay => 6,
+            Self::Reserved(value) => value,
+        }
+    }
+
+    pub const fn from_bits(value: u8) -> Self {
+        match value & 0x0F {
+            0 => Self::Unisystem,
+            1 => Self::UnisystemRbiBaseball,
+            2 => Self::UnisystemTkoBoxing,
+            3 => Self::UnisystemSuperXevious,
+            4 => Self::UnisystemIceClimberJapan,
+            5 => Self::DualSystem,
+            6 => Self::DualSystemRaidOnBungelingBay,
+            value => Self::Reserved(value),
+        }
+    }
+
+    pub fn is_dual_system(&self) -> bool {
+        matches!(self, Self::DualSystem | Self::DualSystemRaidOnBungelingBay)
+    }
+}
+
+// Palette index to RP2C03 index. The RP2C04s have no color at the RP2C03's 0x1A, 0x2F,
+// 0x30 and 0x3B, and repeat black (0x2E) and white (0x20) instead.
+pub const RP2C04_0001_PALETTE: [u8; 64] = [
+    0x35, 0x23, 0x16, 0x22, 0x1C, 0x09, 0x1D, 0x15, 0x20, 0x00, 0x27, 0x05, 0x04, 0x28, 0x08, 0x20,
+    0x21, 0x3E, 0x1F, 0x29, 0x3C, 0x32, 0x36, 0x12, 0x3F, 0x2B, 0x2E, 0x1E, 0x3D, 0x2D, 0x24, 0x01,
+    0x0E, 0x31, 0x33, 0x2A, 0x2C, 0x0C, 0x1B, 0x14, 0x2E, 0x07, 0x34, 0x06, 0x13, 0x02, 0x26, 0x2E,
+    0x2E, 0x19, 0x10, 0x0A, 0x39, 0x03, 0x37, 0x17, 0x0F, 0x11, 0x0B, 0x0D, 0x38, 0x25, 0x18, 0x3A,
+];
+
+pub const RP2C04_0002_PALETTE: [u8; 64] = [
+    0x2E, 0x27, 0x18, 0x39, 0x3A, 0x25, 0x1C, 0x31, 0x16, 0x13, 0x38, 0x34, 0x20, 0x23, 0x3C, 0x0B,
+    0x0F, 0x21, 0x06, 0x3D, 0x1B, 0x29, 0x1E, 0x22, 0x1D, 0x24, 0x0E, 0x2B, 0x32, 0x08, 0x2E, 0x03,
+    0x04, 0x36, 0x26, 0x33, 0x11, 0x1F, 0x10, 0x02, 0x14, 0x3F, 0x00, 0x09, 0x12, 0x2E, 0x28, 0x20,
+    0x3E, 0x0D, 0x2A, 0x17, 0x0C, 0x01, 0x15, 0x19, 0x2E, 0x2C, 0x07, 0x37, 0x35, 0x05, 0x0A, 0x2D,
+];
+
+pub const RP2C04_0003_PALETTE: [u8; 64] = [
+    0x14, 0x25, 0x3A, 0x10, 0x0B, 0x20, 0x31, 0x09, 0x01, 0x2E, 0x36, 0x08, 0x15, 0x3D, 0x3E, 0x3C,
+    0x22, 0x1C, 0x05, 0x12, 0x19, 0x18, 0x17, 0x1B, 0x00, 0x03, 0x2E, 0x02, 0x16, 0x06, 0x34, 0x35,
+    0x23, 0x0F, 0x0E, 0x37, 0x0D, 0x27, 0x26, 0x20, 0x29, 0x04, 0x21, 0x24, 0x11, 0x2D, 0x2E, 0x1F,
+    0x2C, 0x1E, 0x39, 0x33, 0x07, 0x2A, 0x28, 0x1D, 0x0A, 0x2E, 0x32, 0x38, 0x13, 0x2B, 0x3F, 0x0C,
+];
+
+pub const RP2C04_0004_PALETTE: [u8; 64] = [
+    0x18, 0x03, 0x1C, 0x28, 0x2E, 0x35, 0x01, 0x17, 0x10, 0x1F, 0x2A, 0x0E, 0x36, 0x37, 0x0B, 0x39,
+    0x25, 0x1E, 0x12, 0x34, 0x2E, 0x1D, 0x06, 0x26, 0x3E, 0x1B, 0x22, 0x19, 0x04, 0x2E, 0x3A, 0x21,
+    0x05, 0x0A, 0x07, 0x02, 0x13, 0x14, 0x00, 0x15, 0x0C, 0x3D, 0x11, 0x0F, 0x0D, 0x38, 0x2D, 0x24,
+    0x33, 0x20, 0x08, 0x16, 0x3F, 0x2B, 0x20, 0x3C, 0x2E, 0x27, 0x23, 0x31, 0x29, 0x32, 0x2C, 0x09,
+];
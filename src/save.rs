@@ -0,0 +1,121 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+
+/// Problems found while loading a save file that were repaired rather than rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveWarning {
+    /// The file was shorter than the cartridge's save RAM; the rest was zero-filled
+    Padded { expected: usize, found: usize },
+    /// The file was longer than the cartridge's save RAM; the excess was dropped
+    Truncated { expected: usize, found: usize },
+}
+
+/// Battery-backed memory of a cartridge, sized from its header, as stored in a `.sav` file.
+///
+/// Created with `nes::cartridge::Cartridge::save_ram` or `gb::cartridge::Cartridge::save_ram`.
+/// The bytes are what the mapper's `save_data`/`load_save_data` exchange.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveRam {
+    ram: Vec<u8>,
+    /// Data emulators append after the RAM (the Game Boy RTC footer), kept as-is
+    footer: Vec<u8>,
+    /// Footer lengths accepted after the RAM without a warning
+    footer_sizes: &'static [usize],
+}
+
+impl SaveRam {
+    /// Zeroed save RAM of `size` bytes
+    pub fn new(size: usize) -> Self {
+        Self::with_footer(size, &[])
+    }
+
+    pub(crate) fn with_footer(size: usize, footer_sizes: &'static [usize]) -> Self {
+        Self { ram: vec![0; size], footer: Vec::new(), footer_sizes }
+    }
+
+    /// Size of the save RAM, without any footer
+    pub fn size(&self) -> usize {
+        self.ram.len()
+    }
+
+    pub fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    pub fn ram_mut(&mut self) -> &mut [u8] {
+        &mut self.ram
+    }
+
+    /// Footer loaded along with the RAM, empty when there was none
+    pub fn footer(&self) -> &[u8] {
+        &self.footer
+    }
+
+    /// Contents of the save file: the RAM followed by the footer
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.ram.clone();
+        bytes.extend(&self.footer);
+        bytes
+    }
+
+    /// Replace the contents with a save file's bytes, padding or truncating to the RAM size.
+    pub fn load(&mut self, bytes: &[u8]) -> Vec<SaveWarning> {
+        let expected = self.ram.len();
+        let found = bytes.len();
+        self.ram.fill(0);
+        self.footer.clear();
+
+        let len = expected.min(found);
+        self.ram[..len].copy_from_slice(&bytes[..len]);
+
+        if found < expected {
+            vec![SaveWarning::Padded { expected, found }]
+        } else if found == expected || self.footer_sizes.contains(&(found - expected)) {
+            self.footer.extend(&bytes[expected..]);
+            Vec::new()
+        } else {
+            vec![SaveWarning::Truncated { expected, found }]
+        }
+    }
+
+    /// Load a `.sav` file. A missing file leaves the RAM blank, as on a new cartridge.
+    pub fn load_file(&mut self, path: impl AsRef<Path>) -> io::Result<Vec<SaveWarning>> {
+        match fs::read(path) {
+            Ok(bytes) => Ok(self.load(&bytes)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                self.ram.fill(0);
+                self.footer.clear();
+                Ok(Vec::new())
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Write a `.sav` file atomically: the data goes to a temporary file next to `path`,
+    /// which then replaces it, so a crash never leaves a half-written save behind.
+    pub fn store_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let temp = temp_path(path);
+
+        let result = File::create(&temp).and_then(|mut file| {
+            file.write_all(&self.to_bytes())?;
+            file.sync_all()
+        });
+        match result.and_then(|()| fs::rename(&temp, path)) {
+            Ok(()) => Ok(()),
+            Err(error) => {
+                let _ = fs::remove_file(&temp);
+                Err(error)
+            }
+        }
+    }
+}
+
+/// `game.sav` -> `game.sav.tmp`, in the same directory so the rename stays on one filesystem
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
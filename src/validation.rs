@@ -0,0 +1,109 @@
+use std::fmt;
+
+
+/// How loaders treat problems in a ROM image
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LoadPolicy {
+    /// Fail on the first error, as the hardware or a careful emulator would
+    #[default]
+    Strict,
+    /// Record errors and keep loading where the file can still be made sense of, for tools
+    /// that inspect broken dumps
+    Lenient,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// Unusual but harmless, or repaired without guessing
+    Warning,
+    /// The file is wrong; only a lenient load gets past it
+    Error,
+}
+
+/// Something found wrong with a ROM image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// A checksum in the header does not match the data it covers
+    ChecksumMismatch { stored: u16, computed: u16 },
+    /// The Game Boy logo is not Nintendo's; a lenient load or logo check lets it through
+    LogoMismatch,
+    /// Bytes the format reserves are not zero, typically a tool's signature such as "DiskDude!"
+    ReservedBytesNotZero,
+    /// A code the format does not define
+    UnknownCode(u8),
+    /// The file is smaller than the header says; a lenient load pads it
+    Underdump { expected: usize, found: usize },
+    /// The file has data beyond what the header describes
+    Overdump { expected: usize, found: usize },
+    /// A size in the header is larger than the cartridge hardware can address
+    SizeExceedsHardware { size: u32, limit: u32 },
+    /// The ROM is not a whole number of banks; a lenient load pads the last one
+    PartialBank { size: usize, bank_size: usize },
+}
+
+/// A problem and where in the file it was found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Byte offset in the file of the field or data concerned
+    pub offset: usize,
+    pub problem: Problem,
+}
+
+/// Everything a loader noticed about a ROM image, in file order of discovery
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ValidationReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|diagnostic| diagnostic.severity == Severity::Warning)
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+
+    /// True when nothing at all was found
+    pub fn is_clean(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    pub(crate) fn warn(&mut self, offset: usize, problem: Problem) {
+        self.diagnostics.push(Diagnostic { severity: Severity::Warning, offset, problem });
+    }
+
+    /// Record an error; a strict load stops with `error`, a lenient one carries on
+    pub(crate) fn error<E>(
+        &mut self,
+        policy: LoadPolicy,
+        offset: usize,
+        problem: Problem,
+        error: E,
+    ) -> Result<(), E> {
+        self.diagnostics.push(Diagnostic { severity: Severity::Error, offset, problem });
+        match policy {
+            LoadPolicy::Strict => Err(error),
+            LoadPolicy::Lenient => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{} at {:#06X}: {:?}", severity, self.offset, self.problem)
+    }
+}
@@ -0,0 +1,677 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use emurom::gb::cartridge::Cartridge;
+use emurom::gb::header::{CartridgeType, Controller};
+use emurom::gb::mbc::mbc1::is_mbc1m;
+use emurom::gb::mbc::camera::{ImageSensor, SENSOR_HEIGHT, SENSOR_WIDTH};
+use emurom::gb::mbc::huc3::{HuC3, HUC3_FOOTER_SIZE};
+use emurom::gb::mbc::mbc3::Mbc3;
+use emurom::gb::mbc::mbc7::Accelerometer;
+use emurom::gb::mbc::rtc::RTC_FOOTER_SIZE;
+use emurom::gb::mbc::tama5::{Tama5, Tama5Time, TAMA5_FOOTER_SIZE, TAMA5_RAM_SIZE};
+use emurom::gb::mbc::{new_mbc, Mbc};
+
+
+const LOGO: [u8; 48] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+    0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+    0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
+];
+
+/// Write a valid header (logo, type, sizes, checksum) at the start of `bank`
+fn write_header(rom: &mut [u8], bank: usize, cartridge_type: u8, rom_code: u8, ram_code: u8) {
+    let base = bank * 0x4000;
+    rom[base + 0x104..base + 0x134].copy_from_slice(&LOGO);
+    rom[base + 0x134..base + 0x143].fill(0);
+    rom[base + 0x134..base + 0x138].copy_from_slice(b"TEST");
+    rom[base + 0x143] = 0;
+    rom[base + 0x147] = cartridge_type;
+    rom[base + 0x148] = rom_code;
+    rom[base + 0x149] = ram_code;
+    let checksum = rom[base + 0x134..base + 0x14D]
+        .iter()
+        .fold(0u8, |acc, &byte| acc.wrapping_sub(byte).wrapping_sub(1));
+    rom[base + 0x14D] = checksum;
+}
+
+/// Build a ROM where every 16 KiB bank is filled with its bank number, with the high byte of
+/// the number at offset 1
+fn build_rom(cartridge_type: u8, rom_code: u8, ram_code: u8) -> Vec<u8> {
+    let banks = 2usize << rom_code;
+    let mut rom: Vec<u8> = (0..banks).flat_map(|bank| std::iter::repeat_n(bank as u8, 0x4000)).collect();
+    for bank in 0..banks {
+        rom[bank * 0x4000 + 1] = (bank >> 8) as u8;
+    }
+    write_header(&mut rom, 0, cartridge_type, rom_code, ram_code);
+    rom
+}
+
+fn load(rom: Vec<u8>) -> Cartridge {
+    Cartridge::load_rom_data(&mut rom.as_slice()).expect("Failed to load generated ROM")
+}
+
+/// MBC3 with a controllable host clock
+fn mbc3_with_clock(cartridge: &Cartridge, now: &Rc<Cell<u64>>) -> Mbc3 {
+    let now = now.clone();
+    Mbc3::with_clock(cartridge, move || now.get())
+}
+
+/// Latch the RTC and read back S, M, H, DL, DH
+fn read_rtc(mbc: &mut dyn Mbc) -> [u8; 5] {
+    mbc.write(0x6000, 0);
+    mbc.write(0x6000, 1);
+    std::array::from_fn(|index| {
+        mbc.write(0x4000, 0x08 + index as u8);
+        mbc.read(0xA000)
+    })
+}
+
+fn write_rtc(mbc: &mut dyn Mbc, registers: [u8; 5]) {
+    for (index, value) in registers.into_iter().enumerate() {
+        mbc.write(0x4000, 0x08 + index as u8);
+        mbc.write(0xA000, value);
+    }
+}
+
+/// Drive the MBC7 EEPROM pins through $A080: CS high, one bit clocked in on DI
+fn mbc7_clock_bit(mbc: &mut dyn Mbc, bit: bool) -> bool {
+    let di = (bit as u8) << 1;
+    mbc.write(0xA080, 0x80 | di);
+    mbc.write(0xA080, 0xC0 | di);
+    mbc.read(0xA080) & 1 != 0
+}
+
+/// Select the chip and send a start bit, a 2-bit opcode, an 8-bit address and optional data
+fn mbc7_command(mbc: &mut dyn Mbc, opcode: u8, address: u8, data: Option<u16>) {
+    mbc.write(0xA080, 0x00);
+    mbc.write(0xA080, 0x80);
+    let bits = (1 << 10) | (opcode as u32) << 8 | address as u32;
+    for bit in (0..11).rev() {
+        mbc7_clock_bit(mbc, bits >> bit & 1 != 0);
+    }
+    if let Some(data) = data {
+        for bit in (0..16).rev() {
+            mbc7_clock_bit(mbc, data >> bit & 1 != 0);
+        }
+    }
+}
+
+struct FixedTilt(f32, f32);
+
+impl Accelerometer for FixedTilt {
+    fn acceleration(&mut self) -> (f32, f32) {
+        (self.0, self.1)
+    }
+}
+
+/// Horizontal gradient from black on the left to almost white on the right
+struct Gradient;
+
+impl ImageSensor for Gradient {
+    fn frame(&mut self) -> Vec<u8> {
+        (0..SENSOR_WIDTH * SENSOR_HEIGHT).map(|index| (index % SENSOR_WIDTH * 2) as u8).collect()
+    }
+}
+
+/// Send a HuC3 RTC command and return the response nibble
+fn huc3_command(mbc: &mut dyn Mbc, command: u8) -> u8 {
+    mbc.write(0x0000, 0x0B);
+    mbc.write(0xA000, command);
+    mbc.write(0x0000, 0x0C);
+    mbc.read(0xA000) & 0x0F
+}
+
+/// Write a nibble to a TAMA5 register
+fn tama5_register(mbc: &mut dyn Mbc, register: u8, value: u8) {
+    mbc.write(0xA001, register);
+    mbc.write(0xA000, value);
+}
+
+/// Run a TAMA5 command and return the read value registers
+fn tama5_command(mbc: &mut dyn Mbc, command: u8, address: u8, value: u8) -> u8 {
+    tama5_register(mbc, 0x4, value & 0x0F);
+    tama5_register(mbc, 0x5, value >> 4);
+    tama5_register(mbc, 0x6, command << 1 | address >> 4);
+    tama5_register(mbc, 0x7, address & 0x0F);
+    mbc.write(0xA001, 0xC);
+    let low = mbc.read(0xA000) & 0x0F;
+    mbc.write(0xA001, 0xD);
+    low | (mbc.read(0xA000) & 0x0F) << 4
+}
+
+#[test]
+fn test_rom_data_keeps_header() {
+    let cartridge = load(build_rom(0x00, 0, 0));
+    assert_eq!(cartridge.rom_data.len(), 0x8000);
+    assert_eq!(&cartridge.rom_data[0x104..0x134], &LOGO);
+}
+
+#[test]
+fn test_every_cartridge_type_supported() {
+    for code in 0..=0xFF {
+        if CartridgeType::from_bits(code).is_some() {
+            let cartridge = load(build_rom(code, 0, 0));
+            let mut mbc = new_mbc(&cartridge);
+            // MMM01 boots into the menu in its last banks instead
+            if cartridge.gb_header.capabilities().controller != Controller::Mmm01 {
+                assert_eq!(mbc.read(0x0147), code, "cartridge type {:#04X} does not map bank 0", code);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_cartridge_capabilities() {
+    let caps = CartridgeType::MBC2.capabilities();
+    assert_eq!(caps.controller, Controller::Mbc2);
+    assert!(caps.ram && !caps.battery);
+    assert_eq!(caps.internal_ram, 512, "MBC2 RAM is inside the controller");
+    let header = load(build_rom(0x06, 1, 0)).gb_header;
+    assert!(header.has_ram() && header.has_battery(), "MBC2 RAM missing without a RAM size");
+
+    let caps = CartridgeType::MBC3TimerBattery.capabilities();
+    assert!(caps.rtc && caps.battery && !caps.ram);
+
+    let caps = CartridgeType::MBC7SensorRumbleRamBattery.capabilities();
+    assert_eq!(caps.controller, Controller::Mbc7);
+    assert!(caps.accelerometer && caps.rumble && caps.battery);
+    assert_eq!(caps.internal_ram, 256);
+
+    let caps = CartridgeType::HuC3.capabilities();
+    assert!(caps.battery && caps.rtc && caps.infrared, "HuC3 type implies its hardware");
+    assert!(CartridgeType::PocketCamera.capabilities().camera);
+    assert!(CartridgeType::HuC1RamBattery.capabilities().infrared);
+    assert!(CartridgeType::MBC5Rumble.capabilities().rumble);
+
+    let caps = CartridgeType::RomOnly.capabilities();
+    assert_eq!(caps.controller, Controller::None);
+    assert!(!caps.ram && !caps.battery && !caps.rtc);
+}
+
+#[test]
+fn test_rom_only_and_ram() {
+    let mut mbc = new_mbc(&load(build_rom(0x00, 0, 0)));
+    assert_eq!(mbc.read(0x4000), 1);
+    mbc.write(0x2000, 3);
+    assert_eq!(mbc.read(0x4000), 1, "ROM-only cartridge switched banks");
+    mbc.write(0xA000, 0x42);
+    assert_eq!(mbc.read(0xA000), 0xFF, "ROM-only cartridge has no RAM");
+    assert_eq!(mbc.save_data(), None);
+
+    let mut mbc = new_mbc(&load(build_rom(0x09, 0, 2)));
+    mbc.write(0xA000, 0x42);
+    assert_eq!(mbc.read(0xA000), 0x42);
+    let save = mbc.save_data().expect("Battery RAM not saved");
+    assert_eq!(save.len(), 8 * 1024);
+    assert_eq!(save[0], 0x42);
+}
+
+#[test]
+fn test_mbc1_rom_banking() {
+    // 2 MiB, 128 banks
+    let mut mbc = new_mbc(&load(build_rom(0x01, 6, 0)));
+    assert_eq!(mbc.read(0x4000), 1);
+
+    mbc.write(0x2000, 0x00);
+    assert_eq!(mbc.read(0x4000), 1, "Bank 0 not translated to 1");
+    mbc.write(0x2000, 0x1F);
+    assert_eq!(mbc.read(0x4000), 0x1F);
+    mbc.write(0x4000, 0x02);
+    assert_eq!(mbc.read(0x4000), 0x5F);
+    // BANK1 = 0 also becomes 1 with the upper bits set, so bank $40 is unreachable here
+    mbc.write(0x2000, 0x20);
+    assert_eq!(mbc.read(0x4000), 0x41);
+
+    // mode 0 keeps bank 0 at $0000, mode 1 applies BANK2 there
+    assert_eq!(mbc.read(0x0000), 0);
+    mbc.write(0x6000, 0x01);
+    assert_eq!(mbc.read(0x0000), 0x40);
+}
+
+#[test]
+fn test_mbc1_ram_banking() {
+    // 512 KiB ROM, 32 KiB battery RAM
+    let mut mbc = new_mbc(&load(build_rom(0x03, 4, 3)));
+
+    mbc.write(0xA000, 0x11);
+    assert_eq!(mbc.read(0xA000), 0xFF, "RAM accessible before enabling");
+
+    mbc.write(0x0000, 0x0A);
+    mbc.write(0xA000, 0x11);
+    mbc.write(0x6000, 0x01);
+    mbc.write(0x4000, 0x02);
+    mbc.write(0xA000, 0x22);
+    assert_eq!(mbc.read(0xA000), 0x22);
+    mbc.write(0x4000, 0x00);
+    assert_eq!(mbc.read(0xA000), 0x11);
+
+    // mode 0 always uses RAM bank 0
+    mbc.write(0x4000, 0x02);
+    mbc.write(0x6000, 0x00);
+    assert_eq!(mbc.read(0xA000), 0x11);
+
+    let save = mbc.save_data().unwrap();
+    assert_eq!(save.len(), 32 * 1024);
+    assert_eq!(save[2 * 0x2000], 0x22);
+}
+
+#[test]
+fn test_mbc1m_multicart() {
+    // 1 MiB with a second header (and logo) at the start of each 256 KiB game
+    let mut rom = build_rom(0x01, 5, 0);
+    assert!(!is_mbc1m(&rom));
+    for game in 1..4 {
+        write_header(&mut rom, game * 0x10, 0x01, 5, 0);
+    }
+    assert!(is_mbc1m(&rom), "Multicart not detected");
+
+    let mut mbc = new_mbc(&load(rom));
+    // BANK2 selects the game through ROM A18-A19, BANK1 bit 4 is ignored
+    mbc.write(0x4000, 0x02);
+    mbc.write(0x2000, 0x13);
+    assert_eq!(mbc.read(0x4000), 0x23);
+    mbc.write(0x2000, 0x10);
+    assert_eq!(mbc.read(0x4000), 0x20, "Zero check should see BANK1 bit 4");
+    mbc.write(0x6000, 0x01);
+    assert_eq!(mbc.read(0x0000), 0x20);
+    assert_eq!(&[mbc.read(0x0104), mbc.read(0x0105)], &LOGO[..2]);
+}
+
+#[test]
+fn test_mbc3_banking() {
+    let mut mbc = new_mbc(&load(build_rom(0x13, 6, 3)));
+    mbc.write(0x2000, 0x45);
+    assert_eq!(mbc.read(0x4000), 0x45);
+    mbc.write(0x2000, 0x00);
+    assert_eq!(mbc.read(0x4000), 1, "Bank 0 not translated to 1");
+
+    mbc.write(0x0000, 0x0A);
+    mbc.write(0x4000, 0x03);
+    mbc.write(0xA000, 0x33);
+    mbc.write(0x4000, 0x00);
+    assert_eq!(mbc.read(0xA000), 0x00);
+    mbc.write(0x4000, 0x03);
+    assert_eq!(mbc.read(0xA000), 0x33);
+    // no clock on this cartridge type
+    mbc.write(0x4000, 0x08);
+    assert_eq!(mbc.read(0xA000), 0xFF);
+}
+
+#[test]
+fn test_mbc3_rtc_latch_halt_and_carry() {
+    let cartridge = load(build_rom(0x10, 6, 3));
+    let now = Rc::new(Cell::new(1_000_000));
+    let mut mbc = mbc3_with_clock(&cartridge, &now);
+    mbc.write(0x0000, 0x0A);
+
+    now.set(now.get() + 2 * 86400 + 3661);
+    mbc.write(0x4000, 0x08);
+    assert_eq!(mbc.read(0xA000), 0, "Registers read before latching");
+    assert_eq!(read_rtc(&mut mbc), [1, 1, 1, 2, 0]);
+
+    // halted clocks keep their time
+    write_rtc(&mut mbc, [1, 1, 1, 2, 0x40]);
+    now.set(now.get() + 500);
+    assert_eq!(read_rtc(&mut mbc), [1, 1, 1, 2, 0x40]);
+
+    // day 511 23:59:59 rolls over into the carry flag
+    write_rtc(&mut mbc, [59, 59, 23, 0xFF, 0x01]);
+    now.set(now.get() + 1);
+    assert_eq!(read_rtc(&mut mbc), [0, 0, 0, 0, 0x80]);
+
+    // out-of-range seconds count up to 63 and wrap without carrying
+    write_rtc(&mut mbc, [62, 0, 0, 0, 0]);
+    now.set(now.get() + 3);
+    assert_eq!(read_rtc(&mut mbc), [1, 0, 0, 0, 0]);
+}
+
+#[test]
+fn test_mbc3_rtc_save_footer() {
+    let cartridge = load(build_rom(0x10, 6, 3));
+    let now = Rc::new(Cell::new(1_000_000));
+    let mut mbc = mbc3_with_clock(&cartridge, &now);
+    mbc.write(0x0000, 0x0A);
+    mbc.write(0x4000, 0x00);
+    mbc.write(0xA000, 0x5A);
+    now.set(now.get() + 125);
+
+    let save = mbc.save_data().expect("MBC3 battery RAM not saved");
+    assert_eq!(save.len(), 32 * 1024 + RTC_FOOTER_SIZE);
+    assert_eq!(save[0], 0x5A);
+    let footer = &save[32 * 1024..];
+    assert_eq!(&footer[0..8], &[5, 0, 0, 0, 2, 0, 0, 0]);
+    assert_eq!(u64::from_le_bytes(footer[40..48].try_into().unwrap()), 1_000_125);
+
+    // an hour passes while the game is switched off
+    now.set(now.get() + 3600);
+    let mut reloaded = mbc3_with_clock(&cartridge, &now);
+    reloaded.load_save_data(&save);
+    reloaded.write(0x0000, 0x0A);
+    assert_eq!(read_rtc(&mut reloaded), [5, 2, 1, 0, 0]);
+    reloaded.write(0x4000, 0x00);
+    assert_eq!(reloaded.read(0xA000), 0x5A);
+
+    // the 44-byte variant stores a 32-bit timestamp
+    let mut short = save[..32 * 1024 + 40].to_vec();
+    short.extend_from_slice(&1_000_125u32.to_le_bytes());
+    let mut reloaded = mbc3_with_clock(&cartridge, &now);
+    reloaded.load_save_data(&short);
+    reloaded.write(0x0000, 0x0A);
+    assert_eq!(read_rtc(&mut reloaded), [5, 2, 1, 0, 0]);
+}
+
+#[test]
+fn test_mbc5_banking_and_rumble() {
+    // 8 MiB, 512 banks
+    let mut mbc = new_mbc(&load(build_rom(0x1B, 8, 4)));
+    mbc.write(0x2000, 0x00);
+    assert_eq!(mbc.read(0x4000), 0, "MBC5 can map bank 0");
+    mbc.write(0x2000, 0x34);
+    mbc.write(0x3000, 0x01);
+    assert_eq!([mbc.read(0x4000), mbc.read(0x4001)], [0x34, 0x01]);
+    mbc.write(0x3000, 0x00);
+    assert_eq!([mbc.read(0x4000), mbc.read(0x4001)], [0x34, 0x00]);
+
+    mbc.write(0x0000, 0x1A);
+    mbc.write(0xA000, 0x11);
+    assert_eq!(mbc.read(0xA000), 0xFF, "Only $0A enables MBC5 RAM");
+    mbc.write(0x0000, 0x0A);
+    mbc.write(0x4000, 0x0F);
+    mbc.write(0xA000, 0x11);
+    assert_eq!(mbc.save_data().unwrap()[15 * 0x2000], 0x11);
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let mut mbc = new_mbc(&load(build_rom(0x1E, 4, 3)));
+    let sink = events.clone();
+    mbc.set_rumble_callback(Box::new(move |on| sink.borrow_mut().push(on)));
+    mbc.write(0x0000, 0x0A);
+    mbc.write(0x4000, 0x0B);
+    assert!(mbc.rumble());
+    mbc.write(0xA000, 0x22);
+    mbc.write(0x4000, 0x03);
+    assert!(!mbc.rumble());
+    assert_eq!(mbc.read(0xA000), 0x22, "Rumble bit changed the RAM bank");
+    mbc.write(0x4000, 0x03);
+    assert_eq!(*events.borrow(), vec![true, false]);
+}
+
+#[test]
+fn test_mbc2_ram() {
+    let cartridge = load(build_rom(0x06, 3, 0));
+    assert!(cartridge.gb_header.has_ram(), "MBC2 RAM not reported");
+    let mut mbc = new_mbc(&cartridge);
+
+    // A8 set: ROM bank register
+    mbc.write(0x2100, 0x05);
+    assert_eq!(mbc.read(0x4000), 5);
+    mbc.write(0x2100, 0x00);
+    assert_eq!(mbc.read(0x4000), 1);
+
+    // A8 clear: RAM enable
+    mbc.write(0x0100, 0x0A);
+    mbc.write(0xA000, 0x0A);
+    assert_eq!(mbc.read(0xA000), 0xFF, "RAM enabled through the ROM bank register");
+    mbc.write(0x0000, 0x0A);
+    mbc.write(0xA001, 0xAB);
+    assert_eq!(mbc.read(0xA001), 0xFB);
+    assert_eq!(mbc.read(0xA201), 0xFB, "RAM not mirrored every 512 bytes");
+
+    let save = mbc.save_data().unwrap();
+    assert_eq!(save.len(), 512);
+    assert_eq!(save[1], 0x0B);
+}
+
+#[test]
+fn test_mbc6_banks_and_flash() {
+    let mut mbc = new_mbc(&load(build_rom(0x20, 5, 0)));
+
+    // 8 KiB windows: 8 KiB bank 5 is the upper half of 16 KiB bank 2
+    mbc.write(0x2000, 5);
+    mbc.write(0x3000, 6);
+    assert_eq!(mbc.read(0x4000), 2);
+    assert_eq!(mbc.read(0x6000), 3);
+
+    mbc.write(0x0000, 0x0A);
+    mbc.write(0x0400, 3);
+    mbc.write(0x0800, 4);
+    mbc.write(0xA000, 0x33);
+    mbc.write(0xB000, 0x44);
+    mbc.write(0x0800, 3);
+    assert_eq!(mbc.read(0xB000), 0x33, "RAM windows do not share banks");
+
+    // map flash bank 2 at $4000 and bank 1 at $6000 so $5555 and $6AAA hit the command addresses
+    mbc.write(0x0C00, 1);
+    mbc.write(0x1000, 1);
+    mbc.write(0x2000, 2);
+    mbc.write(0x2800, 8);
+    mbc.write(0x3000, 1);
+    mbc.write(0x3800, 8);
+    assert_eq!(mbc.read(0x4010), 0xFF);
+    for (addr, value) in [(0x5555, 0xAA), (0x6AAA, 0x55), (0x5555, 0xA0), (0x4010, 0x3C)] {
+        mbc.write(addr, value);
+    }
+    assert_eq!(mbc.read(0x4010), 0x3C);
+
+    for (addr, value) in [(0x5555, 0xAA), (0x6AAA, 0x55), (0x5555, 0x90)] {
+        mbc.write(addr, value);
+    }
+    assert_eq!([mbc.read(0x4000), mbc.read(0x4001)], [0xC2, 0x81]);
+    mbc.write(0x4000, 0xF0);
+
+    let save = mbc.save_data().unwrap();
+    assert_eq!(save.len(), 32 * 1024 + 1024 * 1024);
+    assert_eq!(save[32 * 1024 + 0x4010], 0x3C);
+}
+
+#[test]
+fn test_mbc7_eeprom_and_accelerometer() {
+    let mut mbc = new_mbc(&load(build_rom(0x22, 4, 0)));
+    mbc.set_accelerometer(Box::new(FixedTilt(0.5, -1.0)));
+    assert_eq!(mbc.read(0xA080), 0xFF, "Registers visible before enabling");
+    mbc.write(0x0000, 0x0A);
+    mbc.write(0x4000, 0x40);
+
+    mbc.write(0xA000, 0x55);
+    mbc.write(0xA010, 0xAA);
+    let x = u16::from_le_bytes([mbc.read(0xA020), mbc.read(0xA030)]);
+    let y = u16::from_le_bytes([mbc.read(0xA040), mbc.read(0xA050)]);
+    assert_eq!((x, y), (0x81D0 - 0x38, 0x81D0 - 0x70));
+
+    // EWEN, WRITE word 5, then READ it back
+    mbc7_command(mbc.as_mut(), 0b00, 0xC0, None);
+    mbc7_command(mbc.as_mut(), 0b01, 5, Some(0xBEEF));
+    mbc7_command(mbc.as_mut(), 0b10, 5, None);
+    let word = (0..16).fold(0u16, |acc, _| (acc << 1) | mbc7_clock_bit(mbc.as_mut(), false) as u16);
+    assert_eq!(word, 0xBEEF);
+    mbc.write(0xA080, 0x00);
+
+    let save = mbc.save_data().unwrap();
+    assert_eq!(save.len(), 256);
+    assert_eq!(&save[10..12], &[0xEF, 0xBE]);
+}
+
+#[test]
+fn test_huc1_infrared_and_ram() {
+    let mut mbc = new_mbc(&load(build_rom(0xFF, 4, 3)));
+    mbc.write(0x0000, 0x00);
+    mbc.write(0x4000, 0x02);
+    mbc.write(0xA000, 0x42);
+    assert_eq!(mbc.read(0xA000), 0x42);
+
+    mbc.write(0x0000, 0x0E);
+    assert_eq!(mbc.read(0xA000), 0xC0);
+    mbc.set_ir_input(true);
+    assert_eq!(mbc.read(0xA000), 0xC1);
+    mbc.write(0xA000, 0x01);
+    assert!(mbc.ir_led());
+
+    mbc.write(0x0000, 0x00);
+    assert_eq!(mbc.read(0xA000), 0x42, "IR write reached RAM");
+    assert_eq!(mbc.save_data().unwrap()[2 * 0x2000], 0x42);
+}
+
+#[test]
+fn test_huc3_rtc() {
+    let cartridge = load(build_rom(0xFE, 4, 3));
+    let now = Rc::new(Cell::new(5_000));
+    let clock = now.clone();
+    let mut mbc = HuC3::with_clock(&cartridge, move || clock.get());
+
+    now.set(now.get() + 2 * 86400 + 90 * 60 + 59);
+    huc3_command(&mut mbc, 0x60);
+    huc3_command(&mut mbc, 0x40);
+    huc3_command(&mut mbc, 0x50);
+    let nibbles: Vec<u8> = (0..6).map(|_| huc3_command(&mut mbc, 0x10)).collect();
+    assert_eq!(nibbles, vec![0xA, 0x5, 0x0, 0x2, 0x0, 0x0]);
+
+    // set 23:59 on day $123, then let a minute pass
+    huc3_command(&mut mbc, 0x40);
+    for nibble in [0xF, 0x9, 0x5, 0x3, 0x2, 0x1] {
+        huc3_command(&mut mbc, 0x30 | nibble);
+    }
+    huc3_command(&mut mbc, 0x61);
+    now.set(now.get() + 60);
+    assert_eq!(mbc.rtc_time(), (0, 0x124));
+
+    mbc.write(0x0000, 0x0D);
+    assert_eq!(mbc.read(0xA000) & 1, 1, "RTC not ready");
+
+    // the clock and scratch memory survive a save, and catch up with the time switched off
+    huc3_command(&mut mbc, 0x4F);
+    huc3_command(&mut mbc, 0x5F);
+    huc3_command(&mut mbc, 0x37);
+    let save = mbc.save_data().unwrap();
+    assert_eq!(save.len(), 32 * 1024 + HUC3_FOOTER_SIZE);
+    now.set(now.get() + 86400);
+    let clock = now.clone();
+    let mut reloaded = HuC3::with_clock(&cartridge, move || clock.get());
+    reloaded.load_save_data(&save);
+    assert_eq!(reloaded.rtc_time(), (0, 0x125));
+    huc3_command(&mut reloaded, 0x4F);
+    huc3_command(&mut reloaded, 0x5F);
+    assert_eq!(huc3_command(&mut reloaded, 0x10), 0x7);
+}
+
+#[test]
+fn test_pocket_camera_capture() {
+    let mut mbc = new_mbc(&load(build_rom(0xFC, 5, 4)));
+    mbc.set_image_sensor(Box::new(Gradient));
+
+    mbc.write(0x2000, 0x00);
+    assert_eq!(mbc.read(0x4000), 0, "Camera can map bank 0");
+
+    // nominal exposure, and the same thresholds for every matrix cell
+    mbc.write(0x4000, 0x10);
+    mbc.write(0xA002, 0x03);
+    mbc.write(0xA003, 0x00);
+    for cell in 0..16 {
+        for (index, threshold) in [0x40, 0x80, 0xC0].into_iter().enumerate() {
+            mbc.write(0xA006 + cell * 3 + index as u16, threshold);
+        }
+    }
+    mbc.write(0xA000, 0x01);
+    assert_eq!(mbc.read(0xA000) & 1, 0, "Capture still busy");
+
+    // RAM bank 0 holds the picture from $A100, one 2bpp tile row per two bytes
+    mbc.write(0x4000, 0x00);
+    assert_eq!([mbc.read(0xA100), mbc.read(0xA101)], [0xFF, 0xFF]);
+    // tile 5 covers x = 40-47 (values 80-94): colour 2
+    assert_eq!([mbc.read(0xA150), mbc.read(0xA151)], [0x00, 0xFF]);
+    // tile 15 covers x = 120-127 (values 240-254): colour 0
+    assert_eq!([mbc.read(0xA1F0), mbc.read(0xA1F1)], [0x00, 0x00]);
+
+    mbc.write(0xA100, 0x12);
+    assert_eq!(mbc.read(0xA100), 0xFF, "RAM written while write-protected");
+    mbc.write(0x0000, 0x0A);
+    mbc.write(0xA100, 0x12);
+    assert_eq!(mbc.read(0xA100), 0x12);
+    assert_eq!(mbc.save_data().unwrap().len(), 128 * 1024);
+}
+
+#[test]
+fn test_tama5_banking_ram_and_rtc() {
+    let cartridge = load(build_rom(0xFD, 4, 0));
+    let now = Rc::new(Cell::new(1_000));
+    let clock = now.clone();
+    let mut mbc = Tama5::with_clock(&cartridge, move || clock.get());
+
+    mbc.write(0xA001, 0xA);
+    assert_eq!(mbc.read(0xA000), 0xF1, "TAMA5 not ready");
+
+    tama5_register(&mut mbc, 0x0, 0x5);
+    tama5_register(&mut mbc, 0x1, 0x1);
+    assert_eq!(mbc.read(0x4000), 0x15);
+
+    tama5_command(&mut mbc, 0x0, 0x1F, 0xAB);
+    assert_eq!(tama5_command(&mut mbc, 0x1, 0x1F, 0), 0xAB);
+    let save = mbc.save_data().unwrap();
+    assert_eq!(save.len(), TAMA5_RAM_SIZE + TAMA5_FOOTER_SIZE);
+    assert_eq!(save[0x1F], 0xAB);
+
+    // 23:59:50, then an alarm at 00:01
+    for (address, nibble) in [(0x5, 2), (0x4, 3), (0x3, 5), (0x2, 9), (0x1, 5), (0x0, 0)] {
+        tama5_command(&mut mbc, 0x2, address, nibble);
+    }
+    for (address, nibble) in [(0x15, 0), (0x14, 0), (0x13, 0), (0x12, 1), (0x16, 1)] {
+        tama5_command(&mut mbc, 0x2, address, nibble);
+    }
+
+    now.set(now.get() + 15);
+    let time = mbc.time();
+    assert_eq!(time, Tama5Time { day: 2, weekday: 1, second: 5, ..Tama5Time::default() });
+    assert_eq!(tama5_command(&mut mbc, 0x3, 0x7, 0), 2, "RTC day nibble");
+    assert!(!mbc.alarm());
+
+    now.set(now.get() + 60);
+    assert!(mbc.alarm(), "alarm did not go off");
+    assert_eq!(tama5_command(&mut mbc, 0x3, 0x16, 0), 0x3);
+    tama5_command(&mut mbc, 0x2, 0x16, 1);
+    assert!(!mbc.alarm(), "alarm not cleared");
+
+    // the calendar and alarm survive a save, and a day switched off passes the alarm time
+    let save = mbc.save_data().unwrap();
+    now.set(now.get() + 86400);
+    let clock = now.clone();
+    let mut reloaded = Tama5::with_clock(&cartridge, move || clock.get());
+    reloaded.load_save_data(&save);
+    let time = reloaded.time();
+    assert_eq!(time, Tama5Time { day: 3, weekday: 2, minute: 1, second: 5, ..Tama5Time::default() });
+    assert!(reloaded.alarm(), "alarm did not go off while switched off");
+    assert_eq!(tama5_command(&mut reloaded, 0x1, 0x1F, 0), 0xAB);
+}
+
+#[test]
+fn test_mmm01_menu_and_lock() {
+    // 128 KiB with the first game's MBC1 header at $100 and the menu's header in the last 32 KiB
+    let mut rom = build_rom(0x01, 2, 0);
+    write_header(&mut rom, 6, 0x0D, 2, 4);
+    let cartridge = load(rom);
+    assert_eq!(cartridge.gb_header.cartridge_type, CartridgeType::MMM01RamBattery);
+    assert_eq!(cartridge.gb_header.ram_size, 128 * 1024);
+
+    let mut mbc = new_mbc(&cartridge);
+    assert_eq!((mbc.read(0x0000), mbc.read(0x4000)), (6, 7), "menu not mapped at boot");
+
+    // the menu starts the game in banks 4-7 with RAM banks 4-7, keeping bank bits 2-4
+    mbc.write(0x2000, 0x04);
+    mbc.write(0x4000, 0x04);
+    mbc.write(0x6000, 0x0E << 2);
+    mbc.write(0x0000, 0x4A);
+    assert_eq!((mbc.read(0x0000), mbc.read(0x4000)), (4, 5));
+
+    mbc.write(0x2000, 0x03);
+    assert_eq!(mbc.read(0x4000), 7);
+    mbc.write(0x2000, 0x1E);
+    assert_eq!(mbc.read(0x4000), 6, "game changed a locked bank bit");
+
+    // the configuration stays frozen
+    mbc.write(0x6000, 0);
+    mbc.write(0x0000, 0x0A);
+    mbc.write(0x2000, 0x01);
+    assert_eq!((mbc.read(0x0000), mbc.read(0x4000)), (4, 5));
+
+    mbc.write(0xA000, 0x42);
+    assert_eq!(mbc.read(0xA000), 0x42);
+    assert_eq!(mbc.save_data().unwrap()[4 * 0x2000], 0x42);
+}
@@ -0,0 +1,79 @@
+use emurom::gb::error::PhotoError;
+use emurom::gb::photo::{extract_photos, extract_thumbnail, PHOTO_HEIGHT, PHOTO_SLOTS, PHOTO_WIDTH};
+
+
+/// Encode one colour per pixel as 2bpp tiles laid out row by row
+fn encode_tiles(pixels: &[u8], width: usize) -> Vec<u8> {
+    let mut tiles = vec![0; pixels.len() / 4];
+    for (index, &color) in pixels.iter().enumerate() {
+        let (x, y) = (index % width, index / width);
+        let offset = ((y / 8) * (width / 8) + x / 8) * 16 + (y % 8) * 2;
+        let bit = 0x80 >> (x % 8);
+        if color & 1 != 0 {
+            tiles[offset] |= bit;
+        }
+        if color & 2 != 0 {
+            tiles[offset + 1] |= bit;
+        }
+    }
+    tiles
+}
+
+fn build_save() -> (Vec<u8>, Vec<u8>) {
+    let mut save = vec![0; 128 * 1024];
+    save[0x11B2..0x11B2 + PHOTO_SLOTS].fill(0xFF);
+
+    let pixels: Vec<u8> = (0..PHOTO_WIDTH * PHOTO_HEIGHT)
+        .map(|index| ((index / PHOTO_WIDTH + index) % 4) as u8)
+        .collect();
+    save[0x3000..0x3E00].copy_from_slice(&encode_tiles(&pixels, PHOTO_WIDTH));
+    save[0x11B3] = 0;
+    save[0x3F00..0x3F04].copy_from_slice(b"META");
+    // thumbnail of slot 1: all black
+    save[0x3E00..0x3F00].fill(0xFF);
+    (save, pixels)
+}
+
+#[test]
+fn test_extract_photos() {
+    let (save, pixels) = build_save();
+    let photos = extract_photos(&save).expect("Failed to decode save");
+    assert_eq!(photos.len(), PHOTO_SLOTS);
+
+    assert!(photos[0].is_deleted());
+    let photo = &photos[1];
+    assert_eq!(photo.slot, 1);
+    assert_eq!(photo.album_index, Some(0));
+    assert_eq!(photo.pixels, pixels);
+    assert_eq!(&photo.metadata[..4], b"META");
+    assert_eq!(photo.metadata.len(), 0x100);
+
+    let thumbnail = extract_thumbnail(&save, 1).unwrap();
+    assert_eq!(thumbnail, vec![3; 32 * 32]);
+    assert_eq!(extract_thumbnail(&save, PHOTO_SLOTS), None);
+
+    assert!(
+        matches!(extract_photos(&save[..0x8000]), Err(PhotoError::SaveTooSmall { found: 0x8000, .. })),
+        "Truncated save accepted"
+    );
+}
+
+#[test]
+fn test_photo_image_formats() {
+    let (save, _) = build_save();
+    let photo = &extract_photos(&save).unwrap()[1];
+
+    let pgm = photo.to_pgm();
+    let header = b"P5\n128 112\n255\n";
+    assert_eq!(&pgm[..header.len()], header);
+    assert_eq!(pgm.len(), header.len() + PHOTO_WIDTH * PHOTO_HEIGHT);
+    assert_eq!(&pgm[header.len()..header.len() + 4], &[0xFF, 0xAA, 0x55, 0x00]);
+
+    let png = photo.to_png();
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1A\n");
+    assert_eq!(&png[12..16], b"IHDR");
+    assert_eq!(&png[16..24], &[0, 0, 0, 128, 0, 0, 0, 112]);
+    // IHDR CRC for an 8-bit grayscale 128x112 image
+    assert_eq!(&png[29..33], &[0xF3, 0xC7, 0x27, 0x0E]);
+    assert_eq!(&png[png.len() - 12..], &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]);
+}
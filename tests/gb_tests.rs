@@ -18,5 +18,301 @@ fn test_gb_rom() {
     let cartridge = &cartridge.unwrap();
 
     assert_eq!(cartridge.gb_header.cartridge_type, emurom::gb::header::CartridgeType::MBC1, "Cartridge type mismatch");
-    assert_eq!(cartridge.gb_header.has_ram(), false, "ROM size mismatch"); // No RAM
-}
\ No newline at end of file
+    assert!(!cartridge.gb_header.has_ram(), "ROM size mismatch"); // No RAM
+}
+
+fn build_header(cartridge_type: u8, rom_code: u8, ram_code: u8) -> Vec<u8> {
+    const LOGO: [u8; 48] = [
+        0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+        0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+        0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
+    ];
+    let mut rom = vec![0; 0x8000];
+    rom[0x104..0x134].copy_from_slice(&LOGO);
+    rom[0x147] = cartridge_type;
+    rom[0x148] = rom_code;
+    rom[0x149] = ram_code;
+    rom[0x14D] = rom[0x134..0x14D].iter().fold(0u8, |acc, &byte| acc.wrapping_sub(byte).wrapping_sub(1));
+    rom
+}
+
+#[test]
+fn test_size_codes() {
+    use emurom::gb::error::RomParseError;
+    use emurom::gb::header::GbHeader;
+
+    let parse = |cartridge_type, rom_code, ram_code| {
+        GbHeader::from_bytes(&build_header(cartridge_type, rom_code, ram_code))
+    };
+
+    let header = parse(0x1B, 0x08, 0x04).unwrap();
+    assert_eq!(header.rom_size, 8 * 1024 * 1024);
+    assert_eq!(header.ram_size, 128 * 1024);
+
+    assert_eq!(parse(0x01, 0x52, 0x00).unwrap().rom_size, 1152 * 1024);
+    assert_eq!(parse(0x01, 0x53, 0x00).unwrap().rom_size, 1280 * 1024);
+    assert_eq!(parse(0x01, 0x54, 0x00).unwrap().rom_size, 1536 * 1024);
+    assert_eq!(parse(0x13, 0x00, 0x05).unwrap().ram_size, 64 * 1024);
+
+    assert!(matches!(parse(0x01, 0x09, 0x00), Err(RomParseError::UnknownRomSizeCode(0x09))));
+    assert!(matches!(parse(0x01, 0xFF, 0x00), Err(RomParseError::UnknownRomSizeCode(0xFF))));
+
+    assert!(matches!(parse(0x03, 0x00, 0x07), Err(RomParseError::UnknownRamSizeCode(0x07))));
+
+    // MBC1 stops at 2 MiB and 32 KiB; MBC2 RAM is internal; plain MBC1 has no RAM
+    assert!(matches!(
+        parse(0x03, 0x07, 0x03),
+        Err(RomParseError::RomSizeExceedsController { size: 0x400000, limit: 0x200000 })
+    ));
+    assert!(matches!(
+        parse(0x03, 0x06, 0x04),
+        Err(RomParseError::RamSizeExceedsController { size: 0x20000, limit: 0x8000 })
+    ));
+    assert!(matches!(
+        parse(0x06, 0x03, 0x02),
+        Err(RomParseError::RamSizeExceedsController { size: 0x2000, limit: 0 })
+    ));
+    assert!(matches!(
+        parse(0x01, 0x00, 0x02),
+        Err(RomParseError::RamSizeExceedsController { size: 0x2000, limit: 0 })
+    ));
+}
+
+#[test]
+fn test_title_layouts() {
+    use emurom::gb::header::{CgbMode, GbHeader, HeaderFormat};
+
+    let parse = |title: &[u8], old_licensee: u8| {
+        let mut rom = build_header(0x00, 0x00, 0x00);
+        rom[0x134..0x134 + title.len()].copy_from_slice(title);
+        rom[0x14B] = old_licensee;
+        rom[0x14D] = rom[0x134..0x14D].iter().fold(0u8, |acc, &byte| acc.wrapping_sub(byte).wrapping_sub(1));
+        GbHeader::from_bytes(&rom).unwrap()
+    };
+
+    let header = parse(b"SUPER MARIOLAND2", 0x01);
+    assert_eq!(header.format, HeaderFormat::Dmg);
+    assert_eq!(header.title, "SUPER MARIOLAND2");
+    assert_eq!(header.manufacturer_code, None);
+    assert_eq!(header.cgb_mode(), CgbMode::Dmg);
+
+    let header = parse(b"POKEMON_SLVAAXJ\x80", 0x33);
+    assert_eq!(header.format, HeaderFormat::CgbManufacturer);
+    assert_eq!(header.title, "POKEMON_SLV");
+    assert_eq!(header.manufacturer_code.as_deref(), Some("AAXJ"));
+    assert_eq!(header.cgb_mode(), CgbMode::Compatible);
+
+    let header = parse(b"SHORT\0\0\0\0\0\0BXTE\xC0", 0x33);
+    assert_eq!(header.title, "SHORT");
+    assert_eq!(header.manufacturer_code.as_deref(), Some("BXTE"));
+    assert_eq!(header.cgb_mode(), CgbMode::Only);
+
+    // the end of a 15-character title is not a product code
+    let header = parse(b"ZELDA DXPINBALL\x80", 0x33);
+    assert_eq!(header.format, HeaderFormat::Cgb);
+    assert_eq!(header.title, "ZELDA DXPINBALL");
+    assert_eq!(header.manufacturer_code, None);
+
+    // nor is anything in a cartridge with an old licensee code
+    let header = parse(b"POKEMON_SLVAAXJ\x80", 0x01);
+    assert_eq!(header.format, HeaderFormat::Cgb);
+    assert_eq!(header.title, "POKEMON_SLVAAXJ");
+
+    assert_eq!(parse(b"PGB TEST\0\0\0\0\0\0\0\x84", 0x33).cgb_mode(), CgbMode::Pgb);
+}
+
+#[test]
+fn test_publisher() {
+    use emurom::gb::header::GbHeader;
+
+    let parse = |old_licensee: u8, new_licensee: &[u8; 2], sgb_flag: u8| {
+        let mut rom = build_header(0x00, 0x00, 0x00);
+        rom[0x144..0x146].copy_from_slice(new_licensee);
+        rom[0x146] = sgb_flag;
+        rom[0x14B] = old_licensee;
+        rom[0x14D] = rom[0x134..0x14D].iter().fold(0u8, |acc, &byte| acc.wrapping_sub(byte).wrapping_sub(1));
+        GbHeader::from_bytes(&rom).unwrap()
+    };
+
+    assert_eq!(parse(0x01, b"00", 0x00).publisher(), Some("Nintendo"));
+    assert_eq!(parse(0xA4, b"00", 0x00).publisher(), Some("Konami"));
+    assert_eq!(parse(0x33, b"01", 0x00).publisher(), Some("Nintendo"));
+    assert_eq!(parse(0x33, b"78", 0x00).publisher(), Some("THQ"));
+    assert_eq!(parse(0x01, b"78", 0x00).publisher(), Some("Nintendo"), "new code used without 0x33");
+    assert_eq!(parse(0x00, b"00", 0x00).publisher(), None);
+    assert_eq!(parse(0x33, b"ZZ", 0x00).publisher(), None);
+
+    assert!(parse(0x33, b"01", 0x03).is_sgb());
+    assert!(!parse(0x01, b"01", 0x03).is_sgb(), "SGB flag needs old licensee 0x33");
+    assert!(!parse(0x33, b"01", 0x00).is_sgb());
+}
+
+#[test]
+fn test_product_code_and_region() {
+    use emurom::gb::header::{GbHeader, ProductCode, ProductHardware, Region};
+
+    let parse = |title: &[u8], destination: u8| {
+        let mut rom = build_header(0x00, 0x00, 0x00);
+        rom[0x134..0x134 + title.len()].copy_from_slice(title);
+        rom[0x14A] = destination;
+        rom[0x14B] = 0x33;
+        rom[0x14D] = rom[0x134..0x14D].iter().fold(0u8, |acc, &byte| acc.wrapping_sub(byte).wrapping_sub(1));
+        GbHeader::from_bytes(&rom).unwrap()
+    };
+
+    let header = parse(b"POKEMON_SLVAAXE\x80", 0x01);
+    let code = header.product_code().unwrap();
+    assert_eq!(code, ProductCode { hardware: ProductHardware::Standard, game: "AX".into(), region: Region::Usa });
+    assert_eq!(header.region(), Region::Usa);
+
+    assert_eq!(parse(b"POKEMON_SLVAAXJ\x80", 0x00).region(), Region::Japan);
+    assert_eq!(parse(b"POKEMON_SLVAAXD\x80", 0x01).region(), Region::Germany);
+    assert_eq!(parse(b"PINBALL\0\0\0\0VPHP\x80", 0x01).product_code().unwrap().hardware, ProductHardware::Rumble);
+
+    // without a product code only the destination is known
+    assert_eq!(parse(b"TETRIS", 0x00).region(), Region::Japan);
+    assert_eq!(parse(b"TETRIS", 0x01).region(), Region::Overseas);
+    assert_eq!(parse(b"TETRIS", 0x01).product_code(), None);
+
+    assert_eq!(ProductCode::from_bytes(b"AAX"), None);
+    assert_eq!(ProductCode::from_bytes(b"QAXE"), None);
+    assert_eq!(ProductCode::from_bytes(b"AAXQ"), None);
+}
+
+#[test]
+fn test_validation_report() {
+    use emurom::gb::cartridge::Cartridge;
+    use emurom::gb::error::RomParseError;
+    use emurom::validation::{LoadPolicy, Problem};
+
+    let fix_global_checksum = |rom: &mut Vec<u8>| {
+        let sum = rom
+            .iter()
+            .enumerate()
+            .filter(|&(offset, _)| offset != 0x14E && offset != 0x14F)
+            .fold(0u16, |sum, (_, &byte)| sum.wrapping_add(byte as u16));
+        rom[0x14E..0x150].copy_from_slice(&sum.to_be_bytes());
+    };
+
+    let mut rom = build_header(0x01, 0x00, 0x00);
+    fix_global_checksum(&mut rom);
+    assert!(Cartridge::load_rom_data(&mut rom.as_slice()).unwrap().report.is_clean());
+
+    // the global checksum is not checked by the hardware
+    rom[0x4000] = 0x42;
+    let cartridge = Cartridge::load_rom_data(&mut rom.as_slice()).unwrap();
+    let warning = cartridge.report.warnings().next().unwrap();
+    assert_eq!(warning.offset, 0x14E);
+    assert!(matches!(warning.problem, Problem::ChecksumMismatch { .. }));
+
+    // a bad header checksum only gets through a lenient load
+    let mut rom = build_header(0x01, 0x00, 0x00);
+    rom[0x14D] ^= 0xFF;
+    fix_global_checksum(&mut rom);
+    assert!(matches!(Cartridge::load_rom_data(&mut rom.as_slice()), Err(RomParseError::InvalidHeaderChecksum)));
+    let cartridge = Cartridge::load_rom_data_with_policy(&mut rom.as_slice(), LoadPolicy::Lenient).unwrap();
+    let error = cartridge.report.errors().next().unwrap();
+    assert_eq!(error.offset, 0x14D);
+    assert_eq!(error.problem, Problem::ChecksumMismatch { stored: rom[0x14D] as u16, computed: (rom[0x14D] ^ 0xFF) as u16 });
+
+    // plain MBC1 has no RAM to go with the RAM size code
+    let mut rom = build_header(0x01, 0x00, 0x02);
+    fix_global_checksum(&mut rom);
+    assert!(matches!(
+        Cartridge::load_rom_data(&mut rom.as_slice()),
+        Err(RomParseError::RamSizeExceedsController { size: 0x2000, limit: 0 })
+    ));
+    let cartridge = Cartridge::load_rom_data_with_policy(&mut rom.as_slice(), LoadPolicy::Lenient).unwrap();
+    let error = cartridge.report.errors().next().unwrap();
+    assert_eq!(error.offset, 0x149);
+    assert_eq!(error.problem, Problem::SizeExceedsHardware { size: 0x2000, limit: 0 });
+
+    // unknown ROM size code and a partial bank
+    let mut rom = build_header(0x01, 0x33, 0x00);
+    rom.truncate(0x5000);
+    rom[0x14D] = rom[0x134..0x14D].iter().fold(0u8, |acc, &byte| acc.wrapping_sub(byte).wrapping_sub(1));
+    fix_global_checksum(&mut rom);
+    assert!(matches!(Cartridge::load_rom_data(&mut rom.as_slice()), Err(RomParseError::UnknownRomSizeCode(0x33))));
+    let cartridge = Cartridge::load_rom_data_with_policy(&mut rom.as_slice(), LoadPolicy::Lenient).unwrap();
+    assert_eq!(cartridge.rom_data.len(), 0x8000);
+    assert_eq!(
+        cartridge.report.errors().map(|error| (error.offset, &error.problem)).collect::<Vec<_>>(),
+        vec![
+            (0x148, &Problem::UnknownCode(0x33)),
+            (0x5000, &Problem::PartialBank { size: 0x5000, bank_size: 0x4000 }),
+        ]
+    );
+}
+
+#[test]
+fn test_logo_schemes() {
+    use emurom::gb::cartridge::Cartridge;
+    use emurom::gb::error::RomParseError;
+    use emurom::gb::header::{GbHeader, LogoCheck, LogoScheme};
+    use emurom::validation::{LoadPolicy, Problem};
+
+    let nintendo = build_header(0x00, 0x00, 0x00);
+    let logo = nintendo[0x104..0x134].to_vec();
+    let parse = |rom: &[u8], check| GbHeader::from_bytes_with_logo_check(rom, LoadPolicy::Strict, check);
+
+    let (header, report) = parse(&nintendo, LogoCheck::Dmg).unwrap();
+    assert_eq!(header.logo_scheme, LogoScheme::Nintendo);
+    assert!(report.is_clean());
+
+    // the CGB boot ROM only compares the top half
+    let mut half = nintendo.clone();
+    half[0x130] ^= 0xFF;
+    assert!(matches!(parse(&half, LogoCheck::Dmg), Err(RomParseError::InvalidLogo)));
+    let (header, report) = parse(&half, LogoCheck::Cgb).unwrap();
+    assert_eq!(header.logo_scheme, LogoScheme::TopHalf);
+    assert_eq!(report.warnings().next().unwrap().problem, Problem::LogoMismatch);
+
+    // Sachen: scrambled logo at 0x184-0x1B3, custom logo at 0x104
+    let mut sachen = nintendo.clone();
+    sachen[0x104..0x134].fill(0x55);
+    for (index, &byte) in logo.iter().enumerate() {
+        let addr = (0x104 + index) | 0x80;
+        let bit = |n: usize| (addr >> n) & 1;
+        let scrambled = (addr & !0x53) | bit(0) << 6 | bit(6) | bit(1) << 4 | bit(4) << 1;
+        sachen[scrambled] = byte;
+    }
+    assert!(matches!(parse(&sachen, LogoCheck::Cgb), Err(RomParseError::InvalidLogo)));
+    assert_eq!(parse(&sachen, LogoCheck::Unlicensed).unwrap().0.logo_scheme, LogoScheme::Sachen);
+
+    // Nintendo's logo switched in from elsewhere in bank 0
+    let mut switched = nintendo.clone();
+    switched[0x104..0x134].fill(0xAA);
+    switched[0x2000..0x2030].copy_from_slice(&logo);
+    let cartridge =
+        Cartridge::load_rom_data_with_logo_check(&mut switched.as_slice(), LoadPolicy::Strict, LogoCheck::Unlicensed)
+            .unwrap();
+    assert_eq!(cartridge.gb_header.logo_scheme, LogoScheme::SwitchedLogo { offset: 0x2000 });
+
+    // an unknown logo fails unless the load is lenient
+    let mut unknown = nintendo.clone();
+    unknown[0x104..0x134].fill(0xAA);
+    assert!(matches!(parse(&unknown, LogoCheck::Unlicensed), Err(RomParseError::InvalidLogo)));
+    let (header, report) = GbHeader::from_bytes_with_policy(&unknown, LoadPolicy::Lenient).unwrap();
+    assert_eq!(header.logo_scheme, LogoScheme::Unknown);
+    let error = report.errors().next().unwrap();
+    assert_eq!((error.offset, &error.problem), (0x104, &Problem::LogoMismatch));
+}
+
+#[test]
+fn test_timing_parameters() {
+    use emurom::gb::timing::TimingParameters;
+
+    let dmg = TimingParameters::DMG;
+    assert_eq!(dmg.cpu_cycles_per_frame(), 70_224.0);
+    assert!((dmg.frame_rate() - 59.7275).abs() < 1e-4);
+    assert_eq!(dmg.div_clock(), 16_384.0);
+    assert_eq!(dmg.vblank_cpu_cycles(), 4560.0);
+
+    let double = TimingParameters::CGB_DOUBLE_SPEED;
+    assert_eq!(double.cpu_cycles_per_frame(), 140_448.0);
+    assert_eq!(double.frame_rate(), dmg.frame_rate());
+    assert_eq!(double.machine_clock(), 2_097_152.0);
+
+    assert!((TimingParameters::SGB.frame_rate() - 61.17).abs() < 0.01);
+    assert_eq!(TimingParameters::SGB2, dmg);
+}
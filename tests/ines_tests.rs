@@ -19,9 +19,10 @@ fn test_ines_rom_header() {
     assert_eq!(header.format, emurom::nes::header::HeaderFormat::INes, "Header format mismatch");
     assert_eq!(header.prg_rom_size, 16*1024, "PRG ROM size mismatch"); // 16KB PRG ROM
     assert_eq!(header.chr_rom_size, 8*1024, "CHR ROM size mismatch");  // 8KB CHR ROM
-    assert_eq!(header.flags_6.nametable(), false, "Nametable mirroring mismatch"); // H mirroring
-    assert_eq!(header.flags_6.battery_backed(), false, "Battery backed mismatch");
-    assert_eq!(header.flags_6.trainer(), false, "Trainer mismatch");
+    assert!(!header.flags_6.nametable(), "Nametable mirroring mismatch"); // H mirroring
+    assert_eq!(header.mirroring(), emurom::nes::header::Mirroring::Horizontal, "Mirroring mismatch");
+    assert!(!header.flags_6.battery_backed(), "Battery backed mismatch");
+    assert!(!header.flags_6.trainer(), "Trainer mismatch");
     assert_eq!(header.mapper, 0, "Mapper mismatch"); // No mapper
     assert_eq!(header.submapper, 0, "Submapper mismatch"); // No submapper
 }
@@ -39,9 +40,10 @@ fn test_nes2_rom_header() {
     assert_eq!(header.prg_rom_size, 64*1024, "PRG ROM size mismatch"); // 32KB PRG ROM
     assert_eq!(header.chr_rom_size, 0, "CHR ROM size mismatch");  // 0KB CHR ROM
     assert_eq!(header.chr_ram_size, emurom::nes::header::RamSize::Nes2{ram: 32*1024, nvram: 0}, "CHR RAM size mismatch");  // 32KB CHR RAM
-    assert_eq!(header.flags_6.nametable(), false, "Nametable mirroring mismatch"); // H mirroring
-    assert_eq!(header.flags_6.battery_backed(), false, "Battery backed mismatch");
-    assert_eq!(header.flags_6.trainer(), false, "Trainer mismatch");
+    assert!(!header.flags_6.nametable(), "Nametable mirroring mismatch"); // H mirroring
+    assert_eq!(header.mirroring(), emurom::nes::header::Mirroring::MapperControlled, "Mirroring mismatch"); // MMC3
+    assert!(!header.flags_6.battery_backed(), "Battery backed mismatch");
+    assert!(!header.flags_6.trainer(), "Trainer mismatch");
     assert_eq!(header.mapper, 4, "Mapper mismatch"); // No mapper
     assert_eq!(header.submapper, 0, "Submapper mismatch"); // No submapper
 
@@ -57,9 +59,9 @@ fn test_nes2_rom_header() {
     assert_eq!(header.chr_rom_size, 0, "CHR ROM size mismatch");  // 0KB CHR ROM
     assert_eq!(header.prg_ram_size, emurom::nes::header::RamSize::Nes2{ram: 8*1024, nvram: 0}, "PRG RAM size mismatch");  // 8KB PRG/Work RAM
     assert_eq!(header.chr_ram_size, emurom::nes::header::RamSize::Nes2{ram: 8*1024, nvram: 0}, "CHR RAM size mismatch");  // 8KB CHR RAM
-    assert_eq!(header.flags_6.nametable(), false, "Nametable mirroring mismatch"); // H mirroring
-    assert_eq!(header.flags_6.battery_backed(), false, "Battery backed mismatch");
-    assert_eq!(header.flags_6.trainer(), false, "Trainer mismatch");
+    assert!(!header.flags_6.nametable(), "Nametable mirroring mismatch"); // H mirroring
+    assert!(!header.flags_6.battery_backed(), "Battery backed mismatch");
+    assert!(!header.flags_6.trainer(), "Trainer mismatch");
     assert_eq!(header.mapper, 34, "Mapper mismatch"); // No mapper
     assert_eq!(header.submapper, 2, "Submapper mismatch"); // No submapper
 }
@@ -75,4 +77,310 @@ pub fn test_cartridge_load_data() {
 
     assert_eq!(cartridge.prg_rom.len(), cartridge.ines_header.prg_rom_size as usize, "PRG ROM size mismatch");
     assert_eq!(cartridge.chr_rom.len(), cartridge.ines_header.chr_rom_size as usize, "CHR ROM size mismatch");
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_mirroring() {
+    use emurom::nes::header::{InesHeader, Mirroring};
+
+    let mirroring = |mapper: u16, flags_6: u8| {
+        let header = [
+            b'N', b'E', b'S', 0x1A, 1, 1,
+            flags_6 | ((mapper & 0x0F) << 4) as u8, (mapper & 0xF0) as u8 | 0x08,
+            0, 0, 0, 0, 0, 0, 0, 0,
+        ];
+        InesHeader::from_bytes(&header).unwrap().mirroring()
+    };
+
+    assert_eq!(mirroring(0, 0x00), Mirroring::Horizontal);
+    assert_eq!(mirroring(0, 0x01), Mirroring::Vertical);
+    assert_eq!(mirroring(4, 0x01), Mirroring::MapperControlled);
+    assert_eq!(mirroring(4, 0x08), Mirroring::FourScreen);
+    assert_eq!(mirroring(206, 0x09), Mirroring::FourScreen);
+    assert_eq!(mirroring(30, 0x08), Mirroring::MapperControlled);
+    assert_eq!(mirroring(30, 0x09), Mirroring::FourScreen);
+    assert_eq!(mirroring(218, 0x08), Mirroring::SingleScreenA);
+    assert_eq!(mirroring(218, 0x09), Mirroring::SingleScreenB);
+    assert_eq!(mirroring(218, 0x01), Mirroring::Vertical);
+
+    assert_eq!(Mirroring::Horizontal.nametable_pages(), Some([0, 0, 1, 1]));
+    assert_eq!(Mirroring::SingleScreenB.nametable_pages(), Some([1, 1, 1, 1]));
+    assert_eq!(Mirroring::FourScreen.nametable_pages(), None);
+}
+
+#[test]
+fn test_validation_report() {
+    use emurom::nes::cartridge::Cartridge;
+    use emurom::nes::error::RomParseError;
+    use emurom::validation::{LoadPolicy, Problem, Severity};
+
+    let mut rom = vec![0; 16 + 0x4000 + 0x2000];
+    rom[..8].copy_from_slice(&[b'N', b'E', b'S', 0x1A, 1, 1, 0x10, 0x00]);
+
+    let cartridge = Cartridge::load_rom_data(&mut rom.as_slice()).unwrap();
+    assert!(cartridge.report.is_clean());
+
+    // "DiskDude!" in bytes 7-15 is worked around with a warning
+    let mut garbage = rom.clone();
+    garbage[7..16].copy_from_slice(b"DiskDude!");
+    let cartridge = Cartridge::load_rom_data(&mut garbage.as_slice()).unwrap();
+    assert_eq!(cartridge.ines_header.mapper, 1);
+    let warning = cartridge.report.warnings().next().unwrap();
+    assert_eq!((warning.offset, &warning.problem), (7, &Problem::ReservedBytesNotZero));
+
+    // trailing bytes in an iNES file
+    let mut overdump = rom.clone();
+    overdump.extend_from_slice(&[0; 0x100]);
+    let cartridge = Cartridge::load_rom_data(&mut overdump.as_slice()).unwrap();
+    assert_eq!(
+        cartridge.report.warnings().map(|warning| &warning.problem).collect::<Vec<_>>(),
+        vec![&Problem::Overdump { expected: rom.len(), found: rom.len() + 0x100 }]
+    );
+
+    // a truncated file fails a strict load and is padded by a lenient one
+    let truncated = &rom[..rom.len() - 0x1000];
+    assert!(matches!(Cartridge::load_rom_data(&mut &truncated[..]), Err(RomParseError::InvalidRomSize)));
+    let cartridge = Cartridge::load_rom_data_with_policy(&mut &truncated[..], LoadPolicy::Lenient).unwrap();
+    assert_eq!(cartridge.chr_rom.len(), 0x2000);
+    assert_eq!(cartridge.chr_rom[0x1FFF], 0xFF);
+    let error = cartridge.report.errors().next().unwrap();
+    assert_eq!(error.severity, Severity::Error);
+    assert_eq!(error.offset, truncated.len());
+    assert_eq!(error.problem, Problem::Underdump { expected: rom.len(), found: truncated.len() });
+}
+
+#[test]
+fn test_dump_analysis() {
+    use emurom::nes::cartridge::Cartridge;
+    use emurom::nes::dump::{analyze, canonicalize, TrailingData};
+
+    // 32 KiB PRG whose halves are identical, 8 KiB CHR
+    let mut rom = vec![0; 16];
+    rom[..8].copy_from_slice(&[b'N', b'E', b'S', 0x1A, 2, 1, 0x00, 0x00]);
+    let bank: Vec<u8> = (0..0x4000).map(|i| (i % 251) as u8).collect();
+    rom.extend_from_slice(&bank);
+    rom.extend_from_slice(&bank);
+    rom.extend((0..0x2000).map(|i| (i % 13) as u8));
+    let trailing = |data: &[u8]| {
+        let mut file = rom.clone();
+        file.extend_from_slice(data);
+        analyze(&file).unwrap().trailing.map(|(_, kind)| kind)
+    };
+
+    let analysis = analyze(&rom).unwrap();
+    assert_eq!(analysis.expected_size, rom.len());
+    assert_eq!(analysis.trailing, None);
+    assert_eq!(analysis.prg_unique_size, 0x4000, "PRG halves not detected");
+    assert_eq!(analysis.chr_unique_size, 0x2000);
+
+    assert_eq!(trailing(&[0xFF; 0x100]), Some(TrailingData::Padding(0xFF)));
+    assert_eq!(trailing(&rom[16..16 + 0x6000]), Some(TrailingData::MirroredPrg));
+    assert_eq!(trailing(&rom[16 + 0x8000..]), Some(TrailingData::MirroredChr));
+    assert_eq!(trailing(&rom[16..]), Some(TrailingData::MirroredPrgChr));
+    assert_eq!(trailing(b"Dumped by someone\0\0\0"), Some(TrailingData::Text("Dumped by someone".into())));
+    assert_eq!(trailing(&[0x01, 0x80, 0x02, 0x90]), Some(TrailingData::Unknown));
+    assert_eq!(trailing(&[0x00, 0xFF, 0xFF, 0x00]), Some(TrailingData::Filler));
+
+    // junk does not end up as misc ROM, unknown data does
+    let mut file = rom.clone();
+    file.extend_from_slice(&[0xFF; 0x100]);
+    assert_eq!(Cartridge::load_rom_data(&mut file.as_slice()).unwrap().misc_rom, None);
+    let mut file = rom.clone();
+    file.extend_from_slice(&[0x01, 0x80, 0x02, 0x90]);
+    assert_eq!(Cartridge::load_rom_data(&mut file.as_slice()).unwrap().misc_rom, Some(vec![0x01, 0x80, 0x02, 0x90]));
+
+    // NES 2.0 misc ROM is kept whatever it looks like
+    let mut file = rom.clone();
+    file[7] = 0x08;
+    file[14] = 0x01;
+    file.extend_from_slice(&[0xFF; 0x100]);
+    assert_eq!(analyze(&file).unwrap().trailing, Some((0x100, TrailingData::MiscRom)));
+
+    // the overdumped, mirrored file and the clean 16 KiB one come out the same
+    let mut overdump = rom.clone();
+    overdump.extend_from_slice(b"TITLE");
+    let canonical = canonicalize(&overdump).unwrap();
+    assert_eq!(canonical.len(), 16 + 0x4000 + 0x2000);
+    assert_eq!(canonical[4], 1);
+    assert_eq!(canonical, canonicalize(&canonical).unwrap());
+    let header = emurom::nes::header::InesHeader::from_bytes(&canonical).unwrap();
+    assert!(analyze(&canonical).unwrap().is_canonical(&header));
+
+    // an underdump is reported and left alone
+    let analysis = analyze(&rom[..rom.len() - 0x100]).unwrap();
+    assert_eq!(analysis.missing, 0x100);
+    assert_eq!(canonicalize(&rom[..rom.len() - 0x100]).unwrap().len(), rom.len() - 0x100);
+}
+
+#[test]
+fn test_header_variants() {
+    use emurom::nes::dump::canonicalize;
+    use emurom::nes::header::{HeaderFormat, InesHeader};
+
+    let parse = |bytes_7_15: &[u8; 9], file_size: usize| {
+        let mut rom = vec![0; file_size.max(16)];
+        rom[..7].copy_from_slice(&[b'N', b'E', b'S', 0x1A, 1, 1, 0x41]);
+        rom[7..16].copy_from_slice(bytes_7_15);
+        InesHeader::from_bytes_with_report(&rom).unwrap()
+    };
+    let image_size = 16 + 0x4000 + 0x2000;
+
+    // iNES 1.0 keeps the mapper high nibble: mapper 0x44
+    let (header, report) = parse(&[0x40, 1, 0, 0, 0, 0, 0, 0, 0], image_size);
+    assert_eq!(header.format, HeaderFormat::INes);
+    assert_eq!(header.mapper, 0x44);
+    assert_eq!(header.prg_ram_size, emurom::nes::header::RamSize::Ines(8 * 1024));
+    assert!(report.is_clean());
+
+    // iNES 0.7: byte 7 is fine but a signature fills the end
+    let (header, report) = parse(&[0x40, 1, 0, 0, 0, b'a', b'b', b'c', b'd'], image_size);
+    assert_eq!(header.format, HeaderFormat::INes07);
+    assert_eq!(header.mapper, 0x44);
+    assert_eq!(header.prg_ram_size, emurom::nes::header::RamSize::Ines(0), "byte 8 not masked");
+    assert_eq!(report.warnings().next().unwrap().offset, 8);
+
+    // archaic iNES: byte 7 is garbage too
+    let (header, _) = parse(b"DiskDude!", image_size);
+    assert_eq!(header.format, HeaderFormat::ArchaicINes);
+    assert_eq!(header.mapper, 4);
+    assert_eq!(header.flags_7.into_bits(), 0);
+
+    // NES 2.0 claiming more ROM than the file holds is not trusted
+    let (header, _) = parse(&[0x48, 0, 0, 0, 0, 0, 0, 0, 0], image_size);
+    assert_eq!(header.format, HeaderFormat::Nes2);
+    let (header, _) = parse(&[0x48, 0, 0, 0, 0, 0, 0, 0, 0], image_size - 0x100);
+    assert_eq!(header.format, HeaderFormat::ArchaicINes);
+    // a bare header has nothing to check against
+    assert_eq!(parse(&[0x48, 0, 0, 0, 0, 0, 0, 0, 0], 16).0.format, HeaderFormat::Nes2);
+
+    // NES 2.0 exponent-multiplier sizes: 2^13 * 3 = 24 KiB of PRG-ROM, which stays that way
+    let mut rom = vec![0; 16 + 0x6000 + 0x2000];
+    rom[..10].copy_from_slice(&[b'N', b'E', b'S', 0x1A, 13 << 2 | 1, 1, 0, 0x08, 0, 0x0F]);
+    let (header, report) = InesHeader::from_bytes_with_report(&rom).unwrap();
+    assert_eq!(header.format, HeaderFormat::Nes2);
+    assert_eq!(header.prg_rom_size, 0x6000);
+    assert!(report.is_clean());
+    assert_eq!(canonicalize(&rom).unwrap(), rom);
+
+    // 2^15 = 32 KiB made of two copies is cut to one 16 KiB unit
+    let mut rom = vec![0; 16 + 0x8000 + 0x2000];
+    rom[..10].copy_from_slice(&[b'N', b'E', b'S', 0x1A, 15 << 2, 1, 0, 0x08, 0, 0x0F]);
+    let canonical = canonicalize(&rom).unwrap();
+    assert_eq!(canonical.len(), 16 + 0x4000 + 0x2000);
+    assert_eq!((canonical[4], canonical[9]), (1, 0));
+}
+
+#[test]
+fn test_ines_tv_system_and_ram_bits() {
+    use emurom::nes::header::{InesHeader, TimingMode};
+
+    let header = |mapper_low: u8, byte_9: u8, byte_10: u8| {
+        let mut bytes = [b'N', b'E', b'S', 0x1A, 1, 1, mapper_low << 4, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        bytes[9] = byte_9;
+        bytes[10] = byte_10;
+        InesHeader::from_bytes(&bytes).unwrap()
+    };
+
+    assert_eq!(header(0, 0, 0).timing_mode(), TimingMode::NTSC);
+    assert_eq!(header(0, 0x01, 0).timing_mode(), TimingMode::PAL, "byte 9 PAL bit");
+    assert_eq!(header(0, 0, 0x02).timing_mode(), TimingMode::PAL, "byte 10 TV system");
+    assert_eq!(header(0, 0, 0x03).timing_mode(), TimingMode::MultipleRegions);
+    assert_eq!(header(0, 0x01, 0x01).timing_mode(), TimingMode::PAL, "byte 9 wins");
+
+    // byte 10 bit 4 rules out PRG-RAM the mapper would otherwise get by default
+    let rom = |byte_10: u8| {
+        let mut rom = vec![0; 16 + 0x4000 + 0x2000];
+        rom[..16].copy_from_slice(&[b'N', b'E', b'S', 0x1A, 1, 1, 0x10, 0, 0, 0, byte_10, 0, 0, 0, 0, 0]);
+        emurom::nes::cartridge::Cartridge::load_rom_data(&mut rom.as_slice()).unwrap()
+    };
+    assert!(rom(0).ines_header.prg_ram_present());
+    assert_eq!(emurom::nes::memory::CartridgeMemory::new(&rom(0)).prg_ram.len(), 8 * 1024);
+    assert!(!rom(0x10).ines_header.prg_ram_present());
+    assert!(emurom::nes::memory::CartridgeMemory::new(&rom(0x10)).prg_ram.is_empty());
+
+    assert!(header(2, 0, 0x20).bus_conflicts());
+    assert!(!header(2, 0, 0).bus_conflicts());
+
+    // NES 2.0 uses bytes 9 and 10 for sizes, not these bits
+    let mut bytes = [b'N', b'E', b'S', 0x1A, 1, 1, 0x20, 0x08, 0x00, 0, 0x02, 0, 0x01, 0, 0, 0];
+    let nes2 = InesHeader::from_bytes(&bytes).unwrap();
+    assert_eq!(nes2.timing_mode(), TimingMode::PAL);
+    assert!(!nes2.bus_conflicts());
+    assert!(nes2.prg_ram_present());
+    bytes[8] = 0x20;
+    assert!(InesHeader::from_bytes(&bytes).unwrap().bus_conflicts(), "UxROM submapper 2");
+}
+
+#[test]
+fn test_timing_parameters() {
+    use emurom::nes::header::TimingMode;
+    use emurom::nes::timing::TimingParameters;
+
+    let ntsc = TimingParameters::for_mode(TimingMode::NTSC);
+    assert_eq!(ntsc.cpu_clock().round(), 1_789_773.0);
+    assert_eq!(ntsc.dots_per_cpu_cycle(), 3.0);
+    assert_eq!(ntsc.dots_per_frame(), 89_341.5);
+    assert!((ntsc.frame_rate() - 60.0988).abs() < 1e-4);
+    assert_eq!(ntsc.vblank_start_scanline(), 241);
+    assert_eq!(TimingParameters::for_mode(TimingMode::MultipleRegions), ntsc);
+
+    let pal = TimingParameters::for_mode(TimingMode::PAL);
+    assert_eq!(pal.cpu_clock().round(), 1_662_607.0);
+    assert_eq!(pal.dots_per_cpu_cycle(), 3.2);
+    assert!((pal.frame_rate() - 50.0070).abs() < 1e-4);
+    assert_eq!(pal.vblank_cpu_cycles(), 7459.375);
+
+    let dendy = TimingParameters::for_mode(TimingMode::Dendy);
+    assert_eq!(dendy.dots_per_cpu_cycle(), 3.0);
+    assert_eq!(dendy.frame_rate(), pal.frame_rate());
+    assert_eq!(dendy.vblank_start_scanline(), 291);
+    assert_eq!(dendy.vblank_cpu_cycles(), ntsc.vblank_cpu_cycles());
+
+    for params in [ntsc, pal, dendy] {
+        assert_eq!(params.vblank_start_scanline() + params.vblank_scanlines + 1, params.scanlines);
+    }
+}
+
+#[test]
+fn test_vs_system_type() {
+    use emurom::nes::header::{Flags13Nes2, InesHeader};
+    use emurom::nes::vs::{VsHardwareType, VsPpuType};
+
+    let vs_type = |byte_13: u8| {
+        let bytes = [b'N', b'E', b'S', 0x1A, 1, 1, 0x00, 0x09, 0, 0, 0, 0, 0, byte_13, 0, 0];
+        match InesHeader::from_bytes(&bytes).unwrap().flags_13 {
+            Flags13Nes2::VsSystemType(vs) => vs,
+            other => panic!("not a Vs. System header: {:?}", other),
+        }
+    };
+
+    let vs = vs_type(0x53);
+    assert_eq!(vs.ppu_type(), VsPpuType::RP2C04_0002);
+    assert_eq!(vs.hardware_type(), VsHardwareType::DualSystem);
+    assert!(vs.hardware_type().is_dual_system());
+    assert_eq!(vs_type(0x0C).ppu_type(), VsPpuType::Reserved(0x0C));
+    assert_eq!(vs_type(0x70).hardware_type(), VsHardwareType::Reserved(7));
+    assert_eq!(vs_type(0x3A).hardware_type(), VsHardwareType::UnisystemSuperXevious);
+
+    // every RP2C04 table is a scrambled RP2C03 palette
+    for ppu in [VsPpuType::RP2C04_0001, VsPpuType::RP2C04_0002, VsPpuType::RP2C04_0003, VsPpuType::RP2C04_0004] {
+        let lut = ppu.palette_lut().unwrap();
+        let mut colors = lut.to_vec();
+        colors.sort();
+        colors.dedup();
+        assert_eq!(colors.len(), 60, "{:?}", ppu);
+        assert!(!ppu.swaps_ctrl_and_mask());
+    }
+    assert_eq!(VsPpuType::RP2C04_0001.palette_index(0x40), 0x35, "index wraps at 64");
+    assert_eq!(VsPpuType::RP2C03.palette_index(0x21), 0x21);
+
+    // RC2C05: PPUCTRL and PPUMASK trade places, PPUSTATUS carries an ID
+    let rc2c05 = VsPpuType::RC2C05_02;
+    assert_eq!(rc2c05.register(0x2000), 1);
+    assert_eq!(rc2c05.register(0x3FF9), 0);
+    assert_eq!(rc2c05.register(0x2002), 2);
+    assert_eq!(rc2c05.read_status(0xE0, 0xFF), 0xFD);
+    assert_eq!(VsPpuType::RC2C05_03.read_status(0x80, 0xFF), 0x9C);
+    assert_eq!(VsPpuType::RP2C03.register(0x2000), 0);
+    assert_eq!(VsPpuType::RP2C03.read_status(0xA0, 0x15), 0xB5);
+}
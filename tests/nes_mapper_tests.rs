@@ -0,0 +1,671 @@
+use emurom::nes::cartridge::Cartridge;
+use emurom::nes::mapper::{new_mapper, Ciram, ExpansionAudio, Mapper, CIRAM_SIZE};
+use emurom::nes::memory::CartridgeMemory;
+
+
+/// Build an iNES image where every 8 KiB PRG bank and 1 KiB CHR bank is filled with its bank number
+fn build_rom(mapper: u16, prg_16k: u8, chr_8k: u8) -> Cartridge {
+    let header = [
+        b'N', b'E', b'S', 0x1A, prg_16k, chr_8k,
+        ((mapper & 0x0F) << 4) as u8, (mapper & 0xF0) as u8,
+        0, 0, 0, 0, 0, 0, 0, 0,
+    ];
+    build_image(header, prg_16k, chr_8k)
+}
+
+/// Same as `build_rom` with a NES 2.0 header carrying a submapper and 8 KiB of PRG-RAM
+fn build_nes2_rom(mapper: u16, submapper: u8, prg_16k: u8, chr_8k: u8) -> Cartridge {
+    build_nes2_rom_with_ram(mapper, submapper, 0x07, prg_16k, chr_8k)
+}
+
+/// NES 2.0 image with an explicit PRG-RAM/PRG-NVRAM size byte (header byte 10)
+fn build_nes2_rom_with_ram(mapper: u16, submapper: u8, prg_ram: u8, prg_16k: u8, chr_8k: u8) -> Cartridge {
+    let header = [
+        b'N', b'E', b'S', 0x1A, prg_16k, chr_8k,
+        ((mapper & 0x0F) << 4) as u8, (mapper & 0xF0) as u8 | 0x08,
+        (submapper << 4) | (mapper >> 8) as u8, 0, prg_ram, 0, 0, 0, 0, 0,
+    ];
+    build_image(header, prg_16k, chr_8k)
+}
+
+fn build_image(header: [u8; 16], prg_16k: u8, chr_8k: u8) -> Cartridge {
+    let mut bytes = header.to_vec();
+    for bank in 0..(prg_16k as usize * 2) {
+        bytes.extend(std::iter::repeat_n(bank as u8, 8 * 1024));
+    }
+    for bank in 0..(chr_8k as usize * 8) {
+        bytes.extend(std::iter::repeat_n(bank as u8, 1024));
+    }
+    Cartridge::load_rom_data(&mut bytes.as_slice()).expect("Failed to load generated ROM")
+}
+
+/// Feed the PPU fetch pattern of one rendered scanline through the mapper
+fn render_scanline(mapper: &mut dyn Mapper, ciram: &Ciram) {
+    for tile in 0..32u16 {
+        mapper.ppu_read(0x2000 + tile, ciram);
+        mapper.ppu_read(0x23C0, ciram);
+        mapper.ppu_read(0x0000, ciram);
+        mapper.ppu_read(0x0008, ciram);
+    }
+    for _ in 0..8 {
+        mapper.ppu_read(0x2000, ciram);
+        mapper.ppu_read(0x2000, ciram);
+        mapper.ppu_read(0x1000, ciram);
+        mapper.ppu_read(0x1008, ciram);
+    }
+    for tile in 0..2u16 {
+        mapper.ppu_read(0x2000 + tile, ciram);
+        mapper.ppu_read(0x23C0, ciram);
+        mapper.ppu_read(0x0000, ciram);
+        mapper.ppu_read(0x0008, ciram);
+    }
+    mapper.ppu_read(0x2002, ciram);
+    mapper.ppu_read(0x2002, ciram);
+    // the first fetch of the next line is the third identical read
+    mapper.ppu_read(0x2002, ciram);
+}
+
+/// Drive the Bandai EEPROM lines through $800D, with EEPROM reads enabled
+fn eeprom_lines(mapper: &mut dyn Mapper, scl: bool, sda: bool) {
+    mapper.cpu_write(0x800D, 0x80 | (sda as u8) << 6 | (scl as u8) << 5);
+}
+
+fn eeprom_start(mapper: &mut dyn Mapper) {
+    eeprom_lines(mapper, false, true);
+    eeprom_lines(mapper, true, true);
+    eeprom_lines(mapper, true, false);
+    eeprom_lines(mapper, false, false);
+}
+
+fn eeprom_stop(mapper: &mut dyn Mapper) {
+    eeprom_lines(mapper, false, false);
+    eeprom_lines(mapper, true, false);
+    eeprom_lines(mapper, true, true);
+}
+
+/// Clock one bit out to the EEPROM and return SDA as sampled while SCL was high
+fn eeprom_clock(mapper: &mut dyn Mapper, sda: bool) -> bool {
+    eeprom_lines(mapper, false, sda);
+    eeprom_lines(mapper, true, sda);
+    let out = mapper.cpu_read(0x6000).unwrap() & 0x10 != 0;
+    eeprom_lines(mapper, false, sda);
+    out
+}
+
+/// Send a byte and return whether the EEPROM acknowledged it
+fn eeprom_send(mapper: &mut dyn Mapper, byte: u8, lsb_first: bool) -> bool {
+    for bit in 0..8 {
+        let shift = if lsb_first { bit } else { 7 - bit };
+        eeprom_clock(mapper, byte >> shift & 1 != 0);
+    }
+    !eeprom_clock(mapper, true)
+}
+
+fn eeprom_receive(mapper: &mut dyn Mapper, lsb_first: bool, ack: bool) -> u8 {
+    let mut byte = 0;
+    for bit in 0..8 {
+        let shift = if lsb_first { bit } else { 7 - bit };
+        byte |= (eeprom_clock(mapper, true) as u8) << shift;
+    }
+    eeprom_clock(mapper, !ack);
+    byte
+}
+
+#[test]
+fn test_unsupported_mapper() {
+    let cartridge = build_rom(0, 1, 1);
+    assert!(new_mapper(&cartridge).is_err());
+}
+
+#[test]
+fn test_fixed_mirroring() {
+    use emurom::nes::error::RomParseError;
+    use emurom::nes::header::Mirroring;
+
+    // VRC3 mirroring is soldered, so the header decides it
+    let mut header = [b'N', b'E', b'S', 0x1A, 2, 1, 0x91, 0x48, 0, 0, 0, 0, 0, 0, 0, 0];
+    let mut mapper = new_mapper(&build_image(header, 2, 1)).unwrap();
+    let mut ciram: Ciram = [0; CIRAM_SIZE];
+    mapper.ppu_write(0x2C05, 0x42, &mut ciram);
+    assert_eq!(ciram[0x405], 0x42, "vertical mirroring");
+
+    // the board has no VRAM for four screens
+    header[6] = 0x98;
+    let error = new_mapper(&build_image(header, 2, 1)).err();
+    assert!(matches!(error, Some(RomParseError::UnsupportedMirroring(73, Mirroring::FourScreen))));
+}
+
+#[test]
+fn test_cartridge_memory_defaults() {
+    // iNES leaves PRG-RAM unspecified: MMC2 boards have none, MMC4 boards 8 KiB
+    assert!(CartridgeMemory::new(&build_rom(9, 2, 1)).prg_ram.is_empty());
+    assert_eq!(CartridgeMemory::new(&build_rom(10, 2, 1)).prg_ram.len(), 8 * 1024);
+    assert_eq!(CartridgeMemory::new(&build_rom(5, 2, 1)).prg_ram.len(), 64 * 1024);
+    // with a battery, only the first 8 KiB of it is saved
+    let mut header = [b'N', b'E', b'S', 0x1A, 2, 1, 0x52, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    let memory = CartridgeMemory::new(&build_image(header, 2, 1));
+    assert_eq!((memory.prg_ram.len(), memory.prg_nvram_len), (64 * 1024, 8 * 1024));
+
+    let cprom = CartridgeMemory::new(&build_rom(13, 2, 0));
+    assert!(cprom.chr_is_ram);
+    assert_eq!(cprom.chr.len(), 16 * 1024);
+
+    // NES 2.0: 2 KiB PRG-RAM after 8 KiB PRG-NVRAM, 8 KiB CHR-NVRAM
+    header = [b'N', b'E', b'S', 0x1A, 2, 0, 0x02, 0x08, 0, 0, 0x75, 0x70, 0, 0, 0, 0];
+    let memory = CartridgeMemory::new(&build_image(header, 2, 0));
+    assert_eq!((memory.prg_ram.len(), memory.prg_nvram_len), (10 * 1024, 8 * 1024));
+    assert_eq!((memory.chr.len(), memory.chr_nvram_len), (8 * 1024, 8 * 1024));
+    assert_eq!(memory.save_data().map(|data| data.len()), Some(16 * 1024));
+
+    // battery flag without a size
+    header[7] = 0;
+    header[10] = 0;
+    header[11] = 0;
+    let memory = CartridgeMemory::new(&build_image(header, 2, 0));
+    assert_eq!((memory.prg_ram.len(), memory.prg_nvram_len), (8 * 1024, 8 * 1024));
+}
+
+#[test]
+fn test_cartridge_memory_trainer() {
+    let mut bytes = vec![b'N', b'E', b'S', 0x1A, 2, 1, 0xA4, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    bytes.extend((0..512).map(|index| index as u8));
+    bytes.resize(bytes.len() + 32 * 1024 + 8 * 1024, 0);
+    let cartridge = Cartridge::load_rom_data(&mut bytes.as_slice()).unwrap();
+
+    // MMC4 maps its PRG-RAM at $6000, so the trainer shows up at $7000
+    let mut mapper = new_mapper(&cartridge).unwrap();
+    assert_eq!(mapper.cpu_read(0x7000), Some(0x00));
+    assert_eq!(mapper.cpu_read(0x7001), Some(0x01));
+    assert_eq!(mapper.cpu_read(0x71FF), Some(0xFF));
+
+    // boards without PRG-RAM still get a window for it
+    bytes[6] = 0x94;
+    let cartridge = Cartridge::load_rom_data(&mut bytes.as_slice()).unwrap();
+    let memory = CartridgeMemory::new(&cartridge);
+    assert_eq!(memory.prg_ram.len(), 8 * 1024);
+    assert_eq!(memory.prg_ram[0x1005], 0x05);
+
+    // on a battery-backed board the trainer lies in the NVRAM, and wins over a loaded save
+    bytes[6] = 0xA6;
+    let cartridge = Cartridge::load_rom_data(&mut bytes.as_slice()).unwrap();
+    let mut mapper = new_mapper(&cartridge).unwrap();
+    mapper.load_save_data(&[0xEE; 8 * 1024]);
+    assert_eq!(mapper.cpu_read(0x6000), Some(0xEE));
+    assert_eq!(mapper.cpu_read(0x7001), Some(0x01));
+    assert_eq!(mapper.cpu_read(0x7200), Some(0xEE));
+    assert_eq!(mapper.save_data().unwrap()[0x1001], 0x01);
+}
+
+#[test]
+fn test_mmc5_prg_modes() {
+    let cartridge = build_rom(5, 16, 1);
+    let mut mapper = new_mapper(&cartridge).unwrap();
+
+    // power-on: mode 3 with the last bank at $E000
+    assert_eq!(mapper.cpu_read(0xE000), Some(31), "Reset bank mismatch");
+
+    // mode 0: one 32 KiB bank from $5117
+    mapper.cpu_write(0x5100, 0);
+    mapper.cpu_write(0x5117, 0x85);
+    assert_eq!(mapper.cpu_read(0x8000), Some(4));
+    assert_eq!(mapper.cpu_read(0xE000), Some(7));
+
+    // mode 1: 16 KiB banks from $5115 and $5117
+    mapper.cpu_write(0x5100, 1);
+    mapper.cpu_write(0x5115, 0x8B);
+    assert_eq!(mapper.cpu_read(0x8000), Some(10));
+    assert_eq!(mapper.cpu_read(0xA000), Some(11));
+    assert_eq!(mapper.cpu_read(0xC000), Some(4));
+
+    // mode 2: 16 KiB + 8 KiB + 8 KiB
+    mapper.cpu_write(0x5100, 2);
+    mapper.cpu_write(0x5116, 0x93);
+    assert_eq!(mapper.cpu_read(0xA000), Some(11));
+    assert_eq!(mapper.cpu_read(0xC000), Some(19));
+    assert_eq!(mapper.cpu_read(0xE000), Some(5));
+
+    // mode 3: four 8 KiB banks
+    mapper.cpu_write(0x5100, 3);
+    mapper.cpu_write(0x5114, 0x82);
+    assert_eq!(mapper.cpu_read(0x8000), Some(2));
+}
+
+#[test]
+fn test_mmc5_prg_ram_protect() {
+    let cartridge = build_rom(5, 2, 1);
+    let mut mapper = new_mapper(&cartridge).unwrap();
+
+    mapper.cpu_write(0x6000, 0x42);
+    assert_eq!(mapper.cpu_read(0x6000), Some(0), "Write-protected RAM was modified");
+
+    mapper.cpu_write(0x5102, 2);
+    mapper.cpu_write(0x5103, 1);
+    mapper.cpu_write(0x6000, 0x42);
+    assert_eq!(mapper.cpu_read(0x6000), Some(0x42));
+
+    // RAM mapped into $8000 through $5114 bit 7 = 0
+    mapper.cpu_write(0x5114, 0x00);
+    assert_eq!(mapper.cpu_read(0x8000), Some(0x42));
+}
+
+#[test]
+fn test_mmc5_multiplier() {
+    let cartridge = build_rom(5, 2, 1);
+    let mut mapper = new_mapper(&cartridge).unwrap();
+
+    mapper.cpu_write(0x5205, 200);
+    mapper.cpu_write(0x5206, 100);
+    assert_eq!(mapper.cpu_read(0x5205), Some((20000u16 & 0xFF) as u8));
+    assert_eq!(mapper.cpu_read(0x5206), Some((20000u16 >> 8) as u8));
+}
+
+#[test]
+fn test_mmc5_scanline_irq() {
+    let cartridge = build_rom(5, 2, 1);
+    let mut mapper = new_mapper(&cartridge).unwrap();
+    let ciram: Ciram = [0; CIRAM_SIZE];
+
+    mapper.cpu_write(0x2001, 0x18);
+    mapper.cpu_write(0x5203, 3);
+    mapper.cpu_write(0x5204, 0x80);
+
+    // pre-render line enters the frame
+    render_scanline(mapper.as_mut(), &ciram);
+    assert_eq!(mapper.cpu_read(0x5204).unwrap() & 0x40, 0x40, "In-frame flag not set");
+
+    for _ in 0..2 {
+        render_scanline(mapper.as_mut(), &ciram);
+    }
+    assert!(!mapper.irq());
+    render_scanline(mapper.as_mut(), &ciram);
+    assert!(mapper.irq(), "IRQ not raised on compare scanline");
+
+    // reading the status acknowledges the IRQ
+    assert_eq!(mapper.cpu_read(0x5204).unwrap() & 0x80, 0x80);
+    assert!(!mapper.irq());
+
+    // the NMI vector fetch leaves the frame
+    mapper.cpu_read(0xFFFA);
+    assert_eq!(mapper.cpu_read(0x5204).unwrap() & 0x40, 0);
+}
+
+#[test]
+fn test_mmc5_nametables() {
+    let cartridge = build_rom(5, 2, 1);
+    let mut mapper = new_mapper(&cartridge).unwrap();
+    let mut ciram: Ciram = [0; CIRAM_SIZE];
+
+    // $2000 = CIRAM A, $2400 = CIRAM B, $2800 = ExRAM, $2C00 = fill
+    mapper.cpu_write(0x5105, 0b11_10_01_00);
+    mapper.cpu_write(0x5106, 0x7E);
+    mapper.cpu_write(0x5107, 2);
+
+    mapper.ppu_write(0x2000, 0x11, &mut ciram);
+    mapper.ppu_write(0x2400, 0x22, &mut ciram);
+    mapper.ppu_write(0x2800, 0x33, &mut ciram);
+    assert_eq!(ciram[0x000], 0x11);
+    assert_eq!(ciram[0x400], 0x22);
+
+    assert_eq!(mapper.ppu_read(0x2800, &ciram), 0x33, "ExRAM nametable mismatch");
+    assert_eq!(mapper.ppu_read(0x2C05, &ciram), 0x7E, "Fill tile mismatch");
+    assert_eq!(mapper.ppu_read(0x2FC0, &ciram), 0xAA, "Fill attribute mismatch");
+
+    // ExRAM as CPU RAM
+    mapper.cpu_write(0x5104, 2);
+    mapper.cpu_write(0x5C10, 0x5A);
+    assert_eq!(mapper.cpu_read(0x5C10), Some(0x5A));
+}
+
+#[test]
+fn test_mmc5_chr_sets() {
+    let cartridge = build_rom(5, 2, 2);
+    let mut mapper = new_mapper(&cartridge).unwrap();
+    let ciram: Ciram = [0; CIRAM_SIZE];
+
+    // 1 KiB CHR banks, set A selects bank 9 at $1000, set B bank 3
+    mapper.cpu_write(0x5101, 3);
+    mapper.cpu_write(0x5124, 9);
+    assert_eq!(mapper.ppu_read(0x1000, &ciram), 9);
+    mapper.cpu_write(0x5128, 3);
+    // last written set wins outside of rendering
+    assert_eq!(mapper.ppu_read(0x1000, &ciram), 3);
+}
+
+#[test]
+fn test_vrc4_address_lines() {
+    // VRC4a (mapper 21, submapper 1) selects registers with A1/A2
+    let cartridge = build_nes2_rom(21, 1, 8, 16);
+    let mut mapper = new_mapper(&cartridge).unwrap();
+    let ciram: Ciram = [0; CIRAM_SIZE];
+
+    mapper.cpu_write(0x8000, 5);
+    mapper.cpu_write(0xA000, 6);
+    assert_eq!(mapper.cpu_read(0x8000), Some(5));
+    assert_eq!(mapper.cpu_read(0xA000), Some(6));
+    assert_eq!(mapper.cpu_read(0xC000), Some(14));
+    assert_eq!(mapper.cpu_read(0xE000), Some(15));
+
+    // $9004 is $9002 on VRC4a: PRG swap mode
+    mapper.cpu_write(0x9004, 0x02);
+    assert_eq!(mapper.cpu_read(0x8000), Some(14));
+    assert_eq!(mapper.cpu_read(0xC000), Some(5));
+
+    // CHR bank 0 = $15 through low ($B000) and high ($B002 on VRC4a) halves
+    mapper.cpu_write(0xB000, 0x05);
+    mapper.cpu_write(0xB002, 0x01);
+    assert_eq!(mapper.ppu_read(0x0000, &ciram), 0x15);
+
+    // VRC4c (submapper 2) uses A6/A7 instead
+    let cartridge = build_nes2_rom(21, 2, 8, 16);
+    let mut mapper = new_mapper(&cartridge).unwrap();
+    mapper.cpu_write(0xB000, 0x03);
+    mapper.cpu_write(0xB002, 0x01); // A1 is ignored: still the low nibble
+    mapper.cpu_write(0xB040, 0x01);
+    assert_eq!(mapper.ppu_read(0x0000, &ciram), 0x11);
+}
+
+#[test]
+fn test_vrc_irq_modes() {
+    let cartridge = build_nes2_rom(25, 1, 8, 16);
+    let mut mapper = new_mapper(&cartridge).unwrap();
+
+    // VRC4b: $F000/$F002/$F001/$F003 are latch low, latch high, control, ack
+    mapper.cpu_write(0xF000, 0x0C);
+    mapper.cpu_write(0xF002, 0x0F);
+    // cycle mode, enabled
+    mapper.cpu_write(0xF001, 0x06);
+    for _ in 0..(0xFF - 0xFC) {
+        mapper.cpu_clock();
+    }
+    assert!(!mapper.irq());
+    mapper.cpu_clock();
+    assert!(mapper.irq(), "Cycle mode IRQ not raised");
+    mapper.cpu_write(0xF003, 0);
+    assert!(!mapper.irq());
+
+    // scanline mode: one count per 113.67 CPU cycles
+    mapper.cpu_write(0xF000, 0x0E);
+    mapper.cpu_write(0xF001, 0x02);
+    for _ in 0..(341 * 2 / 3) {
+        mapper.cpu_clock();
+    }
+    assert!(!mapper.irq());
+    for _ in 0..(341 / 3 + 1) {
+        mapper.cpu_clock();
+    }
+    assert!(mapper.irq(), "Scanline mode IRQ not raised");
+}
+
+#[test]
+fn test_vrc6_banking_and_audio() {
+    // VRC6b swaps A0/A1
+    let cartridge = build_rom(26, 8, 16);
+    let mut mapper = new_mapper(&cartridge).unwrap();
+    let ciram: Ciram = [0; CIRAM_SIZE];
+
+    mapper.cpu_write(0x8000, 2);
+    mapper.cpu_write(0xC000, 9);
+    assert_eq!(mapper.cpu_read(0x8000), Some(4));
+    assert_eq!(mapper.cpu_read(0xA000), Some(5));
+    assert_eq!(mapper.cpu_read(0xC000), Some(9));
+    assert_eq!(mapper.cpu_read(0xE000), Some(15));
+
+    // $D001 on VRC6b is R2 (the $D002 register)
+    mapper.cpu_write(0xD001, 0x21);
+    assert_eq!(mapper.ppu_read(0x0800, &ciram), 0x21);
+
+    // pulse 1: $9000 volume/duty, $9002 (VRC6b $9001) period high + enable
+    mapper.cpu_write(0x9000, 0x7A);
+    mapper.cpu_write(0x9002, 0x34);
+    mapper.cpu_write(0x9001, 0x83);
+    match mapper.expansion_audio() {
+        Some(ExpansionAudio::Vrc6(audio)) => {
+            assert_eq!(audio.pulse[0].volume, 0x0A);
+            assert_eq!(audio.pulse[0].duty, 7);
+            assert_eq!(audio.pulse[0].period, 0x334);
+            assert!(audio.pulse[0].enabled);
+        }
+        _ => panic!("VRC6 audio registers missing"),
+    }
+}
+
+#[test]
+fn test_vrc7_audio_port() {
+    let cartridge = build_nes2_rom(85, 2, 8, 16);
+    let mut mapper = new_mapper(&cartridge).unwrap();
+
+    mapper.cpu_write(0x8000, 3);
+    mapper.cpu_write(0x8010, 4);
+    mapper.cpu_write(0x9000, 7);
+    assert_eq!(mapper.cpu_read(0x8000), Some(3));
+    assert_eq!(mapper.cpu_read(0xA000), Some(4));
+    assert_eq!(mapper.cpu_read(0xC000), Some(7));
+
+    mapper.cpu_write(0x9010, 0x10);
+    mapper.cpu_write(0x9030, 0x45);
+    mapper.cpu_write(0x9010, 0x20);
+    mapper.cpu_write(0x9030, 0x1B);
+    mapper.cpu_write(0x9010, 0x30);
+    mapper.cpu_write(0x9030, 0x3F);
+    match mapper.expansion_audio() {
+        Some(ExpansionAudio::Vrc7(audio)) => {
+            let channel = audio.channel(0);
+            assert_eq!(channel.fnum, 0x145);
+            assert_eq!(channel.octave, 5);
+            assert!(channel.key_on);
+            assert_eq!(channel.instrument, 3);
+            assert_eq!(channel.volume, 0xF);
+        }
+        _ => panic!("VRC7 audio registers missing"),
+    }
+}
+
+#[test]
+fn test_mmc2_chr_latch() {
+    let cartridge = build_rom(9, 8, 16);
+    let mut mapper = new_mapper(&cartridge).unwrap();
+    let ciram: Ciram = [0; CIRAM_SIZE];
+
+    assert_eq!(mapper.cpu_read(0xA000), Some(13), "Fixed PRG bank mismatch");
+    assert_eq!(mapper.cpu_read(0xE000), Some(15), "Fixed PRG bank mismatch");
+
+    // $0000 uses bank 2 when latched on $FD, bank 5 on $FE (4 KiB banks, 4 CHR KiB per bank)
+    mapper.cpu_write(0xB000, 2);
+    mapper.cpu_write(0xC000, 5);
+    assert_eq!(mapper.ppu_read(0x0000, &ciram), 20, "Power-on latch should select $FE");
+
+    // the triggering fetch still reads the old bank; the next one sees the new bank
+    assert_eq!(mapper.ppu_read(0x0FD8, &ciram), 23);
+    assert_eq!(mapper.ppu_read(0x0000, &ciram), 8);
+
+    // MMC2 only triggers on exactly $0FE8 for the first table
+    mapper.ppu_read(0x0FE9, &ciram);
+    assert_eq!(mapper.ppu_read(0x0000, &ciram), 8);
+
+    // fetches the host serves itself are reported through ppu_address
+    mapper.ppu_address(0x0FE8);
+    assert_eq!(mapper.ppu_read(0x0000, &ciram), 20);
+}
+
+#[test]
+fn test_mmc4_chr_latch() {
+    let cartridge = build_nes2_rom(10, 0, 8, 16);
+    let mut mapper = new_mapper(&cartridge).unwrap();
+    let ciram: Ciram = [0; CIRAM_SIZE];
+
+    mapper.cpu_write(0xA000, 3);
+    assert_eq!(mapper.cpu_read(0x8000), Some(6));
+    assert_eq!(mapper.cpu_read(0xC000), Some(14));
+
+    mapper.cpu_write(0xB000, 1);
+    mapper.cpu_write(0xC000, 2);
+    mapper.ppu_read(0x0FDB, &ciram);
+    assert_eq!(mapper.ppu_read(0x0000, &ciram), 4);
+
+    mapper.cpu_write(0x6000, 0x99);
+    assert_eq!(mapper.cpu_read(0x6000), Some(0x99), "MMC4 PRG-RAM mismatch");
+}
+
+#[test]
+fn test_fme7_banking_irq_and_audio() {
+    let cartridge = build_rom(69, 8, 16);
+    let mut mapper = new_mapper(&cartridge).unwrap();
+
+    mapper.cpu_write(0x8000, 0x9);
+    mapper.cpu_write(0xA000, 4);
+    assert_eq!(mapper.cpu_read(0x8000), Some(4));
+    assert_eq!(mapper.cpu_read(0xE000), Some(15));
+
+    // PRG-RAM at $6000 once selected and enabled
+    mapper.cpu_write(0x8000, 0x8);
+    mapper.cpu_write(0xA000, 0xC0);
+    mapper.cpu_write(0x6000, 0x77);
+    assert_eq!(mapper.cpu_read(0x6000), Some(0x77));
+
+    // IRQ fires when the down counter wraps past zero
+    mapper.cpu_write(0x8000, 0xE);
+    mapper.cpu_write(0xA000, 2);
+    mapper.cpu_write(0x8000, 0xF);
+    mapper.cpu_write(0xA000, 0);
+    mapper.cpu_write(0x8000, 0xD);
+    mapper.cpu_write(0xA000, 0x81);
+    for _ in 0..3 {
+        assert!(!mapper.irq());
+        mapper.cpu_clock();
+    }
+    assert!(mapper.irq(), "IRQ not raised on counter underflow");
+
+    // 5B audio port
+    mapper.cpu_write(0xC000, 0x00);
+    mapper.cpu_write(0xE000, 0x34);
+    mapper.cpu_write(0xC000, 0x01);
+    mapper.cpu_write(0xE000, 0x12);
+    mapper.cpu_write(0xC000, 0x07);
+    mapper.cpu_write(0xE000, 0b0011_1110);
+    match mapper.expansion_audio() {
+        Some(ExpansionAudio::Sunsoft5B(audio)) => {
+            assert_eq!(audio.tone_period(0), 0x234);
+            assert!(audio.tone_enabled(0));
+            assert!(!audio.tone_enabled(1));
+        }
+        _ => panic!("5B audio registers missing"),
+    }
+}
+
+#[test]
+fn test_namco163_internal_ram_and_irq() {
+    // 8 KiB WRAM plus the 128-byte internal RAM as NVRAM
+    let cartridge = build_nes2_rom_with_ram(19, 0, 0x17, 8, 16);
+    let mut mapper = new_mapper(&cartridge).unwrap();
+    let ciram: Ciram = [0; CIRAM_SIZE];
+
+    mapper.cpu_write(0xE000, 5);
+    mapper.cpu_write(0xF000, 9);
+    assert_eq!(mapper.cpu_read(0x8000), Some(5));
+    assert_eq!(mapper.cpu_read(0xC000), Some(9));
+    assert_eq!(mapper.cpu_read(0xE000), Some(15));
+    mapper.cpu_write(0x8800, 3);
+    assert_eq!(mapper.ppu_read(0x0400, &ciram), 3);
+
+    // internal RAM port with auto-increment
+    mapper.cpu_write(0xF800, 0x80);
+    for value in [0x11, 0x22, 0x33] {
+        mapper.cpu_write(0x4800, value);
+    }
+    mapper.cpu_write(0xF800, 0x81);
+    assert_eq!(mapper.cpu_read(0x4800), Some(0x22));
+    assert_eq!(mapper.cpu_read(0x4800), Some(0x33));
+
+    let save = mapper.save_data().expect("Internal RAM not battery-backed");
+    assert_eq!(save.len(), 128);
+    assert_eq!(&save[..3], &[0x11, 0x22, 0x33]);
+
+    mapper.cpu_write(0x5000, 0xFE);
+    mapper.cpu_write(0x5800, 0xFF);
+    assert!(!mapper.irq());
+    mapper.cpu_clock();
+    assert!(mapper.irq(), "IRQ not raised at $7FFF");
+}
+
+#[test]
+fn test_bandai_24c02_eeprom() {
+    let cartridge = build_nes2_rom_with_ram(16, 5, 0x20, 8, 16);
+    let mut mapper = new_mapper(&cartridge).unwrap();
+    let ciram: Ciram = [0; CIRAM_SIZE];
+
+    mapper.cpu_write(0x8008, 3);
+    mapper.cpu_write(0x8002, 7);
+    assert_eq!(mapper.cpu_read(0x8000), Some(6));
+    assert_eq!(mapper.cpu_read(0xC000), Some(14));
+    assert_eq!(mapper.ppu_read(0x0800, &ciram), 7);
+
+    eeprom_start(mapper.as_mut());
+    assert!(eeprom_send(mapper.as_mut(), 0xA0, false), "Device address not acknowledged");
+    assert!(eeprom_send(mapper.as_mut(), 0x10, false));
+    assert!(eeprom_send(mapper.as_mut(), 0x5A, false));
+    assert!(eeprom_send(mapper.as_mut(), 0xC3, false));
+    eeprom_stop(mapper.as_mut());
+
+    eeprom_start(mapper.as_mut());
+    assert!(eeprom_send(mapper.as_mut(), 0xA0, false));
+    assert!(eeprom_send(mapper.as_mut(), 0x10, false));
+    eeprom_start(mapper.as_mut());
+    assert!(eeprom_send(mapper.as_mut(), 0xA1, false));
+    assert_eq!(eeprom_receive(mapper.as_mut(), false, true), 0x5A);
+    assert_eq!(eeprom_receive(mapper.as_mut(), false, false), 0xC3);
+    eeprom_stop(mapper.as_mut());
+
+    let save = mapper.save_data().expect("EEPROM contents not saved");
+    assert_eq!(save.len(), 256);
+    assert_eq!(&save[0x10..0x12], &[0x5A, 0xC3]);
+
+    let mut reloaded = new_mapper(&cartridge).unwrap();
+    reloaded.load_save_data(&save);
+    assert_eq!(reloaded.save_data(), Some(save));
+}
+
+#[test]
+fn test_bandai_x24c01_eeprom() {
+    let cartridge = build_nes2_rom_with_ram(159, 0, 0x10, 8, 16);
+    let mut mapper = new_mapper(&cartridge).unwrap();
+
+    // the X24C01 takes a 7-bit address and the read/write bit in one byte, LSB first
+    eeprom_start(mapper.as_mut());
+    assert!(eeprom_send(mapper.as_mut(), 0x05, true), "Address not acknowledged");
+    assert!(eeprom_send(mapper.as_mut(), 0x3C, true));
+    eeprom_stop(mapper.as_mut());
+
+    eeprom_start(mapper.as_mut());
+    assert!(eeprom_send(mapper.as_mut(), 0x85, true));
+    assert_eq!(eeprom_receive(mapper.as_mut(), true, false), 0x3C);
+    eeprom_stop(mapper.as_mut());
+
+    let save = mapper.save_data().unwrap();
+    assert_eq!(save.len(), 128);
+    assert_eq!(save[5], 0x3C);
+}
+
+#[test]
+fn test_bandai_153_outer_bank_and_wram() {
+    let cartridge = build_nes2_rom_with_ram(153, 0, 0x70, 32, 1);
+    let mut mapper = new_mapper(&cartridge).unwrap();
+
+    mapper.cpu_write(0x8008, 2);
+    assert_eq!(mapper.cpu_read(0x8000), Some(4));
+    assert_eq!(mapper.cpu_read(0xC000), Some(30));
+    mapper.cpu_write(0x8000, 1);
+    assert_eq!(mapper.cpu_read(0x8000), Some(36));
+    assert_eq!(mapper.cpu_read(0xC000), Some(62));
+
+    assert_eq!(mapper.cpu_read(0x6000), None, "WRAM readable while disabled");
+    mapper.cpu_write(0x800D, 0x20);
+    mapper.cpu_write(0x6000, 0x77);
+    assert_eq!(mapper.cpu_read(0x6000), Some(0x77));
+
+    let save = mapper.save_data().expect("WRAM not battery-backed");
+    assert_eq!(save.len(), 8 * 1024);
+    let mut reloaded = new_mapper(&cartridge).unwrap();
+    reloaded.load_save_data(&save);
+    reloaded.cpu_write(0x800D, 0x20);
+    assert_eq!(reloaded.cpu_read(0x6000), Some(0x77));
+}
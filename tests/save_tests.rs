@@ -0,0 +1,159 @@
+use std::path::PathBuf;
+
+use emurom::save::{SaveRam, SaveWarning};
+
+
+fn temp_file(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("emurom-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn ines_rom(flags_6: u8, prg_ram_byte: u8) -> Vec<u8> {
+    let mut rom = vec![0; 16 + 0x4000 + 0x2000];
+    rom[..4].copy_from_slice(b"NES\x1A");
+    rom[4] = 1;
+    rom[5] = 1;
+    rom[6] = flags_6;
+    rom[8] = prg_ram_byte;
+    rom
+}
+
+fn gb_rom(cartridge_type: u8, ram_code: u8) -> Vec<u8> {
+    const LOGO: [u8; 48] = [
+        0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+        0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+        0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
+    ];
+    let mut rom = vec![0; 0x8000];
+    rom[0x104..0x134].copy_from_slice(&LOGO);
+    rom[0x147] = cartridge_type;
+    rom[0x149] = ram_code;
+    rom[0x14D] = rom[0x134..0x14D].iter().fold(0u8, |acc, &byte| acc.wrapping_sub(byte).wrapping_sub(1));
+    rom
+}
+
+#[test]
+fn test_nes_save_sizes() {
+    let load = |rom: Vec<u8>| emurom::nes::cartridge::Cartridge::load_rom_data(&mut rom.as_slice()).unwrap();
+
+    assert!(load(ines_rom(0x00, 0)).save_ram().is_none(), "no battery");
+    assert_eq!(load(ines_rom(0x02, 0)).save_ram().unwrap().size(), 8 * 1024);
+    assert_eq!(load(ines_rom(0x02, 4)).save_ram().unwrap().size(), 32 * 1024);
+
+    // NES 2.0: 8 KiB PRG-RAM of which 2 KiB is battery-backed
+    let mut rom = ines_rom(0x02, 0);
+    rom[7] = 0x08;
+    rom[10] = 0x57;
+    assert_eq!(load(rom).save_ram().unwrap().size(), 2 * 1024);
+}
+
+#[test]
+fn test_gb_save_sizes() {
+    let load = |rom: Vec<u8>| emurom::gb::cartridge::Cartridge::load_rom_data(&mut rom.as_slice()).unwrap();
+
+    assert!(load(gb_rom(0x02, 3)).save_ram().is_none(), "MBC1 without battery");
+    assert_eq!(load(gb_rom(0x03, 3)).save_ram().unwrap().size(), 32 * 1024);
+    assert_eq!(load(gb_rom(0x06, 0)).save_ram().unwrap().size(), 512, "MBC2 built-in RAM");
+    assert_eq!(load(gb_rom(0x22, 0)).save_ram().unwrap().size(), 256, "MBC7 EEPROM");
+    assert!(load(gb_rom(0x1B, 0)).save_ram().is_none(), "battery without RAM");
+
+    // RTC footers are kept without a warning; anything else is trimmed
+    let mut save = load(gb_rom(0x10, 2)).save_ram().unwrap();
+    assert_eq!(save.load(&vec![0x11; 8 * 1024 + 48]), vec![]);
+    assert_eq!(save.footer().len(), 48);
+    assert_eq!(save.to_bytes().len(), 8 * 1024 + 48);
+    assert_eq!(save.load(&vec![0x11; 8 * 1024 + 44]), vec![]);
+    assert_eq!(
+        save.load(&vec![0x11; 8 * 1024 + 16]),
+        vec![SaveWarning::Truncated { expected: 8 * 1024, found: 8 * 1024 + 16 }]
+    );
+    assert!(save.footer().is_empty());
+
+    let save = load(gb_rom(0x0F, 0)).save_ram().unwrap();
+    assert_eq!(save.size(), 0, "timer-only cartridge keeps just the RTC");
+}
+
+#[test]
+fn test_save_layout_matches_mapper() {
+    use emurom::nes::mapper::new_mapper;
+
+    for mapper in [5, 9, 10, 16, 19, 21, 22, 23, 24, 25, 26, 69, 73, 75, 85, 153, 157, 159] {
+        // NES 2.0 with 8 KiB of PRG-NVRAM
+        let mut rom = ines_rom((mapper << 4) as u8 | 0x02, 0);
+        rom[7] = mapper as u8 & 0xF0 | 0x08;
+        rom[10] = 0x70;
+        let cartridge = emurom::nes::cartridge::Cartridge::load_rom_data(&mut rom.as_slice()).unwrap();
+        let data = new_mapper(&cartridge).unwrap().save_data();
+        let save = cartridge.save_ram();
+        assert_eq!(save.as_ref().map(SaveRam::size), data.as_ref().map(Vec::len), "mapper {}", mapper);
+        match mapper {
+            16 => assert_eq!(save.unwrap().size(), 256, "24C02 EEPROM"),
+            19 => assert_eq!(save.unwrap().size(), 8 * 1024 + 128, "WRAM and internal RAM"),
+            _ => {}
+        }
+    }
+
+    // iNES cannot size MMC5 work RAM, so only the stated PRG-RAM size is battery-backed
+    for (prg_ram_byte, size) in [(0, 8 * 1024), (4, 32 * 1024)] {
+        let rom = ines_rom(0x52, prg_ram_byte);
+        let cartridge = emurom::nes::cartridge::Cartridge::load_rom_data(&mut rom.as_slice()).unwrap();
+        assert_eq!(new_mapper(&cartridge).unwrap().save_data().map(|data| data.len()), Some(size));
+        assert_eq!(cartridge.save_ram().unwrap().size(), size);
+    }
+
+    for cartridge_type in 0..=0xFF {
+        let rom = gb_rom(cartridge_type, 3);
+        let Ok(cartridge) = emurom::gb::cartridge::Cartridge::load_rom_data(&mut rom.as_slice()) else {
+            continue;
+        };
+        let data = emurom::gb::mbc::new_mbc(&cartridge).save_data();
+        let save = cartridge.save_ram();
+        assert_eq!(save.is_some(), data.is_some(), "type {:#04X}", cartridge_type);
+        if let (Some(mut save), Some(data)) = (save, data) {
+            // RTC footers come after the RAM
+            assert_eq!(save.load(&data), vec![], "type {:#04X}", cartridge_type);
+            assert_eq!(save.to_bytes(), data, "type {:#04X}", cartridge_type);
+        }
+    }
+
+    let mbc6 = emurom::gb::cartridge::Cartridge::load_rom_data(&mut gb_rom(0x20, 3).as_slice()).unwrap();
+    assert_eq!(mbc6.save_ram().unwrap().size(), 32 * 1024 + 1024 * 1024, "MBC6 flash");
+}
+
+#[test]
+fn test_save_size_mismatch() {
+    let mut save = SaveRam::new(8);
+    assert_eq!(save.load(&[1, 2, 3]), vec![SaveWarning::Padded { expected: 8, found: 3 }]);
+    assert_eq!(save.ram(), &[1, 2, 3, 0, 0, 0, 0, 0]);
+
+    assert_eq!(save.load(&[9; 12]), vec![SaveWarning::Truncated { expected: 8, found: 12 }]);
+    assert_eq!(save.ram(), &[9; 8]);
+    assert_eq!(save.to_bytes().len(), 8);
+}
+
+#[test]
+fn test_save_file_round_trip() {
+    let path = temp_file("round-trip.sav");
+
+    let mut save = SaveRam::new(16);
+    assert_eq!(save.load_file(&path).unwrap(), vec![], "missing file is a blank save");
+    assert_eq!(save.ram(), &[0; 16]);
+
+    save.ram_mut()[3] = 0x42;
+    save.store_file(&path).unwrap();
+    save.ram_mut()[3] = 0x00;
+    save.store_file(&path).unwrap();
+    save.ram_mut()[5] = 0x24;
+    save.store_file(&path).unwrap();
+
+    let mut temp_name = path.file_name().unwrap().to_os_string();
+    temp_name.push(".tmp");
+    assert!(!path.with_file_name(temp_name).exists(), "temporary file left behind");
+
+    let mut loaded = SaveRam::new(16);
+    assert_eq!(loaded.load_file(&path).unwrap(), vec![]);
+    assert_eq!(loaded, save);
+
+    std::fs::remove_file(&path).unwrap();
+}